@@ -0,0 +1,28 @@
+#![allow(non_snake_case)]
+
+use tat_adr::*;
+
+use std::time::{Instant, Duration};
+use bls12_381::G1Projective;
+
+fn main() {
+    let runs = 1000;
+
+    let G1 = G1Projective::generator();
+    let token = Token::new(rnd_scalar(), (G1 * rnd_scalar()).into(), (G1 * rnd_scalar()).into(), (G1 * rnd_scalar()).into(), &[0u8; 32]);
+
+    for enc in [PointEncoding::Compressed, PointEncoding::Uncompressed].iter() {
+        let bytes = token.to_bytes_with(*enc);
+
+        let mut decode = Duration::from_millis(0);
+        for _ in 0..runs {
+            let init = Instant::now();
+                let res = Token::from_bytes(&bytes);
+            decode += Instant::now() - init;
+            assert!(res.is_ok());
+        }
+
+        let stat = (decode/runs as u32).as_micros() as f64/1000.0;
+        println!("Decode: (encoding: {:?}, size: {}B, time: {:.3}ms)", enc, bytes.len(), stat);
+    }
+}
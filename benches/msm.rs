@@ -0,0 +1,32 @@
+#![allow(non_snake_case)]
+
+use tat_adr::*;
+
+use std::time::{Instant, Duration};
+use bls12_381::{G1Affine, G1Projective};
+
+fn main() {
+    let runs = 10;
+    let n = 1000;
+
+    let G1 = G1Projective::generator();
+    let points = (0..n).map(|_| G1Affine::from(G1 * rnd_scalar())).collect::<Vec<_>>();
+    let scalars = (0..n).map(|_| rnd_scalar()).collect::<Vec<_>>();
+
+    let mut naive = Duration::from_millis(0);
+    let mut pippenger = Duration::from_millis(0);
+    for _ in 0..runs {
+        let init = Instant::now();
+            let expected = points.iter().zip(scalars.iter()).fold(G1Projective::identity(), |acc, (P, s)| acc + P * s);
+        naive += Instant::now() - init;
+
+        let init = Instant::now();
+            let res = msm(&points, &scalars);
+        pippenger += Instant::now() - init;
+        assert!(res == expected);
+    }
+
+    let stat_naive = (naive/runs as u32).as_micros() as f64/1000.0;
+    let stat_pippenger = (pippenger/runs as u32).as_micros() as f64/1000.0;
+    println!("MSM: (terms: {}, naive: {:.3}ms, pippenger: {:.3}ms)", n, stat_naive, stat_pippenger);
+}
@@ -0,0 +1,46 @@
+use std::time::SystemTime;
+
+//-----------------------------------------------------------------------------------------------------------
+// Audit log of issued tokens, records must never contain secrets
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub profile: String,
+    pub time: SystemTime,
+    pub token: [u8; 32]
+}
+
+pub trait AuditLog {
+    fn record(&mut self, record: AuditRecord);
+    fn by_profile(&self, profile: &str) -> Vec<AuditRecord>;
+    fn by_time(&self, from: SystemTime, to: SystemTime) -> Vec<AuditRecord>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryAuditLog {
+    records: Vec<AuditRecord>
+}
+
+impl AuditLog for MemoryAuditLog {
+    fn record(&mut self, record: AuditRecord) {
+        self.records.push(record);
+    }
+
+    fn by_profile(&self, profile: &str) -> Vec<AuditRecord> {
+        self.records.iter().filter(|r| r.profile == profile).cloned().collect()
+    }
+
+    // NOTE: the range is inclusive on both ends
+    fn by_time(&self, from: SystemTime, to: SystemTime) -> Vec<AuditRecord> {
+        self.records.iter().filter(|r| r.time >= from && r.time <= to).cloned().collect()
+    }
+
+    fn len(&self) -> usize {
+        self.records.len()
+    }
+}
@@ -0,0 +1,194 @@
+// NOTE: CBOR encodings as arrays of byte strings, with points and scalars in the canonical compressed forms
+use std::convert::TryFrom;
+
+use crate::crypto::*;
+use crate::tatadr::{Claims, PublicBundle, PublicSetup, Token};
+
+use serde::{de::{self, DeserializeOwned}, Deserialize, Deserializer, Serialize, Serializer};
+use serde_bytes::ByteBuf;
+use bls12_381::{G1Affine, G1Projective, G2Affine};
+
+fn g1(P: &G1Affine) -> ByteBuf {
+    ByteBuf::from(P.to_compressed().to_vec())
+}
+
+fn g2(P: &G2Affine) -> ByteBuf {
+    ByteBuf::from(P.to_compressed().to_vec())
+}
+
+fn to_cbor<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes).expect("CBOR into a Vec doesn't fail");
+    bytes
+}
+
+fn from_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CryptoError> {
+    ciborium::de::from_reader(bytes).map_err(|_| CryptoError::InvalidEncoding)
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Token
+//-----------------------------------------------------------------------------------------------------------
+// (Tk, M, PI, P1, c, p, attrs, holder, scope, nonce, issued_at)
+#[derive(Serialize, Deserialize)]
+struct TokenCbor(ByteBuf, ByteBuf, ByteBuf, ByteBuf, ByteBuf, ByteBuf, Vec<ByteBuf>, Option<ByteBuf>, ByteBuf, ByteBuf, u64);
+
+impl Token {
+    pub fn to_cbor(&self) -> Vec<u8> {
+        to_cbor(&TokenCbor(
+            g1(&self.Tk), g1(&self.M), g1(&self.PI),
+            g1(&self.sig.P1), ByteBuf::from(self.sig.sig.c.to_bytes().to_vec()), ByteBuf::from(self.sig.sig.p.to_bytes().to_vec()),
+            self.claims.attrs.iter().map(|a| ByteBuf::from(a.to_vec())).collect(),
+            self.claims.holder.as_ref().map(g1),
+            ByteBuf::from(self.claims.scope.clone()),
+            ByteBuf::from(self.claims.nonce.to_vec()),
+            self.claims.issued_at
+        ))
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let TokenCbor(Tk, M, PI, P1, c, p, attrs, holder, scope, nonce, issued_at) = from_cbor(bytes)?;
+
+        let attrs = attrs.iter()
+            .map(|a| <[u8; 32]>::try_from(a.as_slice()).map_err(|_| CryptoError::InvalidLength))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let holder = match holder {
+            Some(holder) => Some(decompress_g1_slice(&holder)?),
+            None => None
+        };
+
+        let sig = ExtSignature { P1: decompress_g1_slice(&P1)?, sig: Signature { c: decode_scalar_slice(&c)?, p: decode_scalar_slice(&p)? } };
+        let nonce = <[u8; 32]>::try_from(nonce.as_slice()).map_err(|_| CryptoError::InvalidLength)?;
+        let claims = Claims { attrs, holder, scope: scope.into_vec(), nonce, issued_at };
+        Ok(Token { Tk: decompress_g1_slice(&Tk)?, M: decompress_g1_slice(&M)?, PI: decompress_g1_slice(&PI)?, claims, sig })
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// PublicSetup
+//-----------------------------------------------------------------------------------------------------------
+// (threshold, G1, G2, Y, A1, A2, [(i, Yi)])
+#[derive(Serialize, Deserialize)]
+struct PublicSetupCbor(u32, ByteBuf, ByteBuf, ByteBuf, ByteBuf, ByteBuf, Vec<(u32, ByteBuf)>);
+
+impl PublicSetup {
+    pub fn to_cbor(&self) -> Vec<u8> {
+        to_cbor(&PublicSetupCbor(
+            self.threshold as u32,
+            g1(&self.G1), g2(&self.G2), g1(&self.Y), g1(&self.A1), g2(&self.A2),
+            self.commitments.0.iter().map(|s| (s.i, g1(&s.Yi.into()))).collect()
+        ))
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let PublicSetupCbor(threshold, G1, G2, Y, A1, A2, commitments) = from_cbor(bytes)?;
+
+        let commitments = commitments.iter()
+            .map(|(i, Yi)| Ok(PointShare { i: *i, Yi: G1Projective::from(decompress_g1_slice(Yi)?) }))
+            .collect::<Result<Vec<_>, CryptoError>>()?;
+
+        Ok(PublicSetup {
+            threshold: threshold as usize,
+            G1: decompress_g1_slice(&G1)?, G2: decompress_g2_slice(&G2)?,
+            Y: decompress_g1_slice(&Y)?, A1: decompress_g1_slice(&A1)?, A2: decompress_g2_slice(&A2)?,
+            commitments: PointShareVector(commitments)
+        })
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// PublicBundle
+//-----------------------------------------------------------------------------------------------------------
+// (threshold, parties, G1, G2, Y, A1, A2, [y_commitment], [a_commitment])
+#[derive(Serialize, Deserialize)]
+struct PublicBundleCbor(u32, u32, ByteBuf, ByteBuf, ByteBuf, ByteBuf, ByteBuf, Vec<ByteBuf>, Vec<ByteBuf>);
+
+fn commitment(points: &[ByteBuf]) -> Result<PointPolynomial, CryptoError> {
+    let points = points.iter()
+        .map(|P| Ok(G1Projective::from(decompress_g1_slice(P)?)))
+        .collect::<Result<Vec<_>, CryptoError>>()?;
+
+    Ok(PointPolynomial(points))
+}
+
+impl PublicBundle {
+    pub fn to_cbor(&self) -> Vec<u8> {
+        to_cbor(self)
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, CryptoError> {
+        Self::from_encoded(from_cbor(bytes)?)
+    }
+
+    fn from_encoded(value: PublicBundleCbor) -> Result<Self, CryptoError> {
+        let PublicBundleCbor(threshold, parties, G1, G2, Y, A1, A2, y_commitment, a_commitment) = value;
+        Ok(PublicBundle {
+            threshold: threshold as usize, parties: parties as usize,
+            G1: decompress_g1_slice(&G1)?, G2A: decompress_g2_slice(&G2)?,
+            Y: decompress_g1_slice(&Y)?, A1: decompress_g1_slice(&A1)?, A2A: decompress_g2_slice(&A2)?,
+            y_commitment: commitment(&y_commitment)?, a_commitment: commitment(&a_commitment)?
+        })
+    }
+}
+
+// NOTE: serde through the CBOR layout, so the bundle also embeds in other serde formats
+impl Serialize for PublicBundle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let points = |poly: &PointPolynomial| poly.0.iter().map(|P| g1(&P.into())).collect();
+        PublicBundleCbor(
+            self.threshold as u32, self.parties as u32,
+            g1(&self.G1), g2(&self.G2A), g1(&self.Y), g1(&self.A1), g2(&self.A2A),
+            points(&self.y_commitment), points(&self.a_commitment)
+        ).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicBundle {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = PublicBundleCbor::deserialize(deserializer)?;
+        PublicBundle::from_encoded(value).map_err(|e| de::Error::custom(format!("{:?}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tatadr::NetworkSetup;
+
+    #[test]
+    fn public_setup() {
+        let setup = NetworkSetup::new(2);
+
+        let public = PublicSetup::from_cbor(&setup.public().to_cbor()).unwrap();
+        assert!(public.threshold == 2 && public.Y == G1Affine::from(setup.Y) && public.A2 == setup.A2A);
+        assert!(public.commitments.0.iter().zip(setup.yi.0.iter()).all(|(c, s)| c.i == s.i && c.Yi == setup.G1 * s.yi));
+    }
+
+    #[test]
+    fn public_bundle() {
+        let bundle = NetworkSetup::new(2).export_public_bundle();
+
+        // any serde format
+        let json = serde_json::to_string(&bundle).unwrap();
+        let res: PublicBundle = serde_json::from_str(&json).unwrap();
+        assert!(res.is_consistent() && res.y_commitment == bundle.y_commitment && res.A2A == bundle.A2A);
+
+        let mut encoded = PublicBundleCbor::deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+        encoded.7[1] = ByteBuf::from(vec![0u8; 47]);
+        assert!(PublicBundle::from_cbor(&to_cbor(&encoded)).err() == Some(CryptoError::InvalidLength));
+    }
+
+    #[test]
+    fn invalid_token() {
+        assert!(Token::from_cbor(&[0x80]).err() == Some(CryptoError::InvalidEncoding));
+
+        // a truncated point
+        let G1 = G1Affine::generator();
+        let mut token = TokenCbor(g1(&G1), g1(&G1), g1(&G1), g1(&G1), ByteBuf::from(vec![0u8; 32]), ByteBuf::from(vec![0u8; 32]), vec![], None, ByteBuf::new(), ByteBuf::from(vec![0u8; 32]), 0);
+        assert!(Token::from_cbor(&to_cbor(&token)).is_ok());
+
+        token.0 = ByteBuf::from(vec![0u8; 47]);
+        assert!(Token::from_cbor(&to_cbor(&token)).err() == Some(CryptoError::InvalidLength));
+    }
+}
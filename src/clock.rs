@@ -0,0 +1,27 @@
+use std::time::SystemTime;
+
+//-----------------------------------------------------------------------------------------------------------
+// Wall clock of the network, the reference of the start freshness check
+//-----------------------------------------------------------------------------------------------------------
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// NOTE: a stopped clock, for reproducible runs and tests
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
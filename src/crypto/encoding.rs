@@ -0,0 +1,358 @@
+#![allow(dead_code)]
+
+use std::convert::TryFrom;
+
+use crate::crypto::CryptoError;
+use bls12_381::{Scalar, G1Affine, G2Affine};
+
+//-----------------------------------------------------------------------------------------------------------
+// Point and scalar decoding, all deserialization of points and scalars must go through these
+//-----------------------------------------------------------------------------------------------------------
+pub const G1_COMPRESSED_LEN: usize = 48;
+pub const G1_UNCOMPRESSED_LEN: usize = 96;
+pub const G2_COMPRESSED_LEN: usize = 96;
+pub const SCALAR_LEN: usize = 32;
+
+// NOTE: a base field element (a point coordinate), big-endian
+pub const FP_LEN: usize = 48;
+
+// NOTE: public inputs of signatures and tokens, the identity degenerates the Schnorr and pairing equations. Points
+// decoded here are already in the subgroup, the check covers points built by other means.
+pub fn check_public_g1(P: &G1Affine) -> Result<(), CryptoError> {
+    if bool::from(P.is_identity()) {
+        return Err(CryptoError::IdentityPoint)
+    }
+
+    if !bool::from(P.is_on_curve()) {
+        return Err(CryptoError::InvalidPoint)
+    }
+
+    subgroup_g1(*P).map(|_| ())
+}
+
+// NOTE: subgroup checks of untrusted points. A point on the curve but outside the order r subgroup has a small order
+// component that survives the pairing checks (subgroup confinement). Each check is a scalar multiplication, about the
+// cost of the decompression itself. "skip-subgroup-check" removes them, only for trusted inputs (e.g. local storage).
+fn subgroup_g1(P: G1Affine) -> Result<G1Affine, CryptoError> {
+    match cfg!(feature = "skip-subgroup-check") || bool::from(P.is_torsion_free()) {
+        true => Ok(P),
+        false => Err(CryptoError::NotInSubgroup)
+    }
+}
+
+fn subgroup_g2(P: G2Affine) -> Result<G2Affine, CryptoError> {
+    match cfg!(feature = "skip-subgroup-check") || bool::from(P.is_torsion_free()) {
+        true => Ok(P),
+        false => Err(CryptoError::NotInSubgroup)
+    }
+}
+
+pub fn decompress_g1(bytes: &[u8; G1_COMPRESSED_LEN]) -> Result<G1Affine, CryptoError> {
+    let P = Option::from(G1Affine::from_compressed_unchecked(bytes)).ok_or(CryptoError::InvalidPoint)?;
+    subgroup_g1(P)
+}
+
+pub fn decompress_g2(bytes: &[u8; G2_COMPRESSED_LEN]) -> Result<G2Affine, CryptoError> {
+    let P = Option::from(G2Affine::from_compressed_unchecked(bytes)).ok_or(CryptoError::InvalidPoint)?;
+    subgroup_g2(P)
+}
+
+// NOTE: unchecked decoding doesn't test the curve equation for uncompressed points, unlike decompression
+pub fn decode_uncompressed_g1(bytes: &[u8; G1_UNCOMPRESSED_LEN]) -> Result<G1Affine, CryptoError> {
+    let P: G1Affine = Option::from(G1Affine::from_uncompressed_unchecked(bytes)).ok_or(CryptoError::InvalidPoint)?;
+    if !bool::from(P.is_on_curve()) {
+        return Err(CryptoError::InvalidPoint)
+    }
+
+    subgroup_g1(P)
+}
+
+// NOTE: canonicity guard, only little-endian values below the group order are accepted. Otherwise "s" and "s + q"
+// (when it fits in 32 bytes) would be two encodings of the same scalar, making signatures and messages malleable.
+pub fn decode_scalar(bytes: &[u8; SCALAR_LEN]) -> Result<Scalar, CryptoError> {
+    Option::from(Scalar::from_bytes(bytes)).ok_or(CryptoError::NonCanonicalScalar)
+}
+
+// NOTE: slice variants for external formats, with the length checked
+pub fn decompress_g1_slice(bytes: &[u8]) -> Result<G1Affine, CryptoError> {
+    decompress_g1(<&[u8; G1_COMPRESSED_LEN]>::try_from(bytes).map_err(|_| CryptoError::InvalidLength)?)
+}
+
+pub fn decode_uncompressed_g1_slice(bytes: &[u8]) -> Result<G1Affine, CryptoError> {
+    decode_uncompressed_g1(<&[u8; G1_UNCOMPRESSED_LEN]>::try_from(bytes).map_err(|_| CryptoError::InvalidLength)?)
+}
+
+pub fn decompress_g2_slice(bytes: &[u8]) -> Result<G2Affine, CryptoError> {
+    decompress_g2(<&[u8; G2_COMPRESSED_LEN]>::try_from(bytes).map_err(|_| CryptoError::InvalidLength)?)
+}
+
+pub fn decode_scalar_slice(bytes: &[u8]) -> Result<Scalar, CryptoError> {
+    decode_scalar(<&[u8; SCALAR_LEN]>::try_from(bytes).map_err(|_| CryptoError::InvalidLength)?)
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// PointEncoding
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PointEncoding {
+    Compressed,
+    Uncompressed
+}
+
+impl PointEncoding {
+    pub fn tag(self) -> u8 {
+        match self {
+            PointEncoding::Compressed => 0x00,
+            PointEncoding::Uncompressed => 0x01
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0x00 => Some(PointEncoding::Compressed),
+            0x01 => Some(PointEncoding::Uncompressed),
+            _ => None
+        }
+    }
+
+    pub fn g1_len(self) -> usize {
+        match self {
+            PointEncoding::Compressed => G1_COMPRESSED_LEN,
+            PointEncoding::Uncompressed => G1_UNCOMPRESSED_LEN
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Encoder
+//-----------------------------------------------------------------------------------------------------------
+// NOTE: the first byte is the PointEncoding tag. Uncompressed points are 2x the size but avoid the square-root on decode.
+pub struct Encoder {
+    enc: PointEncoding,
+    bytes: Vec<u8>
+}
+
+impl Encoder {
+    pub fn new(enc: PointEncoding) -> Self {
+        Self { enc, bytes: vec![enc.tag()] }
+    }
+
+    pub fn g1(&mut self, P: &G1Affine) -> &mut Self {
+        match self.enc {
+            PointEncoding::Compressed => self.bytes.extend_from_slice(&P.to_compressed()),
+            PointEncoding::Uncompressed => self.bytes.extend_from_slice(&P.to_uncompressed())
+        }
+
+        self
+    }
+
+    pub fn scalar(&mut self, s: &Scalar) -> &mut Self {
+        self.bytes.extend_from_slice(&s.to_bytes());
+        self
+    }
+
+    pub fn u8(&mut self, v: u8) -> &mut Self {
+        self.bytes.push(v);
+        self
+    }
+
+    pub fn u32(&mut self, v: u32) -> &mut Self {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub fn u64(&mut self, v: u64) -> &mut Self {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub fn bytes32(&mut self, v: &[u8; 32]) -> &mut Self {
+        self.bytes.extend_from_slice(v);
+        self
+    }
+
+    // NOTE: length-prefixed bytes
+    pub fn bytes(&mut self, v: &[u8]) -> &mut Self {
+        self.u32(v.len() as u32);
+        self.bytes.extend_from_slice(v);
+        self
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Decoder
+//-----------------------------------------------------------------------------------------------------------
+pub struct Decoder<'a> {
+    pub enc: PointEncoding,
+    bytes: &'a [u8]
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, CryptoError> {
+        let (tag, bytes) = bytes.split_first().ok_or(CryptoError::InvalidLength)?;
+        let enc = PointEncoding::from_tag(*tag).ok_or(CryptoError::InvalidEncoding)?;
+        Ok(Self { enc, bytes })
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CryptoError> {
+        if self.bytes.len() < n {
+            return Err(CryptoError::InvalidLength)
+        }
+
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub fn g1(&mut self) -> Result<G1Affine, CryptoError> {
+        let data = self.take(self.enc.g1_len())?;
+        match self.enc {
+            PointEncoding::Compressed => decompress_g1_slice(data),
+            PointEncoding::Uncompressed => decode_uncompressed_g1_slice(data)
+        }
+    }
+
+    pub fn scalar(&mut self) -> Result<Scalar, CryptoError> {
+        decode_scalar_slice(self.take(SCALAR_LEN)?)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, CryptoError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, CryptoError> {
+        let mut arr = [0u8; 4];
+        arr.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(arr))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, CryptoError> {
+        let mut arr = [0u8; 8];
+        arr.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(arr))
+    }
+
+    pub fn bytes32(&mut self) -> Result<[u8; 32], CryptoError> {
+        let mut arr = [0u8; 32];
+        arr.copy_from_slice(self.take(32)?);
+        Ok(arr)
+    }
+
+    pub fn bytes(&mut self) -> Result<Vec<u8>, CryptoError> {
+        let n = self.u32()? as usize;
+        Ok(self.take(n)?.to_vec())
+    }
+
+    // NOTE: fails if there are trailing bytes
+    pub fn finish(self) -> Result<(), CryptoError> {
+        if self.bytes.is_empty() { Ok(()) } else { Err(CryptoError::InvalidLength) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls12_381::G1Projective;
+
+    #[test]
+    fn encoding_lengths() {
+        assert!(G1_COMPRESSED_LEN == G1Affine::generator().to_compressed().len());
+        assert!(G1_UNCOMPRESSED_LEN == G1Affine::generator().to_uncompressed().len());
+        assert!(G2_COMPRESSED_LEN == G2Affine::generator().to_compressed().len());
+        assert!(SCALAR_LEN == Scalar::one().to_bytes().len());
+
+        let G1_comp = G1Affine::generator().to_compressed();
+        assert!(decompress_g1_slice(&G1_comp) == Ok(G1Affine::generator()));
+        assert!(decompress_g1_slice(&G1_comp[1..]) == Err(CryptoError::InvalidLength));
+        assert!(decode_uncompressed_g1_slice(&G1_comp) == Err(CryptoError::InvalidLength));
+    }
+
+    #[test]
+    fn invalid_points() {
+        let G1 = G1Affine::generator();
+        assert!(decompress_g1(&G1.to_compressed()) == Ok(G1));
+
+        // x coordinate is not a canonical field element
+        let blob = [0xffu8; G1_COMPRESSED_LEN];
+        assert!(decompress_g1(&blob) == Err(CryptoError::InvalidPoint));
+
+        // compression flag not set
+        let mut blob = G1.to_compressed();
+        blob[0] &= 0x7f;
+        assert!(decompress_g1(&blob) == Err(CryptoError::InvalidPoint));
+
+        let blob = [0xffu8; G2_COMPRESSED_LEN];
+        assert!(decompress_g2(&blob) == Err(CryptoError::InvalidPoint));
+    }
+
+    // NOTE: r*P for a curve point P outside the subgroup, the result has an order dividing the cofactor
+    fn small_order_g1() -> G1Affine {
+        let r = hex_bytes("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001");
+        for x in 1u8.. {
+            let mut bytes = [0u8; G1_COMPRESSED_LEN];
+            bytes[0] = 0x80;
+            bytes[G1_COMPRESSED_LEN - 1] = x;
+
+            if let Some(P) = Option::<G1Affine>::from(G1Affine::from_compressed_unchecked(&bytes)) {
+                let mut Q = G1Projective::identity();
+                for bit in r.iter().flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1 == 1)) {
+                    Q = Q.double();
+                    if bit {
+                        Q += P;
+                    }
+                }
+
+                if !bool::from(Q.is_identity()) {
+                    return Q.into()
+                }
+            }
+        }
+
+        unreachable!()
+    }
+
+    fn hex_bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[cfg(not(feature = "skip-subgroup-check"))]
+    #[test]
+    fn small_order_points() {
+        let Q = small_order_g1();
+        assert!(bool::from(Q.is_on_curve()) && !bool::from(Q.is_torsion_free()));
+
+        assert!(decompress_g1(&Q.to_compressed()) == Err(CryptoError::NotInSubgroup));
+        assert!(decode_uncompressed_g1(&Q.to_uncompressed()) == Err(CryptoError::NotInSubgroup));
+        assert!(check_public_g1(&Q) == Err(CryptoError::NotInSubgroup));
+
+        let mut encoder = Encoder::new(PointEncoding::Compressed);
+        encoder.g1(&Q);
+        assert!(Decoder::new(&encoder.finish()).unwrap().g1() == Err(CryptoError::NotInSubgroup));
+    }
+
+    #[test]
+    fn non_canonical_scalars() {
+        // q - 1 is the largest canonical scalar, q and q + 1 are encodings of 0 and 1
+        let max = (-Scalar::one()).to_bytes();
+        assert!(decode_scalar(&max) == Ok(-Scalar::one()));
+
+        let mut q = max;
+        q[0] += 1;
+        assert!(decode_scalar(&q) == Err(CryptoError::NonCanonicalScalar));
+
+        let mut q1 = max;
+        q1[0] += 2;
+        assert!(decode_scalar(&q1) == Err(CryptoError::NonCanonicalScalar));
+        assert!(decode_scalar_slice(&[0xffu8; 32]) == Err(CryptoError::NonCanonicalScalar));
+
+        let mut encoder = Encoder::new(PointEncoding::Compressed);
+        encoder.scalar(&Scalar::one());
+        let mut bytes = encoder.finish();
+        bytes[1..].copy_from_slice(&q1);
+        assert!(Decoder::new(&bytes).unwrap().scalar() == Err(CryptoError::NonCanonicalScalar));
+    }
+}
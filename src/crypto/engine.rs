@@ -0,0 +1,389 @@
+use crate::crypto::{decompress_g1, decompress_g2, CryptoError, G1_COMPRESSED_LEN, G2_COMPRESSED_LEN};
+use bls12_381::{multi_miller_loop, Scalar, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt};
+
+//-----------------------------------------------------------------------------------------------------------
+// Pairing backend, points cross backends in the canonical compressed encoding. Scalars are always bls12_381 scalars.
+//-----------------------------------------------------------------------------------------------------------
+// NOTE: decompression follows the rules of decompress_g1/decompress_g2, including the "skip-subgroup-check" feature
+pub trait PairingEngine {
+    type G1: Copy;
+    type G2: Copy;
+    type Gt: PartialEq;
+
+    fn g1_identity() -> Self::G1;
+    fn g1_generator() -> Self::G1;
+    fn g1_add(a: &Self::G1, b: &Self::G1) -> Self::G1;
+    fn g1_neg(P: &Self::G1) -> Self::G1;
+    fn g1_mul(P: &Self::G1, s: &Scalar) -> Self::G1;
+    fn g1_compress(P: &Self::G1) -> [u8; G1_COMPRESSED_LEN];
+    fn g1_decompress(bytes: &[u8; G1_COMPRESSED_LEN]) -> Result<Self::G1, CryptoError>;
+
+    fn g1_to_affine(P: &Self::G1) -> Result<G1Affine, CryptoError> {
+        decompress_g1(&Self::g1_compress(P))
+    }
+
+    // NOTE: for points already decoded (and checked) by bls12_381, without a second decompression
+    fn g1_from_affine(P: &G1Affine) -> Self::G1;
+
+    fn g2_generator() -> Self::G2;
+    fn g2_mul(Q: &Self::G2, s: &Scalar) -> Self::G2;
+    fn g2_compress(Q: &Self::G2) -> [u8; G2_COMPRESSED_LEN];
+    fn g2_decompress(bytes: &[u8; G2_COMPRESSED_LEN]) -> Result<Self::G2, CryptoError>;
+
+    // NOTE: product of all pairings with a single final exponentiation
+    fn multi_pairing(terms: &[(Self::G1, Self::G2)]) -> Self::Gt;
+
+    fn pairing(P: &Self::G1, Q: &Self::G2) -> Self::Gt {
+        Self::multi_pairing(&[(*P, *Q)])
+    }
+
+    // NOTE: the product of all pairings is the identity, the empty product
+    fn pairing_check(terms: &[(Self::G1, Self::G2)]) -> bool {
+        Self::multi_pairing(terms) == Self::multi_pairing(&[])
+    }
+
+    fn msm(points: &[Self::G1], scalars: &[Scalar]) -> Self::G1 {
+        points.iter().zip(scalars).fold(Self::g1_identity(), |acc, (P, s)| Self::g1_add(&acc, &Self::g1_mul(P, s)))
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Default backend (bls12_381)
+//-----------------------------------------------------------------------------------------------------------
+pub struct Bls12Engine;
+
+impl PairingEngine for Bls12Engine {
+    type G1 = G1Projective;
+    type G2 = G2Projective;
+    type Gt = Gt;
+
+    fn g1_identity() -> G1Projective {
+        G1Projective::identity()
+    }
+
+    fn g1_generator() -> G1Projective {
+        G1Projective::generator()
+    }
+
+    fn g1_add(a: &G1Projective, b: &G1Projective) -> G1Projective {
+        a + b
+    }
+
+    fn g1_neg(P: &G1Projective) -> G1Projective {
+        -P
+    }
+
+    fn g1_mul(P: &G1Projective, s: &Scalar) -> G1Projective {
+        P * s
+    }
+
+    fn g1_compress(P: &G1Projective) -> [u8; G1_COMPRESSED_LEN] {
+        G1Affine::from(P).to_compressed()
+    }
+
+    fn g1_decompress(bytes: &[u8; G1_COMPRESSED_LEN]) -> Result<G1Projective, CryptoError> {
+        decompress_g1(bytes).map(G1Projective::from)
+    }
+
+    fn g1_to_affine(P: &G1Projective) -> Result<G1Affine, CryptoError> {
+        Ok(P.into())
+    }
+
+    fn g1_from_affine(P: &G1Affine) -> G1Projective {
+        P.into()
+    }
+
+    fn g2_generator() -> G2Projective {
+        G2Projective::generator()
+    }
+
+    fn g2_mul(Q: &G2Projective, s: &Scalar) -> G2Projective {
+        Q * s
+    }
+
+    fn g2_compress(Q: &G2Projective) -> [u8; G2_COMPRESSED_LEN] {
+        G2Affine::from(Q).to_compressed()
+    }
+
+    fn g2_decompress(bytes: &[u8; G2_COMPRESSED_LEN]) -> Result<G2Projective, CryptoError> {
+        decompress_g2(bytes).map(G2Projective::from)
+    }
+
+    fn msm(points: &[G1Projective], scalars: &[Scalar]) -> G1Projective {
+        let mut affine = vec![G1Affine::identity(); points.len()];
+        G1Projective::batch_normalize(points, &mut affine);
+        crate::crypto::msm(&affine, scalars)
+    }
+
+    fn multi_pairing(terms: &[(G1Projective, G2Projective)]) -> Gt {
+        let prepared = terms.iter().map(|(P, Q)| (G1Affine::from(P), G2Prepared::from(G2Affine::from(Q)))).collect::<Vec<_>>();
+        let chain = prepared.iter().map(|(P, Q)| (P, Q)).collect::<Vec<_>>();
+        multi_miller_loop(&chain).final_exponentiation()
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// blst backend
+//-----------------------------------------------------------------------------------------------------------
+#[cfg(feature = "blst")]
+pub use self::blst_engine::*;
+
+#[cfg(feature = "blst")]
+mod blst_engine {
+    use super::{PairingEngine, G1_COMPRESSED_LEN, G2_COMPRESSED_LEN};
+    use crate::crypto::{CryptoError, FP_LEN};
+
+    use bls12_381::{Scalar, G1Affine};
+    use blst::*;
+
+    #[derive(Copy, Clone)]
+    pub struct BlstGt(blst_fp12);
+
+    impl PartialEq for BlstGt {
+        fn eq(&self, other: &Self) -> bool {
+            unsafe { blst_fp12_is_equal(&self.0, &other.0) }
+        }
+    }
+
+    // NOTE: Gt encoding, for caching and transmitting pairing results. The 12 Fp coordinates of the Fp12 element
+    // (c0.c0.c0, c0.c0.c1, ..., c1.c2.c1), FP_LEN bytes big-endian each.
+    pub const GT_LEN: usize = 12 * FP_LEN;
+
+    pub fn gt_to_bytes(x: &BlstGt) -> [u8; GT_LEN] {
+        let mut bytes = [0u8; GT_LEN];
+        let coords = x.0.fp6.iter().flat_map(|fp6| fp6.fp2.iter()).flat_map(|fp2| fp2.fp.iter());
+        for (chunk, fp) in bytes.chunks_mut(FP_LEN).zip(coords) {
+            unsafe { blst_bendian_from_fp(chunk.as_mut_ptr(), fp) };
+        }
+
+        bytes
+    }
+
+    // NOTE: coordinates must be canonical (less than p), checked by encoding back to the same bytes. Elements outside
+    // the order r subgroup are rejected.
+    pub fn gt_from_bytes(bytes: &[u8]) -> Result<BlstGt, CryptoError> {
+        if bytes.len() != GT_LEN {
+            return Err(CryptoError::InvalidLength)
+        }
+
+        let mut x = blst_fp12::default();
+        let coords = x.fp6.iter_mut().flat_map(|fp6| fp6.fp2.iter_mut()).flat_map(|fp2| fp2.fp.iter_mut());
+        for (fp, chunk) in coords.zip(bytes.chunks(FP_LEN)) {
+            unsafe { blst_fp_from_bendian(fp, chunk.as_ptr()) };
+        }
+
+        let x = BlstGt(x);
+        if gt_to_bytes(&x)[..] != bytes[..] {
+            return Err(CryptoError::InvalidEncoding)
+        }
+
+        match cfg!(feature = "skip-subgroup-check") || unsafe { blst_fp12_in_group(&x.0) } {
+            true => Ok(x),
+            false => Err(CryptoError::NotInSubgroup)
+        }
+    }
+
+    pub struct BlstEngine;
+
+    impl PairingEngine for BlstEngine {
+        type G1 = blst_p1;
+        type G2 = blst_p2;
+        type Gt = BlstGt;
+
+        // NOTE: z = 0 is the point at infinity
+        fn g1_identity() -> blst_p1 {
+            blst_p1::default()
+        }
+
+        fn g1_generator() -> blst_p1 {
+            unsafe { *blst_p1_generator() }
+        }
+
+        fn g1_add(a: &blst_p1, b: &blst_p1) -> blst_p1 {
+            let mut out = blst_p1::default();
+            unsafe { blst_p1_add_or_double(&mut out, a, b) };
+            out
+        }
+
+        fn g1_neg(P: &blst_p1) -> blst_p1 {
+            let mut out = *P;
+            unsafe { blst_p1_cneg(&mut out, true) };
+            out
+        }
+
+        fn g1_mul(P: &blst_p1, s: &Scalar) -> blst_p1 {
+            let mut out = blst_p1::default();
+            unsafe { blst_p1_mult(&mut out, P, s.to_bytes().as_ptr(), 255) };
+            out
+        }
+
+        fn g1_compress(P: &blst_p1) -> [u8; G1_COMPRESSED_LEN] {
+            let mut out = [0u8; G1_COMPRESSED_LEN];
+            unsafe { blst_p1_compress(out.as_mut_ptr(), P) };
+            out
+        }
+
+        fn g1_decompress(bytes: &[u8; G1_COMPRESSED_LEN]) -> Result<blst_p1, CryptoError> {
+            let mut aff = blst_p1_affine::default();
+            unsafe {
+                if blst_p1_uncompress(&mut aff, bytes.as_ptr()) != BLST_ERROR::BLST_SUCCESS {
+                    return Err(CryptoError::InvalidPoint)
+                }
+
+                if !cfg!(feature = "skip-subgroup-check") && !blst_p1_affine_in_g1(&aff) {
+                    return Err(CryptoError::NotInSubgroup)
+                }
+
+                let mut out = blst_p1::default();
+                blst_p1_from_affine(&mut out, &aff);
+                Ok(out)
+            }
+        }
+
+        // NOTE: the affine coordinates of the uncompressed encoding (x || y, big-endian), no square root is computed
+        fn g1_from_affine(P: &G1Affine) -> blst_p1 {
+            let mut out = blst_p1::default();
+            if bool::from(P.is_identity()) {
+                return out
+            }
+
+            let bytes = P.to_uncompressed();
+            let (x, y) = bytes.split_at(FP_LEN);
+            let mut aff = blst_p1_affine::default();
+            unsafe {
+                blst_fp_from_bendian(&mut aff.x, x.as_ptr());
+                blst_fp_from_bendian(&mut aff.y, y.as_ptr());
+                blst_p1_from_affine(&mut out, &aff);
+            }
+
+            out
+        }
+
+        fn g2_generator() -> blst_p2 {
+            unsafe { *blst_p2_generator() }
+        }
+
+        fn g2_mul(Q: &blst_p2, s: &Scalar) -> blst_p2 {
+            let mut out = blst_p2::default();
+            unsafe { blst_p2_mult(&mut out, Q, s.to_bytes().as_ptr(), 255) };
+            out
+        }
+
+        fn g2_compress(Q: &blst_p2) -> [u8; G2_COMPRESSED_LEN] {
+            let mut out = [0u8; G2_COMPRESSED_LEN];
+            unsafe { blst_p2_compress(out.as_mut_ptr(), Q) };
+            out
+        }
+
+        fn g2_decompress(bytes: &[u8; G2_COMPRESSED_LEN]) -> Result<blst_p2, CryptoError> {
+            let mut aff = blst_p2_affine::default();
+            unsafe {
+                if blst_p2_uncompress(&mut aff, bytes.as_ptr()) != BLST_ERROR::BLST_SUCCESS {
+                    return Err(CryptoError::InvalidPoint)
+                }
+
+                if !cfg!(feature = "skip-subgroup-check") && !blst_p2_affine_in_g2(&aff) {
+                    return Err(CryptoError::NotInSubgroup)
+                }
+
+                let mut out = blst_p2::default();
+                blst_p2_from_affine(&mut out, &aff);
+                Ok(out)
+            }
+        }
+
+        fn multi_pairing(terms: &[(blst_p1, blst_p2)]) -> BlstGt {
+            let mut acc = unsafe { *blst_fp12_one() };
+            for (P, Q) in terms {
+                let mut P_aff = blst_p1_affine::default();
+                let mut Q_aff = blst_p2_affine::default();
+                let mut ml = blst_fp12::default();
+                unsafe {
+                    blst_p1_to_affine(&mut P_aff, P);
+                    blst_p2_to_affine(&mut Q_aff, Q);
+                    blst_miller_loop(&mut ml, &Q_aff, &P_aff);
+                    blst_fp12_mul(&mut acc, &acc, &ml);
+                }
+            }
+
+            let mut out = blst_fp12::default();
+            unsafe { blst_final_exp(&mut out, &acc) };
+            BlstGt(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnd_scalar;
+
+    fn same_results<E: PairingEngine>() {
+        let s = rnd_scalar();
+        let t = rnd_scalar();
+
+        let P = G1Projective::generator() * s;
+        let Q = G2Projective::generator() * t;
+
+        let P_e = E::g1_mul(&E::g1_generator(), &s);
+        let Q_e = E::g2_mul(&E::g2_generator(), &t);
+        assert!(E::g1_compress(&P_e) == G1Affine::from(P).to_compressed());
+        assert!(E::g2_compress(&Q_e) == G2Affine::from(Q).to_compressed());
+
+        let P_d = E::g1_decompress(&G1Affine::from(P).to_compressed()).unwrap();
+        assert!(E::g1_compress(&P_d) == E::g1_compress(&P_e));
+        assert!(E::g1_to_affine(&P_e) == Ok(G1Affine::from(P)));
+        assert!(E::g1_compress(&E::g1_from_affine(&P.into())) == E::g1_compress(&P_e));
+        assert!(E::g1_compress(&E::g1_from_affine(&G1Affine::identity())) == G1Affine::identity().to_compressed());
+        assert!(E::g1_compress(&E::g1_add(&P_e, &E::g1_neg(&P_e))) == G1Affine::identity().to_compressed());
+        assert!(E::g1_decompress(&[0xff; G1_COMPRESSED_LEN]).is_err());
+
+        // e(G1*s, G2*t) == e(G1*st, G2)
+        let lhs = E::pairing(&P_e, &Q_e);
+        let rhs = E::pairing(&E::g1_mul(&E::g1_generator(), &(s * t)), &E::g2_generator());
+        assert!(lhs == rhs);
+        assert!(E::multi_pairing(&[(P_e, Q_e), (P_e, Q_e)]) == E::pairing(&E::g1_mul(&P_e, &Scalar::from(2)), &Q_e));
+        assert!(E::pairing_check(&[(P_e, Q_e), (E::g1_neg(&P_e), Q_e)]) && !E::pairing_check(&[(P_e, Q_e), (P_e, Q_e)]));
+
+        let scalars = [rnd_scalar(), rnd_scalar()];
+        let res = E::msm(&[P_e, E::g1_generator()], &scalars);
+        assert!(E::g1_compress(&res) == G1Affine::from(P * scalars[0] + G1Projective::generator() * scalars[1]).to_compressed());
+    }
+
+    #[test]
+    fn bls12_engine() {
+        same_results::<Bls12Engine>();
+    }
+
+    #[cfg(feature = "blst")]
+    #[test]
+    fn blst_engine() {
+        same_results::<BlstEngine>();
+    }
+
+    #[cfg(feature = "blst")]
+    #[test]
+    fn gt_encoding() {
+        use crate::crypto::{CryptoError, FP_LEN};
+
+        let x = BlstEngine::pairing(&BlstEngine::g1_mul(&BlstEngine::g1_generator(), &rnd_scalar()), &BlstEngine::g2_generator());
+        let bytes = gt_to_bytes(&x);
+        assert!(gt_from_bytes(&bytes) == Ok(x));
+
+        let one = BlstEngine::multi_pairing(&[]);
+        assert!(gt_from_bytes(&gt_to_bytes(&one)) == Ok(one));
+        assert!(gt_to_bytes(&BlstEngine::multi_pairing(&[(BlstEngine::g1_generator(), BlstEngine::g2_generator())])) != bytes);
+
+        assert!(gt_from_bytes(&bytes[1..]) == Err(CryptoError::InvalidLength));
+        let mut blob = bytes;
+        blob[0..FP_LEN].copy_from_slice(&[0xff; FP_LEN]);
+        assert!(gt_from_bytes(&blob) == Err(CryptoError::InvalidEncoding));
+
+        // a valid Fp12 element, but not in the order r subgroup
+        if !cfg!(feature = "skip-subgroup-check") {
+            let mut blob = bytes;
+            blob[47] ^= 1;
+            assert!(gt_from_bytes(&blob) == Err(CryptoError::NotInSubgroup));
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::crypto::{decompress_g1_slice, decompress_g2_slice, msm, G1_COMPRESSED_LEN, G2_COMPRESSED_LEN};
+
+use core::fmt::Debug;
+use core::ops::{Add, AddAssign, Mul, Neg, Sub};
+use subtle::ConditionallySelectable;
+use bls12_381::{Scalar, G1Affine, G1Projective, G2Affine, G2Projective};
+
+//-----------------------------------------------------------------------------------------------------------
+// Prime order group of BLS12-381, used to share points of G1 and G2 with the same types
+//-----------------------------------------------------------------------------------------------------------
+pub trait Group: Copy + Debug + Eq + ConditionallySelectable + Add<Output = Self> + Sub<Output = Self> + Neg<Output = Self> + Mul<Scalar, Output = Self> + AddAssign {
+    const COMPRESSED_LEN: usize;
+
+    fn identity() -> Self;
+    fn generator() -> Self;
+    fn compress(&self) -> Vec<u8>;
+    fn decompress(bytes: &[u8]) -> Option<Self>;
+
+    // NOTE: sum of points[i] * scalars[i]
+    fn lincomb(points: &[Self], scalars: &[Scalar]) -> Self {
+        points.iter().zip(scalars).fold(Self::identity(), |acc, (P, s)| acc + *P * *s)
+    }
+}
+
+impl Group for G1Projective {
+    const COMPRESSED_LEN: usize = G1_COMPRESSED_LEN;
+
+    fn identity() -> Self {
+        G1Projective::identity()
+    }
+
+    fn generator() -> Self {
+        G1Projective::generator()
+    }
+
+    fn compress(&self) -> Vec<u8> {
+        G1Affine::from(self).to_compressed().to_vec()
+    }
+
+    fn decompress(bytes: &[u8]) -> Option<Self> {
+        decompress_g1_slice(bytes).ok().map(G1Projective::from)
+    }
+
+    fn lincomb(points: &[Self], scalars: &[Scalar]) -> Self {
+        let mut affine = vec![G1Affine::identity(); points.len()];
+        G1Projective::batch_normalize(points, &mut affine);
+        msm(&affine, scalars)
+    }
+}
+
+impl Group for G2Projective {
+    const COMPRESSED_LEN: usize = G2_COMPRESSED_LEN;
+
+    fn identity() -> Self {
+        G2Projective::identity()
+    }
+
+    fn generator() -> Self {
+        G2Projective::generator()
+    }
+
+    fn compress(&self) -> Vec<u8> {
+        G2Affine::from(self).to_compressed().to_vec()
+    }
+
+    fn decompress(bytes: &[u8]) -> Option<Self> {
+        decompress_g2_slice(bytes).ok().map(G2Projective::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::rnd_scalar;
+
+    fn lincomb_and_compression<G: Group>() {
+        let points = (0..5).map(|_| G::generator() * rnd_scalar()).collect::<Vec<_>>();
+        let scalars = (0..5).map(|_| rnd_scalar()).collect::<Vec<_>>();
+
+        let naive = points.iter().zip(scalars.iter()).fold(G::identity(), |acc, (P, s)| acc + *P * *s);
+        assert!(G::lincomb(&points, &scalars) == naive);
+
+        let comp = naive.compress();
+        assert!(comp.len() == G::COMPRESSED_LEN);
+        assert!(G::decompress(&comp) == Some(naive));
+        assert!(G::decompress(&comp[1..]).is_none());
+    }
+
+    #[test]
+    fn groups() {
+        lincomb_and_compression::<G1Projective>();
+        lincomb_and_compression::<G2Projective>();
+    }
+}
@@ -0,0 +1,168 @@
+#![allow(dead_code)]
+
+use crate::crypto::shares::falling_factorial;
+use crate::crypto::{Evaluate, Polynomial, Share, SharesError};
+
+use bls12_381::Scalar;
+
+//-----------------------------------------------------------------------------------------------------------
+// HierarchicalShare
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone)]
+pub struct HierarchicalShare {
+    pub level: usize,
+    pub share: Share
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Hierarchical threshold sharing (Tassa's scheme over Birkhoff interpolation)
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct HierarchicalSharing {
+    pub thresholds: Vec<usize>
+}
+
+impl HierarchicalSharing {
+    // NOTE: thresholds are cumulative. A set is qualified if it has at least thresholds[j] parties from levels 0..=j
+    pub fn new(thresholds: &[usize]) -> Self {
+        if thresholds.is_empty() || thresholds[0] == 0 || thresholds.windows(2).any(|w| w[0] >= w[1]) {
+            panic!("Thresholds must be positive and strictly increasing!");
+        }
+
+        Self { thresholds: thresholds.to_vec() }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.thresholds[self.thresholds.len() - 1] - 1
+    }
+
+    // NOTE: parties at level j hold the derivative of order thresholds[j-1] (level 0 holds the polynomial itself)
+    fn order(&self, level: usize) -> usize {
+        if level == 0 { 0 } else { self.thresholds[level - 1] }
+    }
+
+    // NOTE: parties[j] is the number of parties at level j. Identities are assigned in increasing order of level,
+    // this is required for every qualified set to be non-singular.
+    pub fn shares(&self, secret: Scalar, parties: &[usize]) -> Vec<HierarchicalShare> {
+        if parties.len() != self.thresholds.len() {
+            panic!("Parties must be defined for all levels!");
+        }
+
+        let poly = Polynomial::rnd(secret, self.degree());
+
+        let mut shares = Vec::<HierarchicalShare>::with_capacity(parties.iter().sum());
+        let mut id = 0u32;
+        for (level, n) in parties.iter().enumerate() {
+            let der = poly.derivative(self.order(level));
+            for _ in 0..*n {
+                id += 1;
+                let share = Share { i: id, yi: der.evaluate(Scalar::from(u64::from(id))) };
+                shares.push(HierarchicalShare { level, share });
+            }
+        }
+
+        shares
+    }
+
+    pub fn is_qualified(&self, shares: &[HierarchicalShare]) -> bool {
+        self.is_qualified_levels(&shares.iter().map(|s| s.level).collect::<Vec<_>>())
+    }
+
+    // NOTE: same as is_qualified, with the level of each party only
+    pub fn is_qualified_levels(&self, levels: &[usize]) -> bool {
+        let mut count = 0;
+        for (level, threshold) in self.thresholds.iter().enumerate() {
+            count += levels.iter().filter(|l| **l == level).count();
+            if count < *threshold {
+                return false
+            }
+        }
+
+        true
+    }
+
+    // NOTE: level of the party id, with the identities assigned by shares for the same parties
+    pub fn level_of(parties: &[usize], id: u32) -> Option<usize> {
+        let mut last = 0;
+        for (level, n) in parties.iter().enumerate() {
+            last += *n as u64;
+            if id > 0 && u64::from(id) <= last {
+                return Some(level)
+            }
+        }
+
+        None
+    }
+
+    pub fn reconstruct(&self, shares: &[HierarchicalShare]) -> Result<Scalar, SharesError> {
+        if !self.is_qualified(shares) {
+            return Err(SharesError::Unqualified)
+        }
+
+        // Birkhoff matrix, the row for (x, d) has n!/(n-d)! * x^(n-d) at column n >= d and the share value at the end
+        let k = self.degree() + 1;
+        let mut rows = shares.iter().map(|s| {
+            let d = self.order(s.level);
+            let x = Scalar::from(u64::from(s.share.i));
+
+            let mut row = vec![Scalar::zero(); k + 1];
+            let mut xp = Scalar::one();
+            for (n, item) in row.iter_mut().enumerate().take(k).skip(d) {
+                *item = falling_factorial(n, d) * xp;
+                xp *= x;
+            }
+
+            row[k] = s.share.yi;
+            row
+        }).collect::<Vec<_>>();
+
+        // Gauss-Jordan elimination, the secret is the solved coefficient a_0
+        for col in 0..k {
+            let pivot = (col..rows.len()).find(|r| rows[*r][col] != Scalar::zero()).ok_or(SharesError::Singular)?;
+            rows.swap(col, pivot);
+
+            let inv = rows[col][col].invert().unwrap();
+            let pivot_row = rows[col].iter().map(|v| v * inv).collect::<Vec<_>>();
+            for (r, row) in rows.iter_mut().enumerate() {
+                let f = row[col];
+                if r != col && f != Scalar::zero() {
+                    for (v, p) in row.iter_mut().zip(&pivot_row) {
+                        *v -= f * p;
+                    }
+                }
+            }
+
+            rows[col] = pivot_row;
+        }
+
+        Ok(rows[0][k])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rnd_scalar;
+
+    #[test]
+    fn two_levels() {
+        // at least 1 admin (level 0) and at least 3 parties in total (admins or operators)
+        let hs = HierarchicalSharing::new(&[1, 3]);
+
+        let s = rnd_scalar();
+        let shares = hs.shares(s, &[2, 4]);
+        let (admins, operators) = shares.split_at(2);
+
+        let authorized = [admins[0], operators[0], operators[1]];
+        assert!(hs.reconstruct(&authorized) == Ok(s));
+
+        let authorized = [admins[1], operators[2], operators[3]];
+        assert!(hs.reconstruct(&authorized) == Ok(s));
+
+        let no_admin = [operators[0], operators[1], operators[2]];
+        assert!(hs.reconstruct(&no_admin) == Err(SharesError::Unqualified));
+
+        let too_few = [admins[0], operators[0]];
+        assert!(hs.reconstruct(&too_few) == Err(SharesError::Unqualified));
+    }
+}
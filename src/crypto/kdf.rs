@@ -0,0 +1,93 @@
+use rand::{Error, RngCore};
+use sha2::{Sha512, Digest};
+
+#[cfg(not(feature = "no-zeroize"))]
+use clear_on_drop::clear::Clear;
+
+//-----------------------------------------------------------------------------------------------------------
+// SeedKdf
+//-----------------------------------------------------------------------------------------------------------
+// NOTE: deterministic byte stream of a master seed, block j = SHA-512("tat-adr-kdf" || seed || j) with j as u64 LE.
+// SHA-512 is fixed, independently of the "blake2b" feature. Each rnd_scalar_with draw consumes exactly one block, so
+// the n-th drawn scalar is Scalar::from_bytes_wide(block n).
+pub struct SeedKdf {
+    seed: [u8; 32],
+    counter: u64,
+    block: [u8; 64],
+    pos: usize
+}
+
+impl SeedKdf {
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self { seed, counter: 0, block: [0u8; 64], pos: 64 }
+    }
+
+    fn next_block(&mut self) {
+        let hasher = Sha512::new()
+            .chain(b"tat-adr-kdf")
+            .chain(self.seed)
+            .chain(self.counter.to_le_bytes());
+
+        self.block.copy_from_slice(&hasher.result()[0..64]);
+        self.counter += 1;
+        self.pos = 0;
+    }
+}
+
+// NOTE: the seed and the current block are cleared on drop, unless the (insecure) "no-zeroize" feature is enabled
+#[cfg(not(feature = "no-zeroize"))]
+impl Drop for SeedKdf {
+    fn drop(&mut self) {
+        self.seed.clear();
+        self.block.clear();
+    }
+}
+
+impl RngCore for SeedKdf {
+    fn next_u32(&mut self) -> u32 {
+        let mut arr = [0u8; 4];
+        self.fill_bytes(&mut arr);
+        u32::from_le_bytes(arr)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut arr = [0u8; 8];
+        self.fill_bytes(&mut arr);
+        u64::from_le_bytes(arr)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            if self.pos == 64 {
+                self.next_block();
+            }
+
+            *byte = self.block[self.pos];
+            self.pos += 1;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::rnd_scalar_with;
+    use bls12_381::Scalar;
+
+    #[test]
+    fn documented_blocks() {
+        let seed = [3u8; 32];
+        let mut kdf = SeedKdf::new(seed);
+
+        for j in 0..3u64 {
+            let mut block = [0u8; 64];
+            block.copy_from_slice(&Sha512::new().chain(b"tat-adr-kdf").chain(seed).chain(j.to_le_bytes()).result()[0..64]);
+            assert!(rnd_scalar_with(&mut kdf) == Scalar::from_bytes_wide(&block));
+        }
+    }
+}
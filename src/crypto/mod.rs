@@ -1,22 +1,143 @@
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, RngCore};
 use bls12_381::{multi_miller_loop, Scalar, G1Affine, G2Prepared, Gt};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
 mod macros;
-pub use crate::crypto::macros::*;
+
+mod engine;
+pub use crate::crypto::engine::*;
+
+mod msm;
+pub use crate::crypto::msm::*;
+
+mod group;
+pub use crate::crypto::group::*;
+
+mod encoding;
+pub use crate::crypto::encoding::*;
 
 mod shares;
 pub use crate::crypto::shares::*;
 
+mod storage;
+pub use crate::crypto::storage::*;
+
+mod hierarchical;
+pub use crate::crypto::hierarchical::*;
+
 mod signatures;
 pub use crate::crypto::signatures::*;
 
+mod vrf;
+pub use crate::crypto::vrf::*;
+
+mod kdf;
+pub use crate::crypto::kdf::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CryptoError {
+    InvalidEncoding,
+    InvalidLength,
+    InvalidPoint,
+    IdentityPoint,
+    NotInSubgroup,
+    NonCanonicalScalar,
+    UnsupportedVersion
+}
+
 pub fn rnd_scalar() -> Scalar {
+    rnd_scalar_with(&mut thread_rng())
+}
+
+// NOTE: a seeded rng gives reproducible scalars
+pub fn rnd_scalar_with<R: RngCore + ?Sized>(rng: &mut R) -> Scalar {
     let mut arr = [0u8; 64];
-    thread_rng().fill(&mut arr);
+    rng.fill(&mut arr);
     Scalar::from_bytes_wide(&arr)
 }
 
+// NOTE: Montgomery's trick, n inversions with 1 inversion and 3(n-1) multiplications. Zeros are skipped and left as
+// zero, the result is false if there was any zero (e.g. a Lagrange denominator of repeated indices).
+pub fn batch_invert(scalars: &mut [Scalar]) -> Choice {
+    let mut nonzero = Choice::from(1);
+    let mut acc = Scalar::one();
+    let mut prefix = Vec::with_capacity(scalars.len());
+    for s in scalars.iter() {
+        prefix.push(acc);
+        let zero = ct_is_zero(s);
+        acc = Scalar::conditional_select(&(acc * s), &acc, zero);
+        nonzero &= !zero;
+    }
+
+    let mut inv = acc.invert().unwrap();
+    for (s, p) in scalars.iter_mut().zip(prefix).rev() {
+        let zero = ct_is_zero(s);
+        let next = Scalar::conditional_select(&(inv * *s), &inv, zero);
+        *s = Scalar::conditional_select(&(inv * p), &*s, zero);
+        inv = next;
+    }
+
+    nonzero
+}
+
+// NOTE: constant-time zero check, e.g. of a denominator before its inversion
+pub fn ct_is_zero(s: &Scalar) -> Choice {
+    s.ct_eq(&Scalar::zero())
+}
+
 pub fn multi_pairing(points: &[G1Affine], base: &G2Prepared) -> Gt {
     let chain: Vec<(&G1Affine, &G2Prepared)> = points.iter().map(|p| (p, base)).collect::<Vec<_>>();
     multi_miller_loop(&chain).final_exponentiation()
-}
\ No newline at end of file
+}
+
+// NOTE: checks that the product of all pairings is the identity, with a single final exponentiation
+pub fn pairing_check(terms: &[(&G1Affine, &G2Prepared)]) -> bool {
+    multi_miller_loop(terms).final_exponentiation() == Gt::identity()
+}
+
+// NOTE: e(a1, a2) == e(b1, b2), as e(a1, a2) * e(-b1, b2) == 1 with a single final exponentiation
+pub fn pairing_eq(a1: &G1Affine, a2: &G2Prepared, b1: &G1Affine, b2: &G2Prepared) -> bool {
+    pairing_check(&[(a1, a2), (&-b1, b2)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_inversion() {
+        let mut scalars = (0..10).map(|_| rnd_scalar()).collect::<Vec<_>>();
+        scalars[3] = Scalar::zero();
+        let expected = scalars.iter().map(|s| if *s == Scalar::zero() { *s } else { s.invert().unwrap() }).collect::<Vec<_>>();
+
+        assert!(!bool::from(batch_invert(&mut scalars)));
+        assert!(scalars == expected);
+
+        let mut scalars = (0..10).map(|_| rnd_scalar()).collect::<Vec<_>>();
+        let expected = scalars.iter().map(|s| s.invert().unwrap()).collect::<Vec<_>>();
+        assert!(bool::from(batch_invert(&mut scalars)));
+        assert!(scalars == expected);
+
+        let mut empty: [Scalar; 0] = [];
+        assert!(bool::from(batch_invert(&mut empty)));
+    }
+
+    #[test]
+    fn pairing_equality() {
+        use bls12_381::{pairing, G2Affine};
+
+        let (x, y) = (rnd_scalar(), rnd_scalar());
+        let (G1, G2) = (G1Affine::generator(), G2Affine::generator());
+        let (xG1, yG2) = (G1Affine::from(G1 * x), G2Affine::from(G2 * y));
+        let (G2P, yG2P) = (G2Prepared::from(G2), G2Prepared::from(yG2));
+
+        // e(x*G1, y*G2) == e(xy*G1, G2)
+        let xyG1 = G1Affine::from(G1 * (x * y));
+        assert!(pairing(&xG1, &yG2) == pairing(&xyG1, &G2));
+        assert!(pairing_eq(&xG1, &yG2P, &xyG1, &G2P));
+
+        let other = G1Affine::from(G1 * rnd_scalar());
+        assert!(pairing(&xG1, &yG2) != pairing(&other, &G2));
+        assert!(!pairing_eq(&xG1, &yG2P, &other, &G2P));
+    }
+}
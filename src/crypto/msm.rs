@@ -0,0 +1,79 @@
+use bls12_381::{Scalar, G1Affine, G1Projective};
+
+// NOTE: c bits of the little-endian scalar, starting at bit "start"
+fn window(bytes: &[u8; 32], start: usize, c: usize) -> usize {
+    let mut digit = 0usize;
+    for k in 0..c {
+        let bit = start + k;
+        if bit >= 256 {
+            break
+        }
+
+        digit |= (((bytes[bit / 8] >> (bit % 8)) & 1) as usize) << k;
+    }
+
+    digit
+}
+
+// NOTE: window size in bits, grows with ln(n) so the 2^c buckets stay below the number of points
+fn window_size(n: usize) -> usize {
+    if n < 32 { 3 } else { (n as f64).ln().ceil() as usize }
+}
+
+// NOTE: Pippenger's bucket method. For each c-bit window, points are added into the bucket of their digit and the
+// buckets are summed with a running sum (sum_j j * B_j). Windows are combined with c doublings.
+pub fn msm(points: &[G1Affine], scalars: &[Scalar]) -> G1Projective {
+    assert!(points.len() == scalars.len(), "msm requires the same number of points and scalars!");
+
+    let c = window_size(points.len());
+    let bytes = scalars.iter().map(|s| s.to_bytes()).collect::<Vec<_>>();
+
+    let mut acc = G1Projective::identity();
+    for w in (0..255usize.div_ceil(c)).rev() {
+        for _ in 0..c {
+            acc = acc.double();
+        }
+
+        let mut buckets = vec![G1Projective::identity(); (1 << c) - 1];
+        for (P, s) in points.iter().zip(bytes.iter()) {
+            let digit = window(s, w * c, c);
+            if digit != 0 {
+                buckets[digit - 1] += P;
+            }
+        }
+
+        let mut running = G1Projective::identity();
+        let mut sum = G1Projective::identity();
+        for bucket in buckets.iter().rev() {
+            running += bucket;
+            sum += running;
+        }
+
+        acc += sum;
+    }
+
+    acc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::rnd_scalar;
+
+    #[test]
+    fn naive_sum() {
+        let G1 = G1Projective::generator();
+
+        for n in [0, 1, 5, 64].iter() {
+            let points = (0..*n).map(|_| G1Affine::from(G1 * rnd_scalar())).collect::<Vec<_>>();
+            let mut scalars = (0..*n).map(|_| rnd_scalar()).collect::<Vec<_>>();
+            if *n > 1 {
+                scalars[0] = Scalar::zero();
+                scalars[1] = -Scalar::one();
+            }
+
+            let naive = points.iter().zip(scalars.iter()).fold(G1Projective::identity(), |acc, (P, s)| acc + P * s);
+            assert!(msm(&points, &scalars) == naive);
+        }
+    }
+}
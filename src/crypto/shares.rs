@@ -1,20 +1,62 @@
 #![allow(dead_code)]
 
-use crate::crypto::{rnd_scalar};
+use crate::crypto::{batch_invert, ct_is_zero, rnd_scalar_with, CryptoError, Decoder, Encoder, EqProof, Group, HierarchicalSharing, PointEncoding};
 
+use std::collections::HashMap;
+use rand::{thread_rng, RngCore};
+#[cfg(not(feature = "no-zeroize"))]
 use clear_on_drop::clear::Clear;
-use core::ops::{Add, Mul, Sub};
-use bls12_381::{Scalar, G1Projective};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use core::ops::{Add, Mul, Neg, Sub};
+use core::iter::Sum;
+use bls12_381::{Scalar, G1Affine, G1Projective, G2Projective};
 
 //-----------------------------------------------------------------------------------------------------------
 // Shared traits and functions for Polynomial and PointPolynomial
 //-----------------------------------------------------------------------------------------------------------
-fn cut_tail<Z>(v: &mut Vec::<Z>, elm: Z) where Z: Eq {
-    if let Some(i) = v.iter().rev().rposition(|x| *x == elm) {
-        v.truncate(i);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SharesError {
+    Unqualified,
+    Singular,
+    MissingShare(u32),
+    Inconsistent(u32),
+    DuplicateIndex(u32),
+    LengthMismatch(usize, usize),
+    IndexMismatch(u32, u32),
+    UnexpectedValue
+}
+
+// NOTE: qualified sets of party indices. Weighted shares use Threshold, each evaluation point counts as a party.
+// Hierarchical has the thresholds and the number of parties per level (see HierarchicalSharing::shares).
+#[derive(Debug, Clone)]
+pub enum AccessStructure {
+    Threshold(usize),
+    Hierarchical(HierarchicalSharing, Vec<usize>)
+}
+
+impl AccessStructure {
+    // NOTE: repeated or zero indices are never qualified, the interpolation would fail
+    pub fn is_qualified(&self, indices: &[u32]) -> bool {
+        if indices.iter().enumerate().any(|(k, i)| *i == 0 || indices[..k].contains(i)) {
+            return false
+        }
+
+        match self {
+            AccessStructure::Threshold(t) => indices.len() > *t,
+            AccessStructure::Hierarchical(sharing, parties) => {
+                let levels = indices.iter().map(|i| HierarchicalSharing::level_of(parties, *i)).collect::<Option<Vec<_>>>();
+                levels.is_some_and(|levels| sharing.is_qualified_levels(&levels))
+            }
+        }
     }
 }
 
+// NOTE: removes the trailing elm values only, e.g. the zero coefficients above the degree
+fn cut_tail<Z>(v: &mut Vec::<Z>, elm: Z) where Z: Eq {
+    let len = v.iter().rposition(|x| *x != elm).map_or(0, |i| i + 1);
+    v.truncate(len);
+}
+
 fn short_mul(a: &mut Vec::<Scalar>, b: Scalar) {
     let mut prev = a[0];
     a[0] *= b;
@@ -26,19 +68,103 @@ fn short_mul(a: &mut Vec::<Scalar>, b: Scalar) {
     a.push(Scalar::one());
 }
 
-fn lx_num_bar(range: &[Scalar], i: usize) -> (Vec<Scalar>, Scalar) {
-    let mut num = vec![Scalar::one()];
-    let mut denum = Scalar::one();
-    for j in 0..range.len() {
-        if j != i {
-            short_mul(&mut num, -range[j]);
-            denum *= range[i] - range[j];
+pub(crate) fn falling_factorial(n: usize, k: usize) -> Scalar {
+    // n!/(n-k)!
+    ((n - k + 1)..=n).fold(Scalar::one(), |acc, m| acc * Scalar::from(m as u64))
+}
+
+// NOTE: the party index of a range point, ranges are built from the share indices
+fn range_index(x: &Scalar) -> u32 {
+    let bytes = x.to_bytes();
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+// NOTE: inverts the Lagrange denominators of the range, a zero denominator is a repeated point
+fn invert_denominators(range: &[Scalar], denums: &mut [Scalar]) -> Result<(), SharesError> {
+    if bool::from(batch_invert(denums)) {
+        return Ok(())
+    }
+
+    let pos = denums.iter().position(|d| bool::from(ct_is_zero(d))).unwrap();
+    Err(SharesError::DuplicateIndex(range_index(&range[pos])))
+}
+
+// NOTE: numerator polynomials and barycentric weights for all points in the range, with a single inversion
+fn lx_num_bar(range: &[Scalar]) -> Result<Vec<(Vec<Scalar>, Scalar)>, SharesError> {
+    let mut nums = Vec::with_capacity(range.len());
+    let mut denums = vec![Scalar::one(); range.len()];
+    for i in 0..range.len() {
+        let mut num = vec![Scalar::one()];
+        for j in 0..range.len() {
+            if j != i {
+                short_mul(&mut num, -range[j]);
+                denums[i] *= range[i] - range[j];
+            }
         }
+
+        nums.push(num);
     }
 
-    (num, denum.invert().unwrap())
+    invert_denominators(range, &mut denums)?;
+    Ok(nums.into_iter().zip(denums).collect())
+}
+
+// NOTE: constant-time selection of the share with index i. All shares are visited and no branch depends on the
+// indices, only the final "found" is revealed.
+pub fn ct_select<S: ConditionallySelectable>(shares: &[S], i: u32, index: fn(&S) -> u32) -> Option<S> {
+    let (first, rest) = shares.split_first()?;
+
+    let mut found = index(first).ct_eq(&i);
+    let mut selected = *first;
+    for share in rest.iter() {
+        let this = index(share).ct_eq(&i);
+        selected.conditional_assign(share, this);
+        found |= this;
+    }
+
+    match bool::from(found) {
+        true => Some(selected),
+        false => None
+    }
 }
 
+// NOTE: the shares of the requested parties, in the requested order
+fn select_shares<S: ConditionallySelectable>(shares: &[S], indices: &[u32], index: fn(&S) -> u32) -> Result<Vec<S>, SharesError> {
+    let mut selected = Vec::<S>::with_capacity(indices.len());
+    for (n, i) in indices.iter().enumerate() {
+        if indices[..n].contains(i) {
+            return Err(SharesError::DuplicateIndex(*i))
+        }
+
+        selected.push(ct_select(shares, *i, index).ok_or(SharesError::MissingShare(*i))?);
+    }
+
+    Ok(selected)
+}
+
+// NOTE: interpolation over borrowed slices, no ShareVector is allocated (or zeroized on drop). Repeated indices are
+// reported as DuplicateIndex.
+pub fn interpolate_shares(shares: &[Share]) -> Result<Scalar, SharesError> {
+    let range = shares.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
+
+    let mut acc = Scalar::zero();
+    for (item, l) in shares.iter().zip(Polynomial::l_all(&range)?) {
+        acc += l * item.yi;
+    }
+
+    Ok(acc)
+}
+
+pub fn interpolate_points<G: Group>(shares: &[PointShare<G>]) -> Result<G, SharesError> {
+    let range = shares.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
+    let points = shares.iter().map(|s| s.Yi).collect::<Vec<_>>();
+
+    Ok(G::lincomb(&points, &Polynomial::l_all(&range)?))
+}
+
+// NOTE: the unchecked interpolation traits have no error, repeated indices are a caller bug
+const DISTINCT_INDICES: &str = "Shares must have distinct indices!";
+
 pub trait Interpolate {
     type Output;
     fn interpolate(&self) -> Self::Output;
@@ -115,52 +241,62 @@ impl<'a, 'b> Mul<&'b Scalar> for &'a Share {
     }
 }
 
-define_mul_variants!(LHS = Share, RHS = G1Projective, Output = PointShare);
-define_mul_variants!(LHS = G1Projective, RHS = Share, Output = PointShare);
-define_comut_mul!(LHS = G1Projective, RHS = Share, Output = PointShare);
-impl<'a, 'b> Mul<&'b G1Projective> for &'a Share {
-    type Output = PointShare;
-    fn mul(self, rhs: &'b G1Projective) -> PointShare {
-        PointShare { i: self.i, Yi: rhs * self.yi }
+impl ConditionallySelectable for Share {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Share { i: u32::conditional_select(&a.i, &b.i, choice), yi: Scalar::conditional_select(&a.yi, &b.yi, choice) }
     }
 }
 
 //-----------------------------------------------------------------------------------------------------------
 // PointShare
 //-----------------------------------------------------------------------------------------------------------
+// NOTE: G1 is the default group, PointShare is PointShare<G1Projective>
 #[derive(Debug, Copy, Clone)]
-pub struct PointShare {
+pub struct PointShare<G = G1Projective> {
     pub i: u32,
-    pub Yi: G1Projective
+    pub Yi: G
 }
 
-define_add_variants!(LHS = PointShare, RHS = G1Projective, Output = PointShare);
-define_add_variants!(LHS = G1Projective, RHS = PointShare, Output = PointShare);
-define_comut_add!(LHS = G1Projective, RHS = PointShare, Output = PointShare);
-impl<'a, 'b> Add<&'b G1Projective> for &'a PointShare {
-    type Output = PointShare;
-    fn add(self, rhs: &'b G1Projective) -> PointShare {
-        PointShare { i: self.i, Yi: self.Yi + rhs }
+pub type G1PointShare = PointShare<G1Projective>;
+pub type G2PointShare = PointShare<G2Projective>;
+
+impl<G: Group> ConditionallySelectable for PointShare<G> {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        PointShare { i: u32::conditional_select(&a.i, &b.i, choice), Yi: G::conditional_select(&a.Yi, &b.Yi, choice) }
     }
 }
 
-define_sub_variants!(LHS = PointShare, RHS = G1Projective, Output = PointShare);
-define_sub_variants!(LHS = G1Projective, RHS = PointShare, Output = PointShare);
-define_comut_sub!(LHS = G1Projective, RHS = PointShare, Output = PointShare);
-impl<'a, 'b> Sub<&'b G1Projective> for &'a PointShare {
-    type Output = PointShare;
-    fn sub(self, rhs: &'b G1Projective) -> PointShare {
-        PointShare { i: self.i, Yi: self.Yi - rhs }
+// NOTE: proofs and encoding are only defined for G1
+impl PointShare {
+    // NOTE: proves that Yi = base * yi for the share committed as Ci = G1 * yi
+    pub fn prove(share: &Share, G1: &G1Projective, base: &G1Projective) -> (PointShare, EqProof) {
+        let point = share * base;
+        let Ci = G1 * share.yi;
+        let proof = EqProof::prove(&share.yi, &G1.into(), &Ci.into(), &base.into(), &point.Yi.into());
+        (point, proof)
+    }
+
+    pub fn verify_proof(&self, proof: &EqProof, G1: &G1Projective, Ci: &G1Projective, base: &G1Projective) -> bool {
+        proof.verify(&G1.into(), &Ci.into(), &base.into(), &G1Affine::from(self.Yi))
     }
-}
 
-define_mul_variants!(LHS = PointShare, RHS = Scalar, Output = PointShare);
-define_mul_variants!(LHS = Scalar, RHS = PointShare, Output = PointShare);
-define_comut_mul!(LHS = Scalar, RHS = PointShare, Output = PointShare);
-impl<'a, 'b> Mul<&'b Scalar> for &'a PointShare {
-    type Output = PointShare;
-    fn mul(self, rhs: &'b Scalar) -> PointShare {
-        PointShare { i: self.i, Yi: self.Yi * rhs }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(PointEncoding::Compressed)
+    }
+
+    pub fn to_bytes_with(&self, enc: PointEncoding) -> Vec<u8> {
+        let mut encoder = Encoder::new(enc);
+        encoder.u32(self.i).g1(&self.Yi.into());
+        encoder.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let mut decoder = Decoder::new(bytes)?;
+        let i = decoder.u32()?;
+        let Yi = decoder.g1()?.into();
+        decoder.finish()?;
+
+        Ok(Self { i, Yi })
     }
 }
 
@@ -170,6 +306,8 @@ impl<'a, 'b> Mul<&'b Scalar> for &'a PointShare {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Polynomial(pub Vec<Scalar>);
 
+// NOTE: secret coefficients are cleared on drop, unless the (insecure) "no-zeroize" feature is enabled
+#[cfg(not(feature = "no-zeroize"))]
 impl Drop for Polynomial {
     fn drop(&mut self) {
         for item in self.0.iter_mut() {
@@ -182,6 +320,10 @@ define_add_variants!(LHS = Polynomial, RHS = Polynomial, Output = Polynomial);
 impl<'a, 'b> Add<&'b Polynomial> for &'a Polynomial {
     type Output = Polynomial;
     fn add(self, rhs: &'b Polynomial) -> Polynomial {
+        if self.0.len() != rhs.0.len() {
+            panic!("Polynomial must be of the same degree!");
+        }
+
         Polynomial(self.0.iter().zip(&rhs.0).map(|(a1, a2)| a1 + a2).collect::<Vec<_>>())
     }
 }
@@ -196,59 +338,106 @@ impl<'a, 'b> Mul<&'b Scalar> for &'a Polynomial {
     }
 }
 
-define_mul_variants!(LHS = Polynomial, RHS = G1Projective, Output = PointPolynomial);
-define_mul_variants!(LHS = G1Projective, RHS = Polynomial, Output = PointPolynomial);
-define_comut_mul!(LHS = G1Projective, RHS = Polynomial, Output = PointPolynomial);
-impl<'a, 'b> Mul<&'b G1Projective> for &'a Polynomial {
-    type Output = PointPolynomial;
-    fn mul(self, rhs: &'b G1Projective) -> PointPolynomial {
-        PointPolynomial(self.0.iter().map(|ak| rhs * ak).collect::<Vec<_>>())
-    }
-}
-
 impl Polynomial {
     pub fn rnd(secret: Scalar, degree: usize) -> Self {
+        Self::rnd_with(secret, degree, &mut thread_rng())
+    }
+
+    pub fn rnd_with<R: RngCore + ?Sized>(secret: Scalar, degree: usize, rng: &mut R) -> Self {
         let mut coefs = vec![secret];
 
-        let rnd_coefs: Vec<Scalar> = (0..degree).map(|_| rnd_scalar()).collect();
+        let rnd_coefs: Vec<Scalar> = (0..degree).map(|_| rnd_scalar_with(rng)).collect();
         coefs.extend(rnd_coefs);
 
         Polynomial(coefs)
     }
 
-    pub fn l_i(range: &[Scalar], i: usize) -> Scalar {
+    // NOTE: Lagrange coefficient of the point range[i] evaluated at x = 0, l_i(0) = prod_(j != i) x_j / (x_j - x_i).
+    // The secret is sum(l_i * y_i) over the shares at the range points. Repeated points are reported as DuplicateIndex.
+    pub fn l_i(range: &[Scalar], i: usize) -> Result<Scalar, SharesError> {
+        Self::l_i_at(range, i, Scalar::zero())
+    }
+
+    // NOTE: Lagrange coefficient of the point range[i] evaluated at x, l_i(x) = prod_(j != i) (x - x_j) / (x_i - x_j).
+    // Then f(x) = sum(l_i(x) * y_i) for a polynomial f of degree < range.len().
+    pub fn l_i_at(range: &[Scalar], i: usize, x: Scalar) -> Result<Scalar, SharesError> {
+        for (n, xn) in range.iter().enumerate() {
+            if range[..n].iter().any(|xm| bool::from(ct_is_zero(&(xn - xm)))) {
+                return Err(SharesError::DuplicateIndex(range_index(xn)))
+            }
+        }
+
         let mut num = Scalar::one();
         let mut denum = Scalar::one();
         for j in 0..range.len() {
             if j != i {
-                num *= range[j];
-                denum *= range[j] - range[i];
+                num *= x - range[j];
+                denum *= range[i] - range[j];
             }
         }
 
-        num * denum.invert().unwrap()
+        Ok(num * denum.invert().unwrap())
+    }
+
+    // NOTE: same as l_i for all points in the range, with a single inversion
+    pub fn l_all(range: &[Scalar]) -> Result<Vec<Scalar>, SharesError> {
+        let mut nums = vec![Scalar::one(); range.len()];
+        let mut denums = vec![Scalar::one(); range.len()];
+        for i in 0..range.len() {
+            for j in 0..range.len() {
+                if j != i {
+                    nums[i] *= range[j];
+                    denums[i] *= range[j] - range[i];
+                }
+            }
+        }
+
+        invert_denominators(range, &mut denums)?;
+        Ok(nums.iter().zip(denums).map(|(num, inv)| num * inv).collect())
     }
 
     pub fn shares(&self, n: usize) -> ShareVector {
-        let mut shares = Vec::<Share>::with_capacity(n);
-        for j in 1..=n {
-            let x = Scalar::from(j as u64);
-            let share = Share { i: j as u32, yi: self.evaluate(x) };
-            shares.push(share);
+        ShareVector(self.shares_iter(n).collect())
+    }
+
+    // NOTE: lazy evaluation of the shares for parties 1..=n, a dealer can send each one without holding all of them
+    pub fn shares_iter(&self, n: usize) -> impl Iterator<Item = Share> + '_ {
+        (1..=n).map(move |j| Share { i: j as u32, yi: self.evaluate(Scalar::from(j as u64)) })
+    }
+
+    // NOTE: party k receives weights[k] consecutive evaluation points, starting after the points of party k-1.
+    // Any set of parties with a total weight greater than the degree can interpolate the secret.
+    pub fn weighted_shares(&self, weights: &[usize]) -> WeightedShares {
+        let mut start = 0;
+        let parties = weights.iter().map(|w| {
+            let points = (start + 1..=start + w).map(|j| Share { i: j as u32, yi: self.evaluate(Scalar::from(j as u64)) }).collect();
+            start += w;
+            ShareVector(points)
+        }).collect();
+
+        WeightedShares { threshold: self.degree(), parties }
+    }
+
+    pub fn derivative(&self, order: usize) -> Self {
+        if order > self.degree() {
+            return Polynomial(vec![Scalar::zero()])
         }
 
-        ShareVector(shares)
+        // coefficient n of the result is a_(n+order) * (n+order)!/n!
+        let coefs = self.0.iter().enumerate().skip(order)
+            .map(|(n, an)| an * falling_factorial(n, order))
+            .collect::<Vec<_>>();
+
+        Polynomial(coefs)
     }
 }
 
 impl Evaluate for Polynomial {
     type Output = Scalar;
+    // NOTE: a constant polynomial [a0] evaluates to a0 for any x, an empty one is the zero polynomial
     fn evaluate(&self, x: Scalar) -> Scalar {
         // evaluate using Horner's rule
-        let mut rev = self.0.iter().rev();
-        let head = *rev.next().unwrap();
-            
-        rev.fold(head, |partial, coef| partial * x + coef)
+        self.0.iter().rev().fold(Scalar::zero(), |partial, coef| partial * x + coef)
     }
 }
 
@@ -262,45 +451,63 @@ impl Degree for Polynomial {
 // PointPolynomial
 //-----------------------------------------------------------------------------------------------------------
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct PointPolynomial(pub Vec<G1Projective>);
+pub struct PointPolynomial<G = G1Projective>(pub Vec<G>);
+
+pub type G1PointPolynomial = PointPolynomial<G1Projective>;
+pub type G2PointPolynomial = PointPolynomial<G2Projective>;
 
-define_add_variants!(LHS = PointPolynomial, RHS = PointPolynomial, Output = PointPolynomial);
-impl<'a, 'b> Add<&'b PointPolynomial> for &'a PointPolynomial {
-    type Output = PointPolynomial;
-    fn add(self, rhs: &'b PointPolynomial) -> PointPolynomial {
-        PointPolynomial(self.0.iter().zip(&rhs.0).map(|(A1, A2)| A1 + A2).collect::<Vec<_>>())
+impl<G: Group> PointPolynomial<G> {
+    pub fn verify(&self, share: &PointShare<G>) -> bool {
+        let x = Scalar::from(u64::from(share.i));
+        share.Yi == self.evaluate(x)
     }
-}
 
-define_mul_variants!(LHS = PointPolynomial, RHS = Scalar, Output = PointPolynomial);
-define_mul_variants!(LHS = Scalar, RHS = PointPolynomial, Output = PointPolynomial);
-define_comut_mul!(LHS = Scalar, RHS = PointPolynomial, Output = PointPolynomial);
-impl<'a, 'b> Mul<&'b Scalar> for &'a PointPolynomial {
-    type Output = PointPolynomial;
-    fn mul(self, rhs: &'b Scalar) -> PointPolynomial {
-        PointPolynomial(self.0.iter().map(|Ak| Ak * rhs).collect::<Vec<_>>())
+    // NOTE: checks all shares and reports each failing (i, submitted Yi, expected f(i)). A single bad party suggests a
+    // corrupted share, all parties failing suggests a wrong commitment.
+    pub fn verify_all(&self, shares: &PointShareVector<G>) -> Result<(), Vec<(u32, G, G)>> {
+        let failed = shares.0.iter().filter_map(|share| {
+            let expected = self.evaluate(Scalar::from(u64::from(share.i)));
+            if share.Yi == expected { None } else { Some((share.i, share.Yi, expected)) }
+        }).collect::<Vec<_>>();
+
+        if failed.is_empty() { Ok(()) } else { Err(failed) }
     }
 }
 
-impl PointPolynomial {
-    pub fn verify(&self, share: &PointShare) -> bool {
-        let x = Scalar::from(u64::from(share.i));
-        share.Yi == self.evaluate(x)
+// NOTE: published Feldman commitments, e.g. on a bulletin board. Length-prefixed points, each one decoded with the
+// curve and subgroup checks. The identity is a valid coefficient.
+impl PointPolynomial<G1Projective> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(PointEncoding::Compressed);
+        encoder.u32(self.0.len() as u32);
+        for coef in self.0.iter() {
+            encoder.g1(&coef.into());
+        }
+
+        encoder.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let mut decoder = Decoder::new(bytes)?;
+        let n = decoder.u32()?;
+        let coefs = (0..n).map(|_| decoder.g1().map(G1Projective::from)).collect::<Result<Vec<_>, _>>()?;
+        decoder.finish()?;
+
+        Ok(PointPolynomial(coefs))
     }
 }
 
-impl Evaluate for PointPolynomial {
-    type Output = G1Projective;
-    fn evaluate(&self, x: Scalar) -> G1Projective {
+impl<G: Group> Evaluate for PointPolynomial<G> {
+    type Output = G;
+    // NOTE: a constant polynomial [A0] evaluates to A0 for any x, an empty one (e.g. all identity coefficients cut by
+    // reconstruct) is the identity
+    fn evaluate(&self, x: Scalar) -> G {
         // evaluate using Horner's rule
-        let mut rev = self.0.iter().rev();
-        let head = *rev.next().unwrap();
-            
-        rev.fold(head, |partial, coef| partial * x + coef)
+        self.0.iter().rev().fold(G::identity(), |partial, coef| partial * x + *coef)
     }
 }
 
-impl Degree for PointPolynomial {
+impl<G: Group> Degree for PointPolynomial<G> {
     fn degree(&self) -> usize {
         self.0.len() - 1
     }
@@ -312,6 +519,7 @@ impl Degree for PointPolynomial {
 #[derive(Debug, Clone)]
 pub struct ShareVector(pub Vec<Share>);
 
+#[cfg(not(feature = "no-zeroize"))]
 impl Drop for ShareVector {
     fn drop(&mut self) {
         for item in self.0.iter_mut() {
@@ -338,6 +546,17 @@ impl<'a, 'b> Add<&'b ShareVector> for &'a ShareVector {
     }
 }
 
+// NOTE: index-aligned sum (e.g. DKG aggregation), panics as Add on misaligned vectors. An empty iterator sums to an
+// empty vector, there is no size to build a zero vector from.
+impl<'a> Sum<&'a ShareVector> for ShareVector {
+    fn sum<I: Iterator<Item = &'a ShareVector>>(mut iter: I) -> ShareVector {
+        match iter.next() {
+            Some(first) => iter.fold(first.clone(), |acc, item| &acc + item),
+            None => ShareVector(Vec::new())
+        }
+    }
+}
+
 define_add_variants!(LHS = ShareVector, RHS = Scalar, Output = ShareVector);
 define_add_variants!(LHS = Scalar, RHS = ShareVector, Output = ShareVector);
 define_comut_add!(LHS = Scalar, RHS = ShareVector, Output = ShareVector);
@@ -358,27 +577,202 @@ impl<'a, 'b> Mul<&'b Scalar> for &'a ShareVector {
     }
 }
 
-define_mul_variants!(LHS = ShareVector, RHS = G1Projective, Output = PointShareVector);
-define_mul_variants!(LHS = G1Projective, RHS = ShareVector, Output = PointShareVector);
-define_comut_mul!(LHS = G1Projective, RHS = ShareVector, Output = PointShareVector);
-impl<'a, 'b> Mul<&'b G1Projective> for &'a ShareVector {
-    type Output = PointShareVector;
-    fn mul(self, rhs: &'b G1Projective) -> PointShareVector {
-        PointShareVector(self.0.iter().map(|s| PointShare { i: s.i, Yi: rhs * s.yi }).collect::<Vec<_>>())
+impl ShareVector {
+    // NOTE: each holder deals its share with a new polynomial of the given degree. The new shares are combined with the
+    // lagrange coefficients of the current holders, so the secret is preserved. Requires a qualified set of shares.
+    pub fn reshare(&self, degree: usize, n: usize) -> ShareVector {
+        self.reshare_with(degree, n, &mut thread_rng())
+    }
+
+    pub fn reshare_with<R: RngCore + ?Sized>(&self, degree: usize, n: usize, rng: &mut R) -> ShareVector {
+        let range = self.0.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
+
+        let mut acc = ShareVector((1..=n).map(|j| Share { i: j as u32, yi: Scalar::zero() }).collect::<Vec<_>>());
+        for (item, l) in self.0.iter().zip(Polynomial::l_all(&range).expect(DISTINCT_INDICES)) {
+            let sub_shares = Polynomial::rnd_with(item.yi, degree, rng).shares(n);
+            acc = acc + sub_shares * l;
+        }
+
+        acc
+    }
+
+    // NOTE: shares of the polynomial through these shares at the missing indices up to n. Each new share is a Lagrange
+    // evaluation of the existing shares, the polynomial and its secret are never rebuilt.
+    pub fn extend_to(&self, n: usize) -> ShareVector {
+        let range = self.0.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
+        let weights = lx_num_bar(&range).expect(DISTINCT_INDICES);
+
+        let mut shares = self.0.clone();
+        for j in (1..=n as u32).filter(|j| self.0.iter().all(|s| s.i != *j)) {
+            let x = Scalar::from(j as u64);
+            let yi = self.0.iter().zip(weights.iter())
+                .fold(Scalar::zero(), |acc, (item, (num, barycentric))| acc + item.yi * Polynomial(num.clone()).evaluate(x) * barycentric);
+
+            shares.push(Share { i: j, yi });
+        }
+
+        shares.sort_by_key(|s| s.i);
+        ShareVector(shares)
+    }
+}
+
+impl ShareVector {
+    // NOTE: interpolate with repeated indices reported as DuplicateIndex, instead of a panic
+    pub fn try_interpolate(&self) -> Result<Scalar, SharesError> {
+        interpolate_shares(&self.0)
+    }
+
+    // NOTE: shares keyed by party index, for responses collected out of order. The map values are not cleared on drop.
+    pub fn to_map(&self) -> Result<HashMap<u32, Share>, SharesError> {
+        let mut map = HashMap::new();
+        for share in self.0.iter() {
+            if map.insert(share.i, *share).is_some() {
+                return Err(SharesError::DuplicateIndex(share.i))
+            }
+        }
+
+        Ok(map)
+    }
+
+    // NOTE: ordered by index, the index of each share is used (not the key) and must be unique
+    pub fn from_map(map: &HashMap<u32, Share>) -> Result<Self, SharesError> {
+        let mut shares = map.values().cloned().collect::<Vec<_>>();
+        shares.sort_by_key(|s| s.i);
+        if let Some(dup) = shares.windows(2).find(|w| w[0].i == w[1].i) {
+            return Err(SharesError::DuplicateIndex(dup[0].i))
+        }
+
+        Ok(ShareVector(shares))
+    }
+
+    // NOTE: same as PointShareVector::interpolate_checked
+    pub fn interpolate_checked(&self, expected_degree: usize) -> Result<Scalar, SharesError> {
+        if self.0.len() < expected_degree + 2 {
+            return Err(SharesError::Unqualified)
+        }
+
+        let poly = ShareVector(self.0[..expected_degree + 1].to_vec()).reconstruct();
+        for share in self.0.iter() {
+            if poly.evaluate(Scalar::from(share.i as u64)) != share.yi {
+                return Err(SharesError::Inconsistent(share.i))
+            }
+        }
+
+        Ok(poly.evaluate(Scalar::zero()))
+    }
+
+    pub fn can_reconstruct(&self, structure: &AccessStructure) -> bool {
+        structure.is_qualified(&self.0.iter().map(|s| s.i).collect::<Vec<_>>())
+    }
+
+    // NOTE: checked variants of the operators for untrusted inputs, the operators panic on misaligned vectors
+    pub fn try_add(&self, rhs: &ShareVector) -> Result<ShareVector, SharesError> {
+        self.try_zip(rhs, |a, b| a + b)
+    }
+
+    pub fn try_sub(&self, rhs: &ShareVector) -> Result<ShareVector, SharesError> {
+        self.try_zip(rhs, |a, b| a - b)
+    }
+
+    fn try_zip(&self, rhs: &ShareVector, op: fn(&Scalar, &Scalar) -> Scalar) -> Result<ShareVector, SharesError> {
+        if self.0.len() != rhs.0.len() {
+            return Err(SharesError::LengthMismatch(self.0.len(), rhs.0.len()))
+        }
+
+        let shares = self.0.iter().zip(&rhs.0).map(|(s1, s2)| match s1.i == s2.i {
+            true => Ok(Share { i: s1.i, yi: op(&s1.yi, &s2.yi) }),
+            false => Err(SharesError::IndexMismatch(s1.i, s2.i))
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ShareVector(shares))
+    }
+
+    // NOTE: base * secret, interpolated in the exponent. Each share is lifted to base * yi and combined with its Lagrange
+    // coefficient, so the secret scalar is never formed. Same as (self * base).interpolate() without the vector.
+    pub fn public_key(&self, base: G1Projective) -> G1Projective {
+        let range = self.0.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
+        let points = self.0.iter().map(|s| base * s.yi).collect::<Vec<_>>();
+
+        G1Projective::lincomb(&points, &Polynomial::l_all(&range).expect(DISTINCT_INDICES))
+    }
+
+    // NOTE: local rerandomization by a holder of all the shares (e.g. a trusted dealer), adds the shares of a random
+    // polynomial with a zero secret and the same degree. Unlike a proactive refresh, partial holders can't do this.
+    pub fn blind<R: RngCore + ?Sized>(&self, rng: &mut R) -> ShareVector {
+        let degree = self.reconstruct().0.iter().rposition(|c| *c != Scalar::zero()).unwrap_or(0);
+        let zero = Polynomial::rnd_with(Scalar::zero(), degree, rng);
+        ShareVector(self.0.iter().map(|s| Share { i: s.i, yi: s.yi + zero.evaluate(Scalar::from(u64::from(s.i))) }).collect())
+    }
+
+    // NOTE: moves the shares out without clearing them, the caller becomes responsible for the secrets.
+    // A plain Vec<Share> is never zeroized, avoid keeping copies of it around.
+    pub fn into_inner(mut self) -> Vec<Share> {
+        std::mem::take(&mut self.0)
+    }
+
+    // NOTE: interpolates with the shares of the given parties only, selected in constant-time
+    pub fn interpolate_using(&self, indices: &[u32]) -> Result<Scalar, SharesError> {
+        let selected = select_shares(&self.0, indices, |s| s.i)?;
+        Ok(ShareVector(selected).interpolate())
     }
 }
 
 impl Interpolate for ShareVector {
     type Output = Scalar;
     fn interpolate(&self) -> Scalar {
+        interpolate_shares(&self.0).expect(DISTINCT_INDICES)
+    }
+}
+
+impl Reconstruct for ShareVector {
+    type Output = Polynomial;
+
+    fn reconstruct(&self) -> Polynomial {
         let range = self.0.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
 
-        let mut acc = Scalar::zero();
-        for (i, item) in self.0.iter().enumerate() {
-            acc += Polynomial::l_i(&range, i) * item.yi;
+        let mut acc = vec![Scalar::zero(); range.len()];
+        for (item, (num, barycentric)) in self.0.iter().zip(lx_num_bar(&range).expect(DISTINCT_INDICES)) {
+            for j in 0..num.len() {
+                acc[j] += item.yi * (num[j] * barycentric);
+            }
         }
 
-        acc
+        Polynomial(acc)
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// WeightedShares
+//-----------------------------------------------------------------------------------------------------------
+// NOTE: the evaluation points of each party, in party order. The weight of a party is its number of points.
+#[derive(Debug, Clone)]
+pub struct WeightedShares {
+    pub threshold: usize,
+    pub parties: Vec<ShareVector>
+}
+
+impl WeightedShares {
+    pub fn weight(&self, party: usize) -> usize {
+        self.parties[party].0.len()
+    }
+
+    // NOTE: distinct parties with a total weight greater than the threshold. Unknown parties are never qualified.
+    pub fn is_qualified(&self, parties: &[usize]) -> bool {
+        let mut sorted = parties.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        sorted.len() == parties.len() && parties.iter().all(|k| *k < self.parties.len())
+            && parties.iter().map(|k| self.weight(*k)).sum::<usize>() > self.threshold
+    }
+
+    // NOTE: the points of the given parties, for interpolation
+    pub fn combine(&self, parties: &[usize]) -> Result<ShareVector, SharesError> {
+        if !self.is_qualified(parties) {
+            return Err(SharesError::Unqualified)
+        }
+
+        Ok(ShareVector(parties.iter().flat_map(|k| self.parties[*k].0.iter().cloned()).collect()))
     }
 }
 
@@ -386,80 +780,331 @@ impl Interpolate for ShareVector {
 // PointShareVector
 //-----------------------------------------------------------------------------------------------------------
 #[derive(Debug, Clone)]
-pub struct PointShareVector(pub Vec<PointShare>);
+pub struct PointShareVector<G = G1Projective>(pub Vec<PointShare<G>>);
 
-define_add_variants!(LHS = PointShareVector, RHS = PointShareVector, Output = PointShareVector);
-impl<'a, 'b> Add<&'b PointShareVector> for &'a PointShareVector {
-    type Output = PointShareVector;
-    fn add(self, rhs: &'b PointShareVector) -> PointShareVector {
-        if self.0.len() != rhs.0.len() {
-            panic!("ShareVector must be of the same size!");
+pub type G1PointShareVector = PointShareVector<G1Projective>;
+pub type G2PointShareVector = PointShareVector<G2Projective>;
+
+impl<G: Group> PointShareVector<G> {
+    // NOTE: same as ShareVector::try_interpolate
+    pub fn try_interpolate(&self) -> Result<G, SharesError> {
+        interpolate_points(&self.0)
+    }
+
+    // NOTE: interpolation checked against a known public value (e.g. Y for the yi * G1 shares). A mismatch means that
+    // some share is bad, without telling which one (see reconstruct_checked).
+    pub fn interpolate_expect(&self, expected: G) -> Result<G, SharesError> {
+        let res = self.try_interpolate()?;
+        if res != expected {
+            return Err(SharesError::UnexpectedValue)
         }
 
-        PointShareVector(self.0.iter().zip(&rhs.0).map(|(s1, s2)| {
-            if s1.i != s2.i {
-                panic!("Share in ShareVector must be in the same order!");
+        Ok(res)
+    }
+
+    pub fn can_reconstruct(&self, structure: &AccessStructure) -> bool {
+        structure.is_qualified(&self.0.iter().map(|s| s.i).collect::<Vec<_>>())
+    }
+
+    // NOTE: interpolates with the shares of the given parties only, selected in constant-time
+    pub fn interpolate_using(&self, indices: &[u32]) -> Result<G, SharesError> {
+        let selected = select_shares(&self.0, indices, |s| s.i)?;
+        Ok(PointShareVector(selected).interpolate())
+    }
+
+    // NOTE: interpolate and reconstruct must agree on f(0), for tests of both paths
+    pub fn check_consistency(&self) -> bool {
+        self.0.is_empty() || self.interpolate() == self.reconstruct().evaluate(Scalar::zero())
+    }
+
+    // NOTE: reconstructs from the first "degree + 1" shares and checks every input share against the result. Any set of
+    // points fits a polynomial of degree len - 1, so the expected degree is required to detect a byzantine share.
+    pub fn reconstruct_checked(&self, degree: usize) -> Result<PointPolynomial<G>, SharesError> {
+        if self.0.len() < degree + 1 {
+            return Err(SharesError::Unqualified)
+        }
+
+        let poly = PointShareVector(self.0[..degree + 1].to_vec()).reconstruct();
+        for share in self.0.iter() {
+            if poly.evaluate(Scalar::from(share.i as u64)) != share.Yi {
+                return Err(SharesError::Inconsistent(share.i))
             }
+        }
 
-            PointShare { i: s1.i, Yi: s1.Yi + s2.Yi }
-        }).collect::<Vec<_>>())
+        Ok(poly)
     }
-}
 
-define_add_variants!(LHS = PointShareVector, RHS = G1Projective, Output = PointShareVector);
-define_add_variants!(LHS = G1Projective, RHS = PointShareVector, Output = PointShareVector);
-define_comut_add!(LHS = G1Projective, RHS = PointShareVector, Output = PointShareVector);
-impl<'a, 'b> Add<&'b G1Projective> for &'a PointShareVector {
-    type Output = PointShareVector;
-    fn add(self, rhs: &'b G1Projective) -> PointShareVector {
-        PointShareVector(self.0.iter().map(|s| PointShare { i: s.i, Yi: s.Yi + rhs }).collect::<Vec<_>>())
+    // NOTE: interpolation that detects a dealer/combiner degree mismatch, at least one share over "expected_degree + 1"
+    // is required. With exactly "degree + 1" shares any polynomial fits and a wrong degree gives a wrong secret silently.
+    pub fn interpolate_checked(&self, expected_degree: usize) -> Result<G, SharesError> {
+        if self.0.len() < expected_degree + 2 {
+            return Err(SharesError::Unqualified)
+        }
+
+        Ok(self.reconstruct_checked(expected_degree)?.evaluate(Scalar::zero()))
     }
 }
 
-define_mul_variants!(LHS = PointShareVector, RHS = Scalar, Output = PointShareVector);
-define_mul_variants!(LHS = Scalar, RHS = PointShareVector, Output = PointShareVector);
-define_comut_mul!(LHS = Scalar, RHS = PointShareVector, Output = PointShareVector);
-impl<'a, 'b> Mul<&'b Scalar> for &'a PointShareVector {
-    type Output = PointShareVector;
-    fn mul(self, rhs: &'b Scalar) -> PointShareVector {
-        PointShareVector(self.0.iter().map(|s| PointShare { i: s.i, Yi: s.Yi * rhs }).collect::<Vec<_>>())
+impl<G: Group> Interpolate for PointShareVector<G> {
+    type Output = G;
+
+    fn interpolate(&self) -> G {
+        interpolate_points(&self.0).expect(DISTINCT_INDICES)
     }
 }
 
-impl Interpolate for PointShareVector {
-    type Output = G1Projective;
+impl<G: Group> Reconstruct for PointShareVector<G> {
+    type Output = PointPolynomial<G>;
 
-    fn interpolate(&self) -> G1Projective {
+    fn reconstruct(&self) -> PointPolynomial<G> {
+        self.reconstruct_with_secret().0
+    }
+}
+
+impl<G: Group> PointShareVector<G> {
+    // NOTE: the secret f(0) is the constant coefficient, no extra evaluation or interpolation is needed
+    pub fn reconstruct_with_secret(&self) -> (PointPolynomial<G>, G) {
         let range = self.0.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
 
-        let mut acc = G1Projective::identity();
-        for (i, item) in self.0.iter().enumerate() {
-            acc += item.Yi * Polynomial::l_i(&range, i);
+        let mut acc = vec![G::identity(); range.len()];
+        for (item, (num, barycentric)) in self.0.iter().zip(lx_num_bar(&range).expect(DISTINCT_INDICES)) {
+            for j in 0..num.len() {
+                acc[j] += item.Yi * (num[j] * barycentric);
+            }
         }
 
-        acc
+        let secret = acc.first().cloned().unwrap_or_else(G::identity);
+        debug_assert!(self.0.is_empty() || Ok(secret) == interpolate_points(&self.0));
+
+        cut_tail(&mut acc, G::identity());
+        (PointPolynomial(acc), secret)
     }
 }
 
-impl Reconstruct for PointShareVector {
-    type Output = PointPolynomial;
+//-----------------------------------------------------------------------------------------------------------
+// ShareAccumulator
+//-----------------------------------------------------------------------------------------------------------
+// NOTE: streaming collection of point shares, each one is checked against the commitment as it arrives. Ready with
+// "degree + 1" valid shares of distinct indices, later shares are still checked but not needed.
+#[derive(Debug, Clone)]
+pub struct ShareAccumulator<G = G1Projective> {
+    commitment: PointPolynomial<G>,
+    shares: Vec<PointShare<G>>
+}
+
+impl<G: Group> ShareAccumulator<G> {
+    pub fn new(commitment: PointPolynomial<G>) -> Self {
+        Self { commitment, shares: Vec::new() }
+    }
 
-    fn reconstruct(&self) -> PointPolynomial {
-        let range = self.0.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
+    // NOTE: rejected shares are not kept, returns whether the accumulator is ready
+    pub fn push(&mut self, share: PointShare<G>) -> Result<bool, SharesError> {
+        if self.shares.iter().any(|s| s.i == share.i) {
+            return Err(SharesError::DuplicateIndex(share.i))
+        }
 
-        let mut acc = vec![G1Projective::identity(); range.len()];
-        for (i, item) in self.0.iter().enumerate() {
-            let (num, barycentric) = lx_num_bar(&range, i);
-            for j in 0..num.len() {
-                acc[j] += item.Yi * (num[j] * barycentric);
+        if share.i == 0 || !self.commitment.verify(&share) {
+            return Err(SharesError::Inconsistent(share.i))
+        }
+
+        self.shares.push(share);
+        Ok(self.ready())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shares.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shares.is_empty()
+    }
+
+    pub fn ready(&self) -> bool {
+        self.shares.len() >= self.commitment.0.len()
+    }
+
+    // NOTE: interpolates the first "degree + 1" valid shares
+    pub fn interpolate(&self) -> Result<G, SharesError> {
+        if !self.ready() {
+            return Err(SharesError::Unqualified)
+        }
+
+        interpolate_points(&self.shares[..self.commitment.0.len()])
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Operators with group elements, implemented for each group (the orphan rule forbids "G * Share" for a generic G)
+//-----------------------------------------------------------------------------------------------------------
+macro_rules! define_group_ops {
+    ($G:ty) => {
+        define_mul_variants!(LHS = Share, RHS = $G, Output = PointShare<$G>);
+        define_mul_variants!(LHS = $G, RHS = Share, Output = PointShare<$G>);
+        define_comut_mul!(LHS = $G, RHS = Share, Output = PointShare<$G>);
+        impl<'a, 'b> Mul<&'b $G> for &'a Share {
+            type Output = PointShare<$G>;
+            fn mul(self, rhs: &'b $G) -> PointShare<$G> {
+                PointShare { i: self.i, Yi: rhs * self.yi }
             }
         }
 
-        cut_tail(&mut acc, G1Projective::identity());
-        PointPolynomial(acc)
+        define_add_variants!(LHS = PointShare<$G>, RHS = $G, Output = PointShare<$G>);
+        define_add_variants!(LHS = $G, RHS = PointShare<$G>, Output = PointShare<$G>);
+        define_comut_add!(LHS = $G, RHS = PointShare<$G>, Output = PointShare<$G>);
+        impl<'a, 'b> Add<&'b $G> for &'a PointShare<$G> {
+            type Output = PointShare<$G>;
+            fn add(self, rhs: &'b $G) -> PointShare<$G> {
+                PointShare { i: self.i, Yi: self.Yi + rhs }
+            }
+        }
+
+        define_sub_variants!(LHS = PointShare<$G>, RHS = $G, Output = PointShare<$G>);
+        define_sub_variants!(LHS = $G, RHS = PointShare<$G>, Output = PointShare<$G>);
+        define_comut_sub!(LHS = $G, RHS = PointShare<$G>, Output = PointShare<$G>);
+        impl<'a, 'b> Sub<&'b $G> for &'a PointShare<$G> {
+            type Output = PointShare<$G>;
+            fn sub(self, rhs: &'b $G) -> PointShare<$G> {
+                PointShare { i: self.i, Yi: self.Yi - rhs }
+            }
+        }
+
+        define_mul_variants!(LHS = PointShare<$G>, RHS = Scalar, Output = PointShare<$G>);
+        define_mul_variants!(LHS = Scalar, RHS = PointShare<$G>, Output = PointShare<$G>);
+        define_comut_mul!(LHS = Scalar, RHS = PointShare<$G>, Output = PointShare<$G>);
+        impl<'a, 'b> Mul<&'b Scalar> for &'a PointShare<$G> {
+            type Output = PointShare<$G>;
+            fn mul(self, rhs: &'b Scalar) -> PointShare<$G> {
+                PointShare { i: self.i, Yi: self.Yi * rhs }
+            }
+        }
+
+        define_mul_variants!(LHS = Polynomial, RHS = $G, Output = PointPolynomial<$G>);
+        define_mul_variants!(LHS = $G, RHS = Polynomial, Output = PointPolynomial<$G>);
+        define_comut_mul!(LHS = $G, RHS = Polynomial, Output = PointPolynomial<$G>);
+        impl<'a, 'b> Mul<&'b $G> for &'a Polynomial {
+            type Output = PointPolynomial<$G>;
+            fn mul(self, rhs: &'b $G) -> PointPolynomial<$G> {
+                PointPolynomial(self.0.iter().map(|ak| rhs * ak).collect::<Vec<_>>())
+            }
+        }
+
+        define_add_variants!(LHS = PointPolynomial<$G>, RHS = PointPolynomial<$G>, Output = PointPolynomial<$G>);
+        impl<'a, 'b> Add<&'b PointPolynomial<$G>> for &'a PointPolynomial<$G> {
+            type Output = PointPolynomial<$G>;
+            fn add(self, rhs: &'b PointPolynomial<$G>) -> PointPolynomial<$G> {
+                if self.0.len() != rhs.0.len() {
+                    panic!("PointPolynomial must be of the same degree!");
+                }
+
+                PointPolynomial(self.0.iter().zip(&rhs.0).map(|(A1, A2)| A1 + A2).collect::<Vec<_>>())
+            }
+        }
+
+        define_mul_variants!(LHS = PointPolynomial<$G>, RHS = Scalar, Output = PointPolynomial<$G>);
+        define_mul_variants!(LHS = Scalar, RHS = PointPolynomial<$G>, Output = PointPolynomial<$G>);
+        define_comut_mul!(LHS = Scalar, RHS = PointPolynomial<$G>, Output = PointPolynomial<$G>);
+        impl<'a, 'b> Mul<&'b Scalar> for &'a PointPolynomial<$G> {
+            type Output = PointPolynomial<$G>;
+            fn mul(self, rhs: &'b Scalar) -> PointPolynomial<$G> {
+                PointPolynomial(self.0.iter().map(|Ak| Ak * rhs).collect::<Vec<_>>())
+            }
+        }
+
+        define_mul_variants!(LHS = ShareVector, RHS = $G, Output = PointShareVector<$G>);
+        define_mul_variants!(LHS = $G, RHS = ShareVector, Output = PointShareVector<$G>);
+        define_comut_mul!(LHS = $G, RHS = ShareVector, Output = PointShareVector<$G>);
+        impl<'a, 'b> Mul<&'b $G> for &'a ShareVector {
+            type Output = PointShareVector<$G>;
+            fn mul(self, rhs: &'b $G) -> PointShareVector<$G> {
+                PointShareVector(self.0.iter().map(|s| PointShare { i: s.i, Yi: rhs * s.yi }).collect::<Vec<_>>())
+            }
+        }
+
+        define_add_variants!(LHS = PointShareVector<$G>, RHS = PointShareVector<$G>, Output = PointShareVector<$G>);
+        impl<'a, 'b> Add<&'b PointShareVector<$G>> for &'a PointShareVector<$G> {
+            type Output = PointShareVector<$G>;
+            fn add(self, rhs: &'b PointShareVector<$G>) -> PointShareVector<$G> {
+                if self.0.len() != rhs.0.len() {
+                    panic!("ShareVector must be of the same size!");
+                }
+
+                PointShareVector(self.0.iter().zip(&rhs.0).map(|(s1, s2)| {
+                    if s1.i != s2.i {
+                        panic!("Share in ShareVector must be in the same order!");
+                    }
+
+                    PointShare { i: s1.i, Yi: s1.Yi + s2.Yi }
+                }).collect::<Vec<_>>())
+            }
+        }
+
+        // NOTE: same as ShareVector, an empty iterator sums to an empty vector
+        impl<'a> Sum<&'a PointShareVector<$G>> for PointShareVector<$G> {
+            fn sum<I: Iterator<Item = &'a PointShareVector<$G>>>(mut iter: I) -> PointShareVector<$G> {
+                match iter.next() {
+                    Some(first) => iter.fold(first.clone(), |acc, item| &acc + item),
+                    None => PointShareVector(Vec::new())
+                }
+            }
+        }
+
+        define_sub_variants!(LHS = PointShareVector<$G>, RHS = PointShareVector<$G>, Output = PointShareVector<$G>);
+        impl<'a, 'b> Sub<&'b PointShareVector<$G>> for &'a PointShareVector<$G> {
+            type Output = PointShareVector<$G>;
+            fn sub(self, rhs: &'b PointShareVector<$G>) -> PointShareVector<$G> {
+                if self.0.len() != rhs.0.len() {
+                    panic!("ShareVector must be of the same size!");
+                }
+
+                PointShareVector(self.0.iter().zip(&rhs.0).map(|(s1, s2)| {
+                    if s1.i != s2.i {
+                        panic!("Share in ShareVector must be in the same order!");
+                    }
+
+                    PointShare { i: s1.i, Yi: s1.Yi - s2.Yi }
+                }).collect::<Vec<_>>())
+            }
+        }
+
+        impl Neg for PointShareVector<$G> {
+            type Output = PointShareVector<$G>;
+            fn neg(self) -> PointShareVector<$G> {
+                -&self
+            }
+        }
+
+        impl<'a> Neg for &'a PointShareVector<$G> {
+            type Output = PointShareVector<$G>;
+            fn neg(self) -> PointShareVector<$G> {
+                PointShareVector(self.0.iter().map(|s| PointShare { i: s.i, Yi: -s.Yi }).collect::<Vec<_>>())
+            }
+        }
+
+        define_add_variants!(LHS = PointShareVector<$G>, RHS = $G, Output = PointShareVector<$G>);
+        define_add_variants!(LHS = $G, RHS = PointShareVector<$G>, Output = PointShareVector<$G>);
+        define_comut_add!(LHS = $G, RHS = PointShareVector<$G>, Output = PointShareVector<$G>);
+        impl<'a, 'b> Add<&'b $G> for &'a PointShareVector<$G> {
+            type Output = PointShareVector<$G>;
+            fn add(self, rhs: &'b $G) -> PointShareVector<$G> {
+                PointShareVector(self.0.iter().map(|s| PointShare { i: s.i, Yi: s.Yi + rhs }).collect::<Vec<_>>())
+            }
+        }
+
+        define_mul_variants!(LHS = PointShareVector<$G>, RHS = Scalar, Output = PointShareVector<$G>);
+        define_mul_variants!(LHS = Scalar, RHS = PointShareVector<$G>, Output = PointShareVector<$G>);
+        define_comut_mul!(LHS = Scalar, RHS = PointShareVector<$G>, Output = PointShareVector<$G>);
+        impl<'a, 'b> Mul<&'b Scalar> for &'a PointShareVector<$G> {
+            type Output = PointShareVector<$G>;
+            fn mul(self, rhs: &'b Scalar) -> PointShareVector<$G> {
+                PointShareVector(self.0.iter().map(|s| PointShare { i: s.i, Yi: s.Yi * rhs }).collect::<Vec<_>>())
+            }
+        }
     }
 }
 
+define_group_ops!(G1Projective);
+define_group_ops!(G2Projective);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -480,6 +1125,10 @@ mod tests {
         let shares = poly.shares(parties);
         let s_res = shares.interpolate();
         assert!(s == s_res);
+        assert!(shares.reconstruct() == poly);
+
+        let range = shares.0.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
+        assert!(Polynomial::l_all(&range).unwrap() == (0..parties).map(|i| Polynomial::l_i(&range, i).unwrap()).collect::<Vec<_>>());
 
         let S_poly = poly * G1;
         let S_shares = shares * G1;
@@ -491,4 +1140,530 @@ mod tests {
         let S_res = S_shares.interpolate();
         assert!(S == S_res);
     }
+
+    fn point_interpolation<G: Group>() where for<'a> &'a Polynomial: Mul<G, Output = PointPolynomial<G>>, for<'a> &'a ShareVector: Mul<G, Output = PointShareVector<G>> {
+        let threshold = 3;
+        let s = rnd_scalar();
+        let poly = Polynomial::rnd(s, threshold);
+        let shares = poly.shares(threshold + 2);
+
+        let S_poly = &poly * G::generator();
+        let S_shares = &shares * G::generator();
+        assert!(S_shares.0.iter().all(|S_sh| S_poly.verify(S_sh)));
+        assert!(S_shares.interpolate() == G::generator() * s);
+        assert!(PointShareVector(S_shares.0[0..=threshold].to_vec()).reconstruct() == S_poly);
+
+        let partial = PointShareVector(S_shares.0[0..threshold].to_vec());
+        assert!(partial.interpolate() != G::generator() * s);
+    }
+
+    #[test]
+    fn group_interpolation() {
+        point_interpolation::<G1Projective>();
+        point_interpolation::<G2Projective>();
+
+        let share = G2PointShare { i: 1, Yi: G2Projective::generator() };
+        assert!((share * Scalar::from(2)).Yi == G2Projective::generator().double());
+    }
+
+    #[test]
+    fn interpolate_reconstruct_agree() {
+        let mut rng = thread_rng();
+        for _ in 0..20 {
+            // random degree, number of shares above the degree and subset of parties
+            let degree = (rng.next_u32() % 5) as usize;
+            let n = degree + 1 + (rng.next_u32() % 4) as usize;
+            let mut shares = Polynomial::rnd(rnd_scalar(), degree).shares(n) * G1Projective::generator();
+            shares.0.retain(|_| rng.next_u32() & 3 != 0);
+
+            assert!(shares.check_consistency());
+            if shares.0.len() > degree {
+                assert!(shares.reconstruct().degree() <= degree);
+            }
+        }
+
+        // arbitrary points, not from a low degree polynomial
+        let random = PointShareVector((1..6).map(|i| PointShare { i, Yi: G1Projective::generator() * rnd_scalar() }).collect());
+        assert!(random.check_consistency());
+    }
+
+    #[test]
+    fn subset_interpolation() {
+        let G1 = G1Projective::generator();
+        let s = rnd_scalar();
+        let S_shares = Polynomial::rnd(s, 2).shares(5) * G1;
+
+        let S1 = S_shares.interpolate_using(&[1, 2, 3]).unwrap();
+        let S2 = S_shares.interpolate_using(&[5, 2, 4]).unwrap();
+        assert!(S1 == G1 * s && S1 == S2);
+
+        assert!(S_shares.interpolate_using(&[1, 2, 6]) == Err(SharesError::MissingShare(6)));
+        assert!(S_shares.interpolate_using(&[1, 2, 2]) == Err(SharesError::DuplicateIndex(2)));
+    }
+
+    #[test]
+    fn ct_selection() {
+        let s = rnd_scalar();
+        let shares = Polynomial::rnd(s, 2).shares(5);
+
+        for i in 0..=6u32 {
+            let branchy = shares.0.iter().find(|s| s.i == i);
+            let ct = ct_select(&shares.0, i, |s| s.i);
+            assert!(branchy.map(|s| (s.i, s.yi)) == ct.map(|s| (s.i, s.yi)));
+        }
+
+        let branchy = ShareVector(vec![shares.0[3], shares.0[0], shares.0[4]]).interpolate();
+        assert!(shares.interpolate_using(&[4, 1, 5]) == Ok(branchy));
+        assert!(branchy == s);
+        assert!(shares.interpolate_using(&[4, 1, 9]) == Err(SharesError::MissingShare(9)));
+        assert!(ct_select::<Share>(&[], 1, |s| s.i).is_none());
+    }
+
+    #[test]
+    fn lagrange_at() {
+        let poly = Polynomial::rnd(rnd_scalar(), 3);
+        let shares = poly.shares(4);
+        let range = shares.0.iter().map(|s| Scalar::from(s.i as u64)).collect::<Vec<_>>();
+
+        // evaluation at an arbitrary point, and at a share point
+        for x in [rnd_scalar(), Scalar::from(2)].iter() {
+            let mut fx = Scalar::zero();
+            for (i, share) in shares.0.iter().enumerate() {
+                fx += Polynomial::l_i_at(&range, i, *x).unwrap() * share.yi;
+            }
+
+            assert!(fx == poly.evaluate(*x));
+        }
+
+        let duplicated = [Scalar::from(1), Scalar::from(2), Scalar::from(1)];
+        assert!(Polynomial::l_i(&duplicated, 1) == Err(SharesError::DuplicateIndex(1)));
+        assert!(Polynomial::l_i_at(&duplicated, 0, rnd_scalar()) == Err(SharesError::DuplicateIndex(1)));
+        assert!(Polynomial::l_all(&duplicated) == Err(SharesError::DuplicateIndex(1)));
+    }
+
+    #[test]
+    fn borrowed_interpolation() {
+        let G1 = G1Projective::generator();
+        let s = rnd_scalar();
+        let shares = Polynomial::rnd(s, 2).shares(5);
+        let S_shares = &shares * G1;
+
+        assert!(interpolate_shares(&shares.0[1..4]) == Ok(s));
+        assert!(interpolate_shares(&shares.0[1..4]).unwrap() == ShareVector(shares.0[1..4].to_vec()).interpolate());
+
+        assert!(interpolate_points(&S_shares.0[2..]) == Ok(G1 * s));
+        assert!(interpolate_points(&S_shares.0[2..]).unwrap() == PointShareVector(S_shares.0[2..].to_vec()).interpolate());
+    }
+
+    #[test]
+    fn extracted_shares() {
+        let s = rnd_scalar();
+        let shares = Polynomial::rnd(s, 2).shares(4);
+
+        let inner = shares.into_inner();
+        assert!(inner.len() == 4 && inner.iter().all(|s| s.yi != Scalar::zero()));
+        assert!(interpolate_shares(&inner) == Ok(s));
+        assert!(ShareVector(inner).interpolate() == s);
+    }
+
+    #[cfg(feature = "no-zeroize")]
+    #[test]
+    fn no_zeroize() {
+        let s = rnd_scalar();
+        let poly = Polynomial::rnd(s, 2);
+        let shares = poly.shares(5);
+
+        let copy = shares.clone();
+        drop(shares);
+        drop(poly);
+        assert!(copy.interpolate() == s && copy.reconstruct().evaluate(Scalar::zero()) == s);
+    }
+
+    #[test]
+    fn evaluate_edge_cases() {
+        let a0 = rnd_scalar();
+        let constant = Polynomial(vec![a0]);
+        assert!(constant.degree() == 0);
+        assert!(constant.evaluate(Scalar::zero()) == a0 && constant.evaluate(rnd_scalar()) == a0);
+
+        let empty = Polynomial(Vec::new());
+        assert!(empty.evaluate(rnd_scalar()) == Scalar::zero());
+
+        let G1 = G1Projective::generator();
+        let A0 = G1 * a0;
+        assert!(PointPolynomial(vec![A0]).evaluate(rnd_scalar()) == A0);
+        assert!(PointPolynomial::<G1Projective>(Vec::new()).evaluate(rnd_scalar()) == G1Projective::identity());
+    }
+
+    #[test]
+    fn lazy_shares() {
+        let poly = Polynomial::rnd(rnd_scalar(), 3);
+        let shares = poly.shares(7);
+
+        assert!(poly.shares_iter(7).count() == 7);
+        assert!(poly.shares_iter(7).zip(shares.0.iter()).all(|(a, b)| a.i == b.i && a.yi == b.yi));
+        assert!(poly.shares_iter(0).next().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "same degree")]
+    fn mismatched_polynomials() {
+        let _ = Polynomial::rnd(rnd_scalar(), 2) + Polynomial::rnd(rnd_scalar(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "same degree")]
+    fn mismatched_point_polynomials() {
+        let G1 = G1Projective::generator();
+        let _ = Polynomial::rnd(rnd_scalar(), 3) * G1 + Polynomial::rnd(rnd_scalar(), 2) * G1;
+    }
+
+    #[test]
+    #[should_panic(expected = "distinct indices")]
+    fn duplicate_interpolation() {
+        let mut shares = Polynomial::rnd(rnd_scalar(), 2).shares(3);
+        shares.0[2] = shares.0[0];
+        let _ = shares.interpolate();
+    }
+
+    #[test]
+    #[should_panic(expected = "distinct indices")]
+    fn duplicate_reconstruction() {
+        let G1 = G1Projective::generator();
+        let mut S_shares = Polynomial::rnd(rnd_scalar(), 2).shares(3) * G1;
+        S_shares.0[1] = S_shares.0[2];
+        let _ = S_shares.reconstruct();
+    }
+
+    #[test]
+    fn reconstruct_with_secret() {
+        let G1 = G1Projective::generator();
+        let s = rnd_scalar();
+        let S_shares = Polynomial::rnd(s, 3).shares(5) * G1;
+
+        let (poly, S) = S_shares.reconstruct_with_secret();
+        assert!(S == G1 * s && S == S_shares.interpolate());
+        assert!(poly == S_shares.reconstruct());
+
+        let (poly, S) = PointShareVector::<G1Projective>(Vec::new()).reconstruct_with_secret();
+        assert!(poly.0.is_empty() && S == G1Projective::identity());
+    }
+
+    #[test]
+    fn checked_reconstruction() {
+        let G1 = G1Projective::generator();
+        let s = rnd_scalar();
+        let mut shares = Polynomial::rnd(s, 2).shares(5) * G1;
+
+        let poly = shares.reconstruct_checked(2).unwrap();
+        assert!(poly.evaluate(Scalar::zero()) == G1 * s);
+        assert!(shares.reconstruct_checked(5) == Err(SharesError::Unqualified));
+
+        // a tampered share disagrees with the polynomial of the others, inside or outside the reconstruction set
+        shares.0[4].Yi += G1;
+        assert!(shares.reconstruct_checked(2) == Err(SharesError::Inconsistent(5)));
+
+        shares.0[4].Yi -= G1;
+        shares.0[0].Yi += G1;
+        assert!(shares.reconstruct_checked(2).is_err());
+        assert!(shares.reconstruct_checked(4).is_ok());
+    }
+
+    #[test]
+    fn duplicate_indices() {
+        let G1 = G1Projective::generator();
+        let s = rnd_scalar();
+        let mut shares = Polynomial::rnd(s, 2).shares(4);
+        assert!(shares.try_interpolate() == Ok(s));
+        assert!((&shares * G1).try_interpolate() == Ok(G1 * s));
+
+        shares.0[3].i = 2;
+        assert!(shares.try_interpolate() == Err(SharesError::DuplicateIndex(2)));
+        assert!((&shares * G1).try_interpolate() == Err(SharesError::DuplicateIndex(2)));
+        assert!(interpolate_shares(&shares.0) == Err(SharesError::DuplicateIndex(2)));
+    }
+
+    #[test]
+    fn qualified_sets() {
+        let threshold = AccessStructure::Threshold(2);
+        let shares = Polynomial::rnd(rnd_scalar(), 2).shares(5);
+        assert!(shares.can_reconstruct(&threshold));
+        assert!((&shares * G1Projective::generator()).can_reconstruct(&threshold));
+        assert!(ShareVector(shares.0[2..].to_vec()).can_reconstruct(&threshold));
+        assert!(!ShareVector(shares.0[3..].to_vec()).can_reconstruct(&threshold));
+
+        let mut repeated = ShareVector(shares.0[..3].to_vec());
+        repeated.0[2].i = 1;
+        assert!(!repeated.can_reconstruct(&threshold));
+
+        // 2 admins and 4 operators, at least 1 admin and 3 parties in total
+        let hierarchical = AccessStructure::Hierarchical(HierarchicalSharing::new(&[1, 3]), vec![2, 4]);
+        assert!(hierarchical.is_qualified(&[1, 3, 4]));
+        assert!(hierarchical.is_qualified(&[2, 5, 6]));
+        assert!(!hierarchical.is_qualified(&[3, 4, 5]));
+        assert!(!hierarchical.is_qualified(&[1, 3]));
+        assert!(!hierarchical.is_qualified(&[1, 3, 7]));
+    }
+
+    #[test]
+    fn accumulated_shares() {
+        let G1 = G1Projective::generator();
+        let s = rnd_scalar();
+        let poly = Polynomial::rnd(s, 2);
+        let shares = &poly.shares(5) * G1;
+
+        let mut acc = ShareAccumulator::new(&poly * G1);
+        assert!(acc.push(shares.0[4]) == Ok(false));
+        assert!(acc.push(shares.0[4]) == Err(SharesError::DuplicateIndex(5)));
+
+        let mut bad = shares.0[0];
+        bad.Yi += G1;
+        assert!(acc.push(bad) == Err(SharesError::Inconsistent(1)));
+        assert!(acc.interpolate() == Err(SharesError::Unqualified));
+
+        assert!(acc.push(shares.0[2]) == Ok(false));
+        assert!(acc.push(shares.0[0]) == Ok(true));
+        assert!(acc.len() == 3 && acc.interpolate() == Ok(G1 * s));
+    }
+
+    #[test]
+    fn blinded_shares() {
+        let s = rnd_scalar();
+        let shares = Polynomial::rnd(s, 2).shares(5);
+        let blinded = shares.blind(&mut thread_rng());
+
+        assert!(blinded.interpolate() == s);
+        assert!(ShareVector(blinded.0[2..].to_vec()).interpolate() == s);
+        assert!(blinded.0.iter().zip(shares.0.iter()).all(|(b, s)| b.i == s.i && b.yi != s.yi));
+    }
+
+    #[test]
+    fn public_key() {
+        let base = G1Projective::generator() * rnd_scalar();
+        let shares = Polynomial::rnd(rnd_scalar(), 2).shares(4);
+
+        assert!(shares.public_key(base) == base * shares.interpolate());
+        assert!(shares.public_key(base) == (&shares * base).interpolate());
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let (s1, s2) = (rnd_scalar(), rnd_scalar());
+        let (v1, v2) = (Polynomial::rnd(s1, 2).shares(4), Polynomial::rnd(s2, 2).shares(4));
+        assert!(v1.try_add(&v2).unwrap().interpolate() == s1 + s2);
+        assert!(v1.try_sub(&v2).unwrap().interpolate() == s1 - s2);
+
+        let short = ShareVector(v2.0[..3].to_vec());
+        assert!(v1.try_add(&short).err() == Some(SharesError::LengthMismatch(4, 3)));
+
+        let mut reordered = v2.clone();
+        reordered.0.swap(1, 2);
+        assert!(v1.try_sub(&reordered).err() == Some(SharesError::IndexMismatch(2, 3)));
+    }
+
+    #[test]
+    fn commitment_encoding() {
+        use crate::crypto::G1_COMPRESSED_LEN;
+
+        let G1 = G1Projective::generator();
+        let poly = Polynomial::rnd(rnd_scalar(), 3);
+        let commitment = &poly * G1;
+
+        let bytes = commitment.to_bytes();
+        assert!(bytes.len() == 1 + 4 + 4 * G1_COMPRESSED_LEN);
+        let decoded = PointPolynomial::from_bytes(&bytes).unwrap();
+        assert!(decoded == commitment);
+        assert!(decoded.verify(&(&poly.shares(2) * G1).0[1]));
+
+        // a point that is not on the curve
+        let mut invalid = bytes.clone();
+        invalid[5..5 + G1_COMPRESSED_LEN].copy_from_slice(&[0xff; G1_COMPRESSED_LEN]);
+        assert!(PointPolynomial::from_bytes(&invalid) == Err(CryptoError::InvalidPoint));
+
+        assert!(PointPolynomial::from_bytes(&bytes[..bytes.len() - 1]) == Err(CryptoError::InvalidLength));
+    }
+
+    #[test]
+    fn summed_vectors() {
+        let G1 = G1Projective::generator();
+        let secrets = (0..3).map(|_| rnd_scalar()).collect::<Vec<_>>();
+        let dealt = secrets.iter().map(|s| Polynomial::rnd(*s, 2).shares(4)).collect::<Vec<_>>();
+
+        let total = secrets.iter().fold(Scalar::zero(), |acc, s| acc + s);
+        let sum: ShareVector = dealt.iter().sum();
+        assert!(sum.0.len() == 4 && sum.interpolate() == total);
+
+        let points = dealt.iter().map(|d| d * G1).collect::<Vec<_>>();
+        let sum: PointShareVector = points.iter().sum();
+        assert!(sum.interpolate() == G1 * total);
+
+        let empty: ShareVector = Vec::<ShareVector>::new().iter().sum();
+        assert!(empty.0.is_empty());
+    }
+
+    #[test]
+    fn share_maps() {
+        let s = rnd_scalar();
+        let shares = Polynomial::rnd(s, 2).shares(4);
+
+        let map = shares.to_map().unwrap();
+        assert!(map.len() == 4 && map[&3].yi == shares.0[2].yi);
+
+        let res = ShareVector::from_map(&map).unwrap();
+        assert!(res.0.iter().zip(shares.0.iter()).all(|(a, b)| a.i == b.i && a.yi == b.yi));
+        assert!(res.interpolate() == s);
+
+        let mut dup = shares.clone();
+        dup.0[3].i = 2;
+        assert!(dup.to_map().err() == Some(SharesError::DuplicateIndex(2)));
+
+        let mut map = map;
+        map.insert(9, Share { i: 1, yi: s });
+        assert!(ShareVector::from_map(&map).err() == Some(SharesError::DuplicateIndex(1)));
+    }
+
+    #[test]
+    fn verify_all_diagnostics() {
+        let G1 = G1Projective::generator();
+        let poly = Polynomial::rnd(rnd_scalar(), 2);
+        let commitment = &poly * G1;
+        let mut shares = poly.shares(4) * G1;
+        assert!(commitment.verify_all(&shares).is_ok());
+
+        let expected = shares.0[2].Yi;
+        shares.0[2].Yi += G1;
+        assert!(commitment.verify_all(&shares) == Err(vec![(3, expected + G1, expected)]));
+        assert!(!commitment.verify(&shares.0[2]) && commitment.verify(&shares.0[1]));
+
+        // a wrong commitment fails for every party
+        let wrong = Polynomial::rnd(rnd_scalar(), 2) * G1;
+        assert!(wrong.verify_all(&shares).unwrap_err().len() == 4);
+    }
+
+    #[test]
+    fn degree_mismatch() {
+        let G1 = G1Projective::generator();
+        let s = rnd_scalar();
+
+        // dealt with degree 3, combined expecting degree 2
+        let shares = Polynomial::rnd(s, 3).shares(5);
+        assert!(ShareVector(shares.0[..3].to_vec()).interpolate() != s);
+        assert!(shares.interpolate_checked(2) == Err(SharesError::Inconsistent(4)));
+        assert!(shares.interpolate_checked(3) == Ok(s));
+        assert!(shares.interpolate_checked(4) == Err(SharesError::Unqualified));
+
+        let S_shares = &shares * G1;
+        assert!(S_shares.interpolate_checked(2) == Err(SharesError::Inconsistent(4)));
+        assert!(S_shares.interpolate_checked(3) == Ok(G1 * s));
+    }
+
+    #[test]
+    fn point_share_differences() {
+        let G1 = G1Projective::generator();
+        let v = Polynomial::rnd(rnd_scalar(), 2).shares(4) * G1;
+        let w = Polynomial::rnd(rnd_scalar(), 2).shares(4) * G1;
+
+        let zero = &v - &v;
+        assert!(zero.0.iter().zip(v.0.iter()).all(|(z, s)| z.i == s.i && z.Yi == G1Projective::identity()));
+        assert!((&v + -&v).0.iter().all(|z| z.Yi == G1Projective::identity()));
+
+        // differences interpolate to the difference of the secrets
+        assert!((&v - &w).interpolate() == v.interpolate() - w.interpolate());
+
+        let V = Polynomial::rnd(rnd_scalar(), 2).shares(3) * G2Projective::generator();
+        assert!((-&V).0.iter().zip(V.0.iter()).all(|(n, s)| n.Yi == -s.Yi));
+    }
+
+    #[test]
+    fn weighted_interpolation() {
+        let threshold = 3;
+        let weights = [3, 1, 1, 1];
+
+        let s = rnd_scalar();
+        let poly = Polynomial::rnd(s, threshold);
+        let shares = poly.weighted_shares(&weights);
+        assert!(shares.parties.iter().map(|p| p.0.len()).collect::<Vec<_>>() == weights);
+        assert!(shares.parties[1].0[0].i == 4 && shares.parties[3].0[0].i == 6);
+
+        // heavy party (points 1..=3) plus one light party (point 4)
+        assert!(shares.is_qualified(&[0, 1]));
+        assert!(shares.combine(&[1, 0]).unwrap().interpolate() == s);
+
+        // two or three light parties, or a party counted twice
+        assert!(!shares.is_qualified(&[1, 2]) && !shares.is_qualified(&[1, 2, 3]));
+        assert!(shares.combine(&[2, 3]).err() == Some(SharesError::Unqualified));
+        assert!(!shares.is_qualified(&[1, 1, 1, 1]) && !shares.is_qualified(&[0, 4]));
+        assert!(shares.combine(&[0, 2, 3]).unwrap().interpolate() == s);
+    }
+
+    #[test]
+    fn point_share_proofs() {
+        let G1 = G1Projective::generator();
+        let R = G1 * rnd_scalar();
+
+        let threshold = 3;
+        let poly = Polynomial::rnd(rnd_scalar(), threshold);
+        let commit = &poly * G1;
+
+        for share in poly.shares(threshold + 1).0.iter() {
+            let Ci = commit.evaluate(Scalar::from(u64::from(share.i)));
+            let (point, proof) = PointShare::prove(share, &G1, &R);
+            assert!(point.verify_proof(&proof, &G1, &Ci, &R));
+
+            let forged = point + G1;
+            assert!(!forged.verify_proof(&proof, &G1, &Ci, &R));
+        }
+    }
+
+    #[test]
+    fn point_share_encoding() {
+        let share = PointShare { i: 7, Yi: G1Projective::generator() * rnd_scalar() };
+
+        for enc in [PointEncoding::Compressed, PointEncoding::Uncompressed].iter() {
+            let bytes = share.to_bytes_with(*enc);
+            assert!(bytes.len() == 1 + 4 + enc.g1_len());
+
+            let res = PointShare::from_bytes(&bytes).unwrap();
+            assert!(res.i == share.i && res.Yi == share.Yi);
+        }
+    }
+
+    #[test]
+    fn expected_interpolation() {
+        let y = rnd_scalar();
+        let Y = G1Projective::generator() * y;
+        let mut shares = &Polynomial::rnd(y, 2).shares(3) * G1Projective::generator();
+        assert!(shares.interpolate_expect(Y) == Ok(Y));
+
+        shares.0[1].Yi += G1Projective::generator();
+        assert!(shares.interpolate_expect(Y) == Err(SharesError::UnexpectedValue));
+
+        shares.0[1].i = shares.0[0].i;
+        assert!(shares.interpolate_expect(Y) == Err(SharesError::DuplicateIndex(shares.0[0].i)));
+    }
+
+    #[test]
+    fn resharing() {
+        let s = rnd_scalar();
+        let shares = Polynomial::rnd(s, 4).shares(5);
+
+        let reshared = shares.reshare(2, 5);
+        assert!(ShareVector(reshared.0[0..3].to_vec()).interpolate() == s);
+        assert!(ShareVector(reshared.0[2..5].to_vec()).interpolate() == s);
+        assert!(ShareVector(reshared.0[0..2].to_vec()).interpolate() != s);
+    }
+
+    #[test]
+    fn extension() {
+        let poly = Polynomial::rnd(rnd_scalar(), 2);
+        let all = poly.shares(6);
+
+        let extended = ShareVector(all.0[0..3].to_vec()).extend_to(6);
+        assert!(extended.0.iter().zip(all.0.iter()).all(|(a, b)| a.i == b.i && a.yi == b.yi));
+
+        // missing indices in the middle are filled in order
+        let extended = ShareVector(vec![all.0[0], all.0[2], all.0[4]]).extend_to(5);
+        assert!(extended.0.iter().zip(all.0.iter()).all(|(a, b)| a.i == b.i && a.yi == b.yi));
+        assert!(extended.0.len() == 5);
+    }
 }
\ No newline at end of file
@@ -1,14 +1,29 @@
 #![allow(dead_code)]
 
+use crate::crypto::{check_public_g1, rnd_scalar, CryptoError, PairingEngine, G1_COMPRESSED_LEN};
+
 use sha2::{Sha512, Digest};
+use clear_on_drop::clear::Clear;
 use bls12_381::{Scalar, G1Affine};
 
+// NOTE: SHA-512 by default, Blake2b with the "blake2b" feature. Both have 64 byte outputs, but hashes, challenges and
+// token signatures are not interchangeable, all parties (network, clients and verifiers) must use the same build.
+#[cfg(not(feature = "blake2b"))]
+pub type HashFunction = Sha512;
+
+#[cfg(feature = "blake2b")]
+pub type HashFunction = blake2::Blake2b;
+
 pub fn hash(data: &[&[u8]]) -> Scalar {
-    let mut hasher = Sha512::new();
+    let mut hasher = HashFunction::new();
     for d in data {
         hasher.input(*d);
     }
     
+    hash_result(hasher)
+}
+
+fn hash_result(hasher: HashFunction) -> Scalar {
     let result = unsafe {
         &*(hasher.result().as_ptr() as *const [u8; 64])
     };
@@ -16,14 +31,58 @@ pub fn hash(data: &[&[u8]]) -> Scalar {
     Scalar::from_bytes_wide(result)
 }
 
+// NOTE: hash state of a common prefix, finalized many times with different suffixes.
+// PrefixHash::new(a).hash(b) == hash(a ++ b), the prefix is processed only once.
+#[derive(Clone)]
+pub struct PrefixHash(HashFunction);
+
+impl PrefixHash {
+    pub fn new(prefix: &[&[u8]]) -> Self {
+        let mut hasher = HashFunction::new();
+        for d in prefix {
+            hasher.input(*d);
+        }
+
+        PrefixHash(hasher)
+    }
+
+    pub fn hash(&self, suffix: &[&[u8]]) -> Scalar {
+        let mut hasher = self.0.clone();
+        for d in suffix {
+            hasher.input(*d);
+        }
+
+        hash_result(hasher)
+    }
+}
+
+// NOTE: the only challenge computation, c = H(G1, P1, M, data). Sign, verify and the streaming and engine variants
+// all go through challenge_hasher and challenge, so they can't diverge.
 fn hash_c(G1: &G1Affine, P1: &G1Affine, M: &G1Affine, data: &[&[u8]]) -> Scalar {
-    let G1_comp = G1.to_compressed();
-    let P1_comp = P1.to_compressed();
-    let M_comp = M.to_compressed();
+    hash_c_comp(&G1.to_compressed(), &P1.to_compressed(), &M.to_compressed(), data)
+}
 
-    let mut all = vec![G1_comp.as_ref(), P1_comp.as_ref(), M_comp.as_ref()];
-    all.extend_from_slice(data);
-    hash(&all)
+fn hash_c_comp(G1_comp: &[u8; G1_COMPRESSED_LEN], P1_comp: &[u8; G1_COMPRESSED_LEN], M_comp: &[u8; G1_COMPRESSED_LEN], data: &[&[u8]]) -> Scalar {
+    let mut hasher = challenge_hasher(G1_comp, P1_comp, M_comp);
+    for d in data {
+        hasher.input(*d);
+    }
+
+    challenge(hasher)
+}
+
+fn nonce(s: &Scalar, data: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new()
+        .chain(s.to_bytes());
+    
+    for d in data {
+        hasher.input(d);
+    }
+
+    let mut result = [0u8; 64];
+    result.copy_from_slice(&hasher.result()[0..64]);
+
+    Scalar::from_bytes_wide(&result)
 }
 
 //-----------------------------------------------------------------------------------------------------------
@@ -37,17 +96,7 @@ pub struct Signature {
 
 impl Signature {
     pub fn sign(s: &Scalar, G1: &G1Affine, P1: &G1Affine, data: &[&[u8]]) -> Self {
-        let mut hasher = Sha512::new()
-            .chain(s.to_bytes());
-        
-        for d in data {
-            hasher.input(d);
-        }
-
-        let mut result = [0u8; 64];
-        result.copy_from_slice(&hasher.result()[0..64]);
-
-        let m = Scalar::from_bytes_wide(&result);
+        let m = nonce(s, data);
         let M: G1Affine = (G1 * m).into();
 
         let c = hash_c(G1, P1, &M, data);
@@ -55,11 +104,114 @@ impl Signature {
         Self { c, p: m - c * s }
     }
 
+    // NOTE: with an identity P1 (or G1) any (c, p) with c = H(G1, P1, p*G1, data) validates, such keys are rejected
     pub fn verify(&self, G1: &G1Affine, P1: &G1Affine, data: &[&[u8]]) -> bool {
+        self.verify_with_challenge(G1, P1, data).0
+    }
+
+    // NOTE: for debugging, also returns the recomputed challenge. It's computed even for rejected keys.
+    pub fn verify_with_challenge(&self, G1: &G1Affine, P1: &G1Affine, data: &[&[u8]]) -> (bool, Scalar) {
         let M: G1Affine = (P1 * self.c + G1 * self.p).into();
 
         let c = hash_c(G1, P1, &M, data);
-        c == self.c
+        let valid = check_public_g1(G1).is_ok() && check_public_g1(P1).is_ok() && c == self.c;
+        (valid, c)
+    }
+
+    // NOTE: same as sign, with group operations running on the selected backend
+    pub fn sign_with<E: PairingEngine>(s: &Scalar, G1: &E::G1, P1: &E::G1, data: &[&[u8]]) -> Self {
+        let m = nonce(s, data);
+        let M = E::g1_mul(G1, &m);
+
+        let c = hash_c_comp(&E::g1_compress(G1), &E::g1_compress(P1), &E::g1_compress(&M), data);
+
+        Self { c, p: m - c * s }
+    }
+
+    // NOTE: G1 and P1 are rejected as in verify, checked on their bls12_381 points
+    pub fn verify_with<E: PairingEngine>(&self, G1: &E::G1, P1: &E::G1, data: &[&[u8]]) -> bool {
+        let public = |P: &E::G1| E::g1_to_affine(P).and_then(|P| check_public_g1(&P)).is_ok();
+        public(G1) && public(P1) && self.challenge_with::<E>(G1, P1, data) == self.c
+    }
+
+    // NOTE: the recomputed challenge on the selected backend, for callers that already checked G1 and P1
+    pub(crate) fn challenge_with<E: PairingEngine>(&self, G1: &E::G1, P1: &E::G1, data: &[&[u8]]) -> Scalar {
+        let M = E::g1_add(&E::g1_mul(P1, &self.c), &E::g1_mul(G1, &self.p));
+        hash_c_comp(&E::g1_compress(G1), &E::g1_compress(P1), &E::g1_compress(&M), data)
+    }
+
+    // NOTE: streaming variants, the data is hashed in chunks. The challenge is the same as for the concatenated data,
+    // so signatures are interchangeable with sign/verify.
+    pub fn signer(s: &Scalar, G1: &G1Affine, P1: &G1Affine) -> Signer {
+        Signer::new(s, G1, P1)
+    }
+
+    pub fn verifier(&self, G1: &G1Affine, P1: &G1Affine) -> Verifier {
+        let valid = check_public_g1(G1).is_ok() && check_public_g1(P1).is_ok();
+        let M: G1Affine = (P1 * self.c + G1 * self.p).into();
+        Verifier { c: self.c, valid, hasher: challenge_hasher(&G1.to_compressed(), &P1.to_compressed(), &M.to_compressed()) }
+    }
+}
+
+fn challenge_hasher(G1_comp: &[u8; G1_COMPRESSED_LEN], P1_comp: &[u8; G1_COMPRESSED_LEN], M_comp: &[u8; G1_COMPRESSED_LEN]) -> HashFunction {
+    HashFunction::new()
+        .chain(G1_comp.as_ref())
+        .chain(P1_comp.as_ref())
+        .chain(M_comp.as_ref())
+}
+
+fn challenge(hasher: HashFunction) -> Scalar {
+    hash_result(hasher)
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Streaming Schnorr's signature
+//-----------------------------------------------------------------------------------------------------------
+// NOTE: in a single pass the nonce can't depend on the data (M is hashed before it), so it's derived from the key and fresh randomness
+pub struct Signer {
+    s: Scalar,
+    m: Scalar,
+    hasher: HashFunction
+}
+
+impl Drop for Signer {
+    fn drop(&mut self) {
+        self.s.clear();
+        self.m.clear();
+    }
+}
+
+impl Signer {
+    fn new(s: &Scalar, G1: &G1Affine, P1: &G1Affine) -> Self {
+        let m = nonce(s, &[&rnd_scalar().to_bytes()]);
+        let M: G1Affine = (G1 * m).into();
+
+        Self { s: *s, m, hasher: challenge_hasher(&G1.to_compressed(), &P1.to_compressed(), &M.to_compressed()) }
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.input(chunk);
+    }
+
+    pub fn finalize(mut self) -> Signature {
+        let c = challenge(std::mem::take(&mut self.hasher));
+        Signature { c, p: self.m - c * self.s }
+    }
+}
+
+pub struct Verifier {
+    c: Scalar,
+    valid: bool,
+    hasher: HashFunction
+}
+
+impl Verifier {
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.input(chunk);
+    }
+
+    pub fn finalize(self) -> bool {
+        self.valid && challenge(self.hasher) == self.c
     }
 }
 
@@ -82,12 +234,79 @@ impl ExtSignature {
     pub fn verify(&self, G1: &G1Affine, data: &[&[u8]]) -> bool {
         self.sig.verify(G1, &self.P1, data)
     }
+
+    // NOTE: single message, same as verify with a one-element slice. The challenge is hashed without allocations.
+    pub fn verify_msg(&self, G1: &G1Affine, msg: &[u8]) -> bool {
+        self.verify(G1, &[msg])
+    }
+
+    // NOTE: same as sign, with group operations running on the selected backend. P1 is kept as a bls12_381 point.
+    pub fn sign_with<E: PairingEngine>(s: &Scalar, G1: &E::G1, data: &[&[u8]]) -> Result<Self, CryptoError> {
+        let P1_e = E::g1_mul(G1, s);
+        let P1 = E::g1_to_affine(&P1_e)?;
+        let sig = Signature::sign_with::<E>(s, G1, &P1_e, data);
+        Ok(Self { P1, sig })
+    }
+
+    pub fn verify_with<E: PairingEngine>(&self, G1: &E::G1, data: &[&[u8]]) -> bool {
+        self.sig.verify_with::<E>(G1, &E::g1_from_affine(&self.P1), data)
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Chaum-Pedersen proof of equality of discrete logarithms, log_G1(P1) == log_B(Y)
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct EqProof {
+    pub c: Scalar,
+    pub p: Scalar
+}
+
+impl EqProof {
+    pub fn prove(s: &Scalar, G1: &G1Affine, P1: &G1Affine, B: &G1Affine, Y: &G1Affine) -> Self {
+        let B_comp = B.to_compressed();
+        let Y_comp = Y.to_compressed();
+
+        let hasher = Sha512::new()
+            .chain(s.to_bytes())
+            .chain(B_comp.as_ref())
+            .chain(Y_comp.as_ref());
+
+        let mut result = [0u8; 64];
+        result.copy_from_slice(&hasher.result()[0..64]);
+
+        let m = Scalar::from_bytes_wide(&result);
+        let M1: G1Affine = (G1 * m).into();
+        let M2: G1Affine = (B * m).into();
+
+        let c = hash_eq(G1, P1, B, Y, &M1, &M2);
+
+        Self { c, p: m - c * s }
+    }
+
+    pub fn verify(&self, G1: &G1Affine, P1: &G1Affine, B: &G1Affine, Y: &G1Affine) -> bool {
+        if [G1, P1, B, Y].iter().any(|P| check_public_g1(P).is_err()) {
+            return false
+        }
+
+        let M1: G1Affine = (P1 * self.c + G1 * self.p).into();
+        let M2: G1Affine = (Y * self.c + B * self.p).into();
+
+        let c = hash_eq(G1, P1, B, Y, &M1, &M2);
+        c == self.c
+    }
+}
+
+fn hash_eq(G1: &G1Affine, P1: &G1Affine, B: &G1Affine, Y: &G1Affine, M1: &G1Affine, M2: &G1Affine) -> Scalar {
+    let points = [G1, P1, B, Y, M1, M2].iter().map(|P| P.to_compressed()).collect::<Vec<_>>();
+    let all = points.iter().map(|P| P.as_ref()).collect::<Vec<_>>();
+    hash(&all)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::rnd_scalar;
+    use crate::crypto::*;
 
     #[test]
     fn correct() {
@@ -120,4 +339,188 @@ mod tests {
         let data2 = &[d0.as_ref(), d2.as_ref()];
         assert!(sig.verify(&G1, data2) == false);
     }
+
+    #[test]
+    fn single_message() {
+        let G1 = G1Affine::generator();
+        let s = rnd_scalar();
+        let msg = rnd_scalar().to_bytes();
+
+        let sig = ExtSignature::sign(&s, &G1, &[msg.as_ref()]);
+        assert!(sig.verify_msg(&G1, &msg) && sig.verify(&G1, &[msg.as_ref()]));
+        assert!(!sig.verify_msg(&G1, b"other") && !sig.verify(&G1, &[b"other"]));
+    }
+
+    #[test]
+    fn same_challenge() {
+        let G1 = G1Affine::generator();
+        let s = rnd_scalar();
+        let P1: G1Affine = (G1 * s).into();
+        let data: &[&[u8]] = &[b"chunk-1", b"chunk-2"];
+
+        // the commitment M of sign, recomputed by verify from (c, p)
+        let sig = Signature::sign(&s, &G1, &P1, data);
+        let M: G1Affine = (G1 * nonce(&s, data)).into();
+        assert!(M == (P1 * sig.c + G1 * sig.p).into());
+
+        let c = hash_c(&G1, &P1, &M, data);
+        assert!(c == sig.c && sig.verify_with_challenge(&G1, &P1, data).1 == c);
+
+        // the streaming path gives the same challenge for the same inputs
+        let mut hasher = challenge_hasher(&G1.to_compressed(), &P1.to_compressed(), &M.to_compressed());
+        hasher.input(b"chunk-1chunk-2");
+        assert!(challenge(hasher) == c);
+    }
+
+    #[test]
+    fn recomputed_challenge() {
+        let G1 = G1Affine::generator();
+        let s = rnd_scalar();
+        let P1: G1Affine = (G1 * s).into();
+
+        let d0 = rnd_scalar().to_bytes();
+        let sig = Signature::sign(&s, &G1, &P1, &[d0.as_ref()]);
+
+        let (valid, c) = sig.verify_with_challenge(&G1, &P1, &[d0.as_ref()]);
+        assert!(valid && c == sig.c);
+
+        let (valid, c) = sig.verify_with_challenge(&G1, &P1, &[b"other"]);
+        assert!(!valid && c != sig.c);
+    }
+
+    #[test]
+    fn engine_signatures() {
+        let G1 = G1Affine::generator();
+        let s = rnd_scalar();
+
+        let d0 = rnd_scalar().to_bytes();
+        let data = &[d0.as_ref()];
+        let sig = ExtSignature::sign(&s, &G1, data);
+
+        let G1_e = Bls12Engine::g1_generator();
+        let P1_e = Bls12Engine::g1_mul(&G1_e, &s);
+        let sig_e = Signature::sign_with::<Bls12Engine>(&s, &G1_e, &P1_e, data);
+        assert!(sig_e.c == sig.sig.c && sig_e.p == sig.sig.p);
+        assert!(sig.sig.verify_with::<Bls12Engine>(&G1_e, &P1_e, data));
+        let ext_e = ExtSignature::sign_with::<Bls12Engine>(&s, &G1_e, data).unwrap();
+        assert!(ext_e.P1 == sig.P1 && ext_e.sig.c == sig.sig.c && ext_e.sig.p == sig.sig.p);
+        assert!(sig.verify_with::<Bls12Engine>(&G1_e, data));
+
+        #[cfg(feature = "blst")] {
+            let G1_b = BlstEngine::g1_generator();
+            let P1_b = BlstEngine::g1_mul(&G1_b, &s);
+            let sig_b = Signature::sign_with::<BlstEngine>(&s, &G1_b, &P1_b, data);
+            assert!(sig_b.c == sig.sig.c && sig_b.p == sig.sig.p);
+            assert!(sig.sig.verify_with::<BlstEngine>(&G1_b, &P1_b, data));
+            let ext_b = ExtSignature::sign_with::<BlstEngine>(&s, &G1_b, data).unwrap();
+            assert!(ext_b.P1 == sig.P1 && ext_b.sig.c == sig.sig.c && ext_b.sig.p == sig.sig.p);
+            assert!(sig.verify_with::<BlstEngine>(&G1_b, data));
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let G1 = G1Affine::generator();
+        let s = rnd_scalar();
+        let P1: G1Affine = (G1 * s).into();
+
+        let payload = (0..10_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        let mut signer = Signature::signer(&s, &G1, &P1);
+        for chunk in payload.chunks(1000) {
+            signer.update(chunk);
+        }
+
+        let sig = signer.finalize();
+        assert!(sig.verify(&G1, &P1, &[&payload]));
+        assert!(!sig.verify(&G1, &P1, &[&payload[1..]]));
+
+        // one-shot signature checked with chunks of a different size
+        let sig = Signature::sign(&s, &G1, &P1, &[&payload]);
+        let mut verifier = sig.verifier(&G1, &P1);
+        for chunk in payload.chunks(333) {
+            verifier.update(chunk);
+        }
+        assert!(verifier.finalize());
+
+        let mut verifier = sig.verifier(&G1, &P1);
+        verifier.update(&payload[..9999]);
+        assert!(!verifier.finalize());
+    }
+
+    #[test]
+    fn hash_function() {
+        let data: &[&[u8]] = &[b"tat", b"adr"];
+        let mut sha = [0u8; 64];
+        sha.copy_from_slice(&Sha512::new().chain(b"tatadr").result());
+
+        // the selected function is the one in use, SHA-512 unless "blake2b" is enabled
+        let is_sha = hash(data) == Scalar::from_bytes_wide(&sha);
+        assert!(is_sha == cfg!(not(feature = "blake2b")));
+        assert!(hash(data) == hash(&[b"tatadr"]));
+
+        // and signatures round-trip with it, also through the streaming challenge
+        let G1 = G1Affine::generator();
+        let s = rnd_scalar();
+        let P1: G1Affine = (G1 * s).into();
+        let sig = Signature::sign(&s, &G1, &P1, data);
+        assert!(sig.verify(&G1, &P1, data));
+
+        let mut verifier = sig.verifier(&G1, &P1);
+        verifier.update(b"tatadr");
+        assert!(verifier.finalize());
+    }
+
+    #[test]
+    fn identity_key() {
+        let G1 = G1Affine::generator();
+        let O = G1Affine::identity();
+        let data: &[&[u8]] = &[b"anything"];
+
+        // a "signature" for the identity key without any secret, c = H(G1, O, p*G1, data) closes the equation
+        let p = rnd_scalar();
+        let c = hash_c(&G1, &O, &(G1 * p).into(), data);
+        let forged = Signature { c, p };
+        assert!(!forged.verify(&G1, &O, data));
+        assert!(!ExtSignature { P1: O, sig: forged.clone() }.verify(&G1, data));
+
+        let mut verifier = forged.verifier(&G1, &O);
+        verifier.update(data[0]);
+        assert!(!verifier.finalize());
+
+        assert!(check_public_g1(&O) == Err(CryptoError::IdentityPoint));
+        assert!(check_public_g1(&G1).is_ok());
+
+        // the same forgery on the engine path
+        let (G1_e, O_e) = (Bls12Engine::g1_generator(), Bls12Engine::g1_identity());
+        assert!(!forged.verify_with::<Bls12Engine>(&G1_e, &O_e, data));
+        assert!(!ExtSignature { P1: O, sig: forged.clone() }.verify_with::<Bls12Engine>(&G1_e, data));
+
+        #[cfg(feature = "blst")] {
+            let (G1_b, O_b) = (BlstEngine::g1_generator(), BlstEngine::g1_identity());
+            assert!(!forged.verify_with::<BlstEngine>(&G1_b, &O_b, data));
+            assert!(!ExtSignature { P1: O, sig: forged }.verify_with::<BlstEngine>(&G1_b, data));
+        }
+    }
+
+    #[test]
+    fn eq_proof() {
+        let G1 = G1Affine::generator();
+        let B: G1Affine = (G1 * rnd_scalar()).into();
+
+        let s = rnd_scalar();
+        let P1: G1Affine = (G1 * s).into();
+        let Y: G1Affine = (B * s).into();
+
+        let proof = EqProof::prove(&s, &G1, &P1, &B, &Y);
+        assert!(proof.verify(&G1, &P1, &B, &Y));
+
+        // Y is not B * s
+        let Y_forged: G1Affine = (B * rnd_scalar()).into();
+        assert!(!proof.verify(&G1, &P1, &B, &Y_forged));
+
+        // proof produced with a secret unrelated to the commitment
+        let forged = EqProof::prove(&rnd_scalar(), &G1, &P1, &B, &Y_forged);
+        assert!(!forged.verify(&G1, &P1, &B, &Y_forged));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,207 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::crypto::{CryptoError, Decoder, Encoder, PointEncoding, Polynomial, Share, ShareVector};
+
+use clear_on_drop::clear::Clear;
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    Decryption,
+    Encoding(CryptoError),
+    DuplicateIndex(u32)
+}
+
+impl From<io::Error> for StorageError {
+    fn from(err: io::Error) -> Self {
+        StorageError::Io(err)
+    }
+}
+
+impl From<CryptoError> for StorageError {
+    fn from(err: CryptoError) -> Self {
+        StorageError::Encoding(err)
+    }
+}
+
+// NOTE: the plaintext is cleared after the hook runs. The hook is caller-supplied, so key management stays outside.
+fn seal<F: Fn(&[u8]) -> Vec<u8>>(mut plain: Vec<u8>, encrypt: F) -> Vec<u8> {
+    let sealed = encrypt(&plain);
+    plain.as_mut_slice().clear();
+    sealed
+}
+
+fn share_file(i: u32) -> String {
+    format!("share-{}.bin", i)
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Share
+//-----------------------------------------------------------------------------------------------------------
+impl Share {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(PointEncoding::Compressed);
+        encoder.u32(self.i).scalar(&self.yi);
+        encoder.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let mut decoder = Decoder::new(bytes)?;
+        let i = decoder.u32()?;
+        let yi = decoder.scalar()?;
+        decoder.finish()?;
+
+        Ok(Self { i, yi })
+    }
+
+    // NOTE: the hook receives the party index, so each party can have its own key
+    pub fn write_file<F: Fn(u32, &[u8]) -> Vec<u8>>(&self, path: &Path, encrypt: F) -> Result<(), StorageError> {
+        let sealed = seal(self.to_bytes(), |plain| encrypt(self.i, plain));
+        fs::write(path, sealed)?;
+        Ok(())
+    }
+
+    pub fn read_file<F: Fn(u32, &[u8]) -> Option<Vec<u8>>>(path: &Path, i: u32, decrypt: F) -> Result<Self, StorageError> {
+        let sealed = fs::read(path)?;
+        let mut plain = decrypt(i, &sealed).ok_or(StorageError::Decryption)?;
+        let share = Share::from_bytes(&plain);
+        plain.as_mut_slice().clear();
+
+        // the file must hold the share of the expected party
+        match share? {
+            share if share.i == i => Ok(share),
+            _ => Err(StorageError::Encoding(CryptoError::InvalidEncoding))
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// ShareVector
+//-----------------------------------------------------------------------------------------------------------
+impl ShareVector {
+    // NOTE: one file per party, named share-<i>.bin. Other names are ignored on read, also non-canonical forms of an
+    // index (e.g. share-01.bin), which would otherwise load the share of share-1.bin twice.
+    pub fn write_files<F: Fn(u32, &[u8]) -> Vec<u8>>(&self, dir: &Path, encrypt: F) -> Result<(), StorageError> {
+        fs::create_dir_all(dir)?;
+        for share in self.0.iter() {
+            share.write_file(&dir.join(share_file(share.i)), &encrypt)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_files<F: Fn(u32, &[u8]) -> Option<Vec<u8>>>(dir: &Path, decrypt: F) -> Result<Self, StorageError> {
+        let mut shares = Vec::<Share>::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = match name.to_str() {
+                Some(name) => name,
+                None => continue
+            };
+
+            let index = name.strip_prefix("share-")
+                .and_then(|name| name.strip_suffix(".bin"))
+                .and_then(|i| i.parse::<u32>().ok())
+                .filter(|i| share_file(*i) == name);
+
+            if let Some(i) = index {
+                shares.push(Share::read_file(&entry.path(), i, &decrypt)?);
+            }
+        }
+
+        shares.sort_by_key(|s| s.i);
+        if let Some(pair) = shares.windows(2).find(|pair| pair[0].i == pair[1].i) {
+            return Err(StorageError::DuplicateIndex(pair[0].i))
+        }
+
+        Ok(ShareVector(shares))
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Polynomial
+//-----------------------------------------------------------------------------------------------------------
+impl Polynomial {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(PointEncoding::Compressed);
+        encoder.u32(self.0.len() as u32);
+        for ak in self.0.iter() {
+            encoder.scalar(ak);
+        }
+
+        encoder.finish()
+    }
+
+    // NOTE: n = 0 is the zero polynomial, written by to_bytes as any other
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let mut decoder = Decoder::new(bytes)?;
+        let n = decoder.u32()?;
+        let coefs = (0..n).map(|_| decoder.scalar()).collect::<Result<Vec<_>, _>>()?;
+        decoder.finish()?;
+
+        Ok(Polynomial(coefs))
+    }
+
+    // NOTE: the dealer's own record of the polynomial
+    pub fn write_file<F: Fn(&[u8]) -> Vec<u8>>(&self, path: &Path, encrypt: F) -> Result<(), StorageError> {
+        fs::write(path, seal(self.to_bytes(), encrypt))?;
+        Ok(())
+    }
+
+    pub fn read_file<F: Fn(&[u8]) -> Option<Vec<u8>>>(path: &Path, decrypt: F) -> Result<Self, StorageError> {
+        let sealed = fs::read(path)?;
+        let mut plain = decrypt(&sealed).ok_or(StorageError::Decryption)?;
+        let poly = Polynomial::from_bytes(&plain);
+        plain.as_mut_slice().clear();
+
+        Ok(poly?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::*;
+
+    // NOTE: test-only encryptor, never use outside tests
+    fn identity(_: u32, plain: &[u8]) -> Vec<u8> {
+        plain.to_vec()
+    }
+
+    #[test]
+    fn files_round_trip() {
+        let dir = std::env::temp_dir().join(format!("tat-adr-storage-{}", std::process::id()));
+
+        let s = rnd_scalar();
+        let poly = Polynomial::rnd(s, 2);
+        let shares = poly.shares(4);
+
+        shares.write_files(&dir, identity).unwrap();
+        let loaded = ShareVector::read_files(&dir, |i, sealed| Some(identity(i, sealed))).unwrap();
+        assert!(loaded.0.len() == 4);
+        assert!(loaded.0.iter().zip(shares.0.iter()).all(|(a, b)| a.i == b.i && a.yi == b.yi));
+        assert!(loaded.interpolate() == s);
+
+        let path = dir.join("polynomial.bin");
+        poly.write_file(&path, |plain| identity(0, plain)).unwrap();
+        assert!(Polynomial::read_file(&path, |sealed| Some(identity(0, sealed))).unwrap() == poly);
+
+        let zero = Polynomial(Vec::new());
+        zero.write_file(&path, |plain| identity(0, plain)).unwrap();
+        assert!(Polynomial::read_file(&path, |sealed| Some(identity(0, sealed))).unwrap() == zero);
+
+        // a failing decryption hook
+        assert!(matches!(ShareVector::read_files(&dir, |_, _| None), Err(StorageError::Decryption)));
+
+        // non-canonical names of an index are ignored, the share is loaded once
+        fs::copy(dir.join("share-1.bin"), dir.join("share-01.bin")).unwrap();
+        fs::copy(dir.join("share-1.bin"), dir.join("share-+1.bin")).unwrap();
+        let loaded = ShareVector::read_files(&dir, |i, sealed| Some(identity(i, sealed))).unwrap();
+        assert!(loaded.0.len() == 4 && loaded.interpolate() == s);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use crate::crypto::{hash, pairing_eq, EqProof, Interpolate, PointShare, PointShareVector, Share, SharesError, G1_COMPRESSED_LEN};
+
+use sha2::{Sha512, Digest};
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Prepared};
+
+// NOTE: try-and-increment hash to G1, the discrete log of the result is unknown. Not constant-time, for public inputs only.
+pub fn hash_to_g1(data: &[&[u8]]) -> G1Affine {
+    for ctr in 0u32.. {
+        let mut hasher = Sha512::new()
+            .chain(b"hash-to-g1")
+            .chain(ctr.to_le_bytes());
+
+        for d in data {
+            hasher.input(d);
+        }
+
+        // compressed x coordinate, with the sign bit taken from the hash
+        let result = hasher.result();
+        let mut x = [0u8; G1_COMPRESSED_LEN];
+        x.copy_from_slice(&result[0..G1_COMPRESSED_LEN]);
+        x[0] = (x[0] & 0x3f) | 0x80;
+
+        if let Some(P) = Option::<G1Affine>::from(G1Affine::from_compressed_unchecked(&x)) {
+            let P = G1Projective::from(P).clear_cofactor();
+            if !bool::from(P.is_identity()) {
+                return P.into()
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Threshold VRF, Z = x*H(input) for a shared key x with public X2 = x*G2
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct VrfPartial {
+    pub share: PointShare,
+    pub proof: EqProof
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VrfOutput {
+    pub Z: G1Affine,
+    pub value: [u8; 32]
+}
+
+// NOTE: commitments are the Xi = xi*G1 of the key shares, used to check each partial evaluation
+#[derive(Debug, Clone)]
+pub struct ThresholdVrf {
+    pub threshold: usize,
+    pub G1: G1Projective,
+    pub X2: G2Affine,
+    pub commitments: PointShareVector
+}
+
+impl ThresholdVrf {
+    pub fn new(threshold: usize, X2: G2Affine, commitments: PointShareVector) -> Self {
+        Self { threshold, G1: G1Projective::generator(), X2, commitments }
+    }
+
+    pub fn base(input: &[u8]) -> G1Projective {
+        hash_to_g1(&[b"vrf", input]).into()
+    }
+
+    // NOTE: node side, Zi = xi*H(input) with a proof of log_G1(Xi) == log_H(Zi)
+    pub fn partial(share: &Share, input: &[u8]) -> VrfPartial {
+        let (share, proof) = PointShare::prove(share, &G1Projective::generator(), &Self::base(input));
+        VrfPartial { share, proof }
+    }
+
+    pub fn verify_partial(&self, input: &[u8], partial: &VrfPartial) -> bool {
+        match self.commitments.0.iter().find(|c| c.i == partial.share.i) {
+            Some(Ci) => partial.share.verify_proof(&partial.proof, &self.G1, &Ci.Yi, &Self::base(input)),
+            None => false
+        }
+    }
+
+    // NOTE: invalid partials are discarded and repeated ones count once, at least "threshold + 1" valid ones from
+    // distinct nodes are required
+    pub fn combine(&self, input: &[u8], partials: &[VrfPartial]) -> Result<VrfOutput, SharesError> {
+        let mut valid = Vec::<PointShare>::with_capacity(partials.len());
+        for partial in partials {
+            if !valid.iter().any(|s| s.i == partial.share.i) && self.verify_partial(input, partial) {
+                valid.push(partial.share);
+            }
+        }
+
+        if valid.len() < self.threshold + 1 {
+            return Err(SharesError::Unqualified)
+        }
+
+        let Z = G1Affine::from(PointShareVector(valid).interpolate());
+        Ok(VrfOutput { Z, value: Self::value(input, &Z) })
+    }
+
+    // NOTE: public verification, e(Z, G2) == e(H(input), X2)
+    pub fn verify(&self, input: &[u8], output: &VrfOutput) -> bool {
+        let H = G1Affine::from(Self::base(input));
+        let G2P: G2Prepared = G2Affine::generator().into();
+        let X2P: G2Prepared = self.X2.into();
+
+        output.value == Self::value(input, &output.Z) && pairing_eq(&output.Z, &G2P, &H, &X2P)
+    }
+
+    fn value(input: &[u8], Z: &G1Affine) -> [u8; 32] {
+        hash(&[b"vrf-output", input, &Z.to_compressed()]).to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::*;
+    use bls12_381::G2Projective;
+
+    #[test]
+    fn hash_to_curve() {
+        let H = hash_to_g1(&[b"input"]);
+        assert!(bool::from(H.is_torsion_free()) && bool::from(H.is_on_curve()));
+        assert!(H == hash_to_g1(&[b"input"]));
+        assert!(H != hash_to_g1(&[b"other"]));
+    }
+
+    #[test]
+    fn threshold_vrf() {
+        let threshold = 2;
+        let x = rnd_scalar();
+        let shares = Polynomial::rnd(x, threshold).shares(4);
+
+        let X2 = G2Affine::from(G2Projective::generator() * x);
+        let vrf = ThresholdVrf::new(threshold, X2, &shares * G1Projective::generator());
+
+        let input = b"epoch-42";
+        let mut partials = shares.0.iter().map(|s| ThresholdVrf::partial(s, input)).collect::<Vec<_>>();
+        assert!(partials.iter().all(|p| vrf.verify_partial(input, p)));
+
+        let output = vrf.combine(input, &partials[0..3]).unwrap();
+        assert!(output.Z == G1Affine::from(ThresholdVrf::base(input) * x));
+        assert!(vrf.verify(input, &output));
+        assert!(!vrf.verify(b"epoch-43", &output));
+
+        // a cheating node is discarded, and any qualified subset gives the same output
+        partials[0].share.Yi += G1Projective::generator();
+        assert!(!vrf.verify_partial(input, &partials[0]));
+        assert!(vrf.combine(input, &partials).unwrap() == output);
+        assert!(vrf.combine(input, &partials[0..3]).err() == Some(SharesError::Unqualified));
+
+        // a partial repeated by the same node counts once
+        let repeated = vec![partials[1].clone(), partials[1].clone(), partials[2].clone(), partials[2].clone()];
+        assert!(vrf.combine(input, &repeated).err() == Some(SharesError::Unqualified));
+
+        let repeated = vec![partials[1].clone(), partials[2].clone(), partials[1].clone(), partials[3].clone()];
+        assert!(vrf.combine(input, &repeated).unwrap() == output);
+
+        let forged = VrfOutput { Z: output.Z, value: [0u8; 32] };
+        assert!(!vrf.verify(input, &forged));
+    }
+}
@@ -0,0 +1,32 @@
+#![allow(non_snake_case)]
+#![allow(clippy::needless_lifetimes)]
+#![cfg_attr(test, allow(clippy::bool_comparison))]
+
+mod tatadr;
+pub use crate::tatadr::*;
+
+mod audit;
+pub use crate::audit::*;
+
+mod replay;
+pub use crate::replay::*;
+
+mod timer;
+pub use crate::timer::*;
+
+mod clock;
+pub use crate::clock::*;
+
+#[cfg(feature = "transcript")]
+mod transcript;
+#[cfg(feature = "transcript")]
+pub use crate::transcript::*;
+
+mod crypto;
+pub use crate::crypto::*;
+
+#[cfg(feature = "proto")]
+pub mod proto;
+
+#[cfg(feature = "cbor")]
+mod cbor;
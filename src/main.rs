@@ -1,127 +1,296 @@
 #![allow(non_snake_case)]
 
-mod tatadr;
-mod crypto;
+use tat_adr::*;
 
-use crate::tatadr::*;
-use crate::crypto::*;
-
-use clap::{Arg, App};
-use std::time::{Instant, Duration};
+use clap::{Arg, App, AppSettings, ArgMatches, SubCommand};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use bls12_381::G1Affine;
 
 fn main() {
+    let threshold = Arg::with_name("threshold")
+        .help("Sets the threshold number (t). The number of parties are set automatically to t+1.")
+        .short("t")
+        .long("threshold")
+        .takes_value(true);
+
+    let seed = Arg::with_name("seed")
+        .help("Seeds the RNG for reproducible runs.")
+        .long("seed")
+        .takes_value(true);
+
     let matches = App::new("Simulations for TAT-ADR")
         .version("1.0")
         .author("Micael Pedrosa <micaelpedrosa@ua.pt>")
         .about("Simulations and measurements for (Threshold access token for anonymous data resources)")
-        .arg(Arg::with_name("threshold")
-            .help("Sets the threshold number (t). The number of parties are set automatically to t+1.")
-            .required(true)
-            .short("t")
-            .long("threshold")
-            .takes_value(true))
-        .arg(Arg::with_name("runs")
-            .help("Sets the number of runs.")
-            .required(true)
-            .short("r")
-            .long("runs")
-            .takes_value(true))
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("simulate")
+            .about("Measures running times of the protocol")
+            .arg(threshold.clone().required_unless("sweep"))
+            .arg(seed.clone())
+            .arg(Arg::with_name("sweep")
+                .help("Runs the simulation for each threshold in t_min..t_max (inclusive) and writes a CSV.")
+                .long("sweep")
+                .conflicts_with("threshold")
+                .takes_value(true))
+            .arg(Arg::with_name("out")
+                .help("Writes the sweep CSV to a file instead of stdout.")
+                .long("out")
+                .requires("sweep")
+                .takes_value(true))
+            .arg(Arg::with_name("runs")
+                .help("Sets the number of runs.")
+                .required(true)
+                .short("r")
+                .long("runs")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("issue")
+            .about("Runs a single protocol. Prints the token and the network public key as hex, one per line.")
+            .arg(threshold.default_value("2"))
+            .arg(seed)
+            .arg(Arg::with_name("profile")
+                .help("Sets the profile name.")
+                .short("p")
+                .long("profile")
+                .default_value("EHR")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("verify")
+            .about("Verifies a token against the network public key")
+            .arg(Arg::with_name("token")
+                .help("The token as hex.")
+                .required(true)
+                .index(1))
+            .arg(Arg::with_name("network")
+                .help("The network public key as hex, as printed by issue.")
+                .required(true)
+                .short("n")
+                .long("network")
+                .takes_value(true)))
         .get_matches();
 
-    // setup parameters
-    let str_threshold = matches.value_of("threshold").unwrap();
-    let threshold = str_threshold.parse::<usize>().unwrap();
+    match matches.subcommand() {
+        ("simulate", Some(sub)) => simulate(sub),
+        ("issue", Some(sub)) => issue(sub),
+        ("verify", Some(sub)) => verify(sub),
+        _ => unreachable!()
+    }
+}
+
+// NOTE: the network rng is derived from this one, so a single seed fixes all random inputs
+fn rng(matches: &ArgMatches) -> ChaCha20Rng {
+    match matches.value_of("seed") {
+        Some(seed) => match seed.parse::<u64>() {
+            Ok(seed) => ChaCha20Rng::seed_from_u64(seed),
+            Err(_) => fail("invalid seed, expected an unsigned integer")
+        },
+        None => ChaCha20Rng::from_entropy()
+    }
+}
+
+// NOTE: start time of seeded runs, 2020-09-13T12:26:40Z
+const SEEDED_TIME: u64 = 1_600_000_000;
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
 
-    let str_runs = matches.value_of("runs").unwrap();
-    let runs = str_runs.parse::<usize>().unwrap();
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes().chunks(2)
+        .map(|pair| match pair.len() {
+            2 => u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok(),
+            _ => None
+        })
+        .collect()
+}
+
+// NOTE: the public key is (Y, A2) compressed
+fn network_key(setup: &NetworkSetup) -> Vec<u8> {
+    let mut bytes = G1Affine::from(setup.Y).to_compressed().to_vec();
+    bytes.extend_from_slice(&setup.A2A.to_compressed());
+    bytes
+}
+
+fn issue(matches: &ArgMatches) {
+    let threshold = matches.value_of("threshold").unwrap().parse::<usize>().unwrap();
+    let profile = matches.value_of("profile").unwrap();
+    let location = "Hospital";
+
+    let mut rng = rng(matches);
+    let network = ChaCha20Rng::from_rng(&mut rng).unwrap();
+    let l = rnd_scalar_with(&mut rng);
+    let r = rnd_scalar_with(&mut rng);
+    let st = rnd_scalar_with(&mut rng);
+    let k = rnd_scalar_with(&mut rng);
+
+    let mut setup = NetworkSetup::with_rng(threshold, network);
+    setup.location(location, setup.Y * l);
+    setup.profile(profile, location, setup.G1 * r, setup.A1 * r).unwrap();
+
+    // NOTE: a seeded run also fixes the start time and the network clock, the token is reproducible
+    let time = match matches.value_of("seed") {
+        Some(_) => SystemTime::UNIX_EPOCH + Duration::from_secs(SEEDED_TIME),
+        None => SystemTime::now()
+    };
+    setup.set_clock(Box::new(FixedClock(time)));
+
+    // client init
+    let seq = 1usize;
+    let session = SessionId::new(seq, time);
+
+    let seq_bytes = seq.to_le_bytes();
+    let time_str = encode_time(time);
+    let data = &[profile.as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()];
+    let sig = ExtSignature::sign(&st, &setup.G1.into(), data);
+
+    // start session (round 1), the shares are checked before interpolation
+    let ((Mi, PIi), commitments) = setup.start_verifiable(sig, profile, seq, time).unwrap();
+    commitments.verify(&Mi, &PIi).expect("Invalid start shares!");
+    let round1 = client_round1(&Mi, &PIi, &k, setup.public_params());
+
+    // request token (round 2)
+    let Tki = setup.request(session.as_str(), &round1.Akc, &round1.Kc).unwrap();
+    let claims = Claims { nonce: rnd_scalar_with(&mut rng).to_bytes(), issued_at: unix_time(time), ..Claims::default() };
+    let token = Token::with_claims(k, Tki.interpolate().into(), round1.M, round1.PI, claims, &setup.public_params().setup_id);
+
+    // verify token (round 3)
+    assert!(token.verify(setup.public_params()));
+
+    println!("{}", to_hex(&token.to_bytes()));
+    println!("{}", to_hex(&network_key(&setup)));
+}
 
-    println!("Setup: (threshold: {}, runs: {})", threshold, runs);
+fn verify(matches: &ArgMatches) {
+    let token = from_hex(matches.value_of("token").unwrap())
+        .and_then(|bytes| Token::from_bytes(&bytes).ok());
 
+    let key = from_hex(matches.value_of("network").unwrap()).filter(|key| key.len() == G1_COMPRESSED_LEN + G2_COMPRESSED_LEN);
+    let params = key.and_then(|key| {
+        let (Y, A2) = key.split_at(G1_COMPRESSED_LEN);
+        Some(PublicParams::from_keys(decompress_g1_slice(Y).ok()?, decompress_g2_slice(A2).ok()?))
+    });
+
+    match (token, params) {
+        (None, _) => fail("invalid token encoding"),
+        (_, None) => fail("invalid network key encoding"),
+        (Some(token), Some(params)) if token.verify(&params) => println!("valid"),
+        _ => fail("invalid token")
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    std::process::exit(1);
+}
+
+fn simulate(matches: &ArgMatches) {
+    let runs = matches.value_of("runs").unwrap().parse::<usize>().unwrap();
+    let mut rng = rng(matches);
+
+    let sweep = match matches.value_of("sweep") {
+        None => {
+            let threshold = matches.value_of("threshold").unwrap().parse::<usize>().unwrap();
+            println!("Setup: (threshold: {}, runs: {})", threshold, runs);
+
+            let stats = run(threshold, runs, &mut rng);
+            println!("Stats: (init: {:.3}ms, start-net: {:.3}ms, start-cli: {:.3}ms, request-net: {:.3}ms, request-cli: {:.3}ms, verify: {:.3}ms, total: {:.3}ms, single-thread: {:.3}ms)",
+                stats.init, stats.start_net, stats.start_cli, stats.request_net, stats.request_cli, stats.verify, stats.total(), stats.single);
+            return
+        },
+        Some(sweep) => sweep
+    };
+
+    // NOTE: inclusive range "t_min..t_max"
+    let range = sweep.split("..").map(|t| t.parse::<usize>()).collect::<Result<Vec<_>, _>>();
+    let (t_min, t_max) = match range.as_ref().map(|r| r.as_slice()) {
+        Ok([t_min, t_max]) if t_min <= t_max => (*t_min, *t_max),
+        _ => fail("invalid sweep, expected t_min..t_max")
+    };
+
+    let mut out: Box<dyn Write> = match matches.value_of("out") {
+        Some(path) => Box::new(File::create(path).unwrap()),
+        None => Box::new(io::stdout())
+    };
+
+    writeln!(out, "threshold,init,start-net,start-cli,request-net,request-cli,verify,total").unwrap();
+    for threshold in t_min..=t_max {
+        let stats = run(threshold, runs, &mut rng);
+        writeln!(out, "{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3}", threshold,
+            stats.init, stats.start_net, stats.start_cli, stats.request_net, stats.request_cli, stats.verify, stats.total()).unwrap();
+    }
+}
+
+// NOTE: average times in ms, network times are latencies with the nodes in parallel. "single" is the measured time with
+// all nodes in one thread.
+struct Stats {
+    init: f64,
+    start_net: f64,
+    start_cli: f64,
+    request_net: f64,
+    request_cli: f64,
+    verify: f64,
+    single: f64
+}
+
+impl Stats {
+    fn total(&self) -> f64 {
+        self.init + self.start_net + self.start_cli + self.request_net + self.request_cli + self.verify
+    }
+}
+
+// NOTE: the network setup is excluded from the measured rounds
+fn run(threshold: usize, runs: usize, rng: &mut ChaCha20Rng) -> Stats {
     // setup private keys
-    let l = rnd_scalar();  // location key
-    let r = rnd_scalar();  // profile key
-    let st = rnd_scalar(); // client key
+    let network = ChaCha20Rng::from_rng(&mut *rng).unwrap();
+    let l = rnd_scalar_with(rng);  // location key
+    let r = rnd_scalar_with(rng);  // profile key
+    let st = rnd_scalar_with(rng); // client key
 
     // setup network
     let profile = "EHR";
     let location = "Hospital";
 
-    let mut setup = NetworkSetup::new(threshold);
+    let mut setup = NetworkSetup::with_rng(threshold, network);
     setup.location(location, setup.Y * l);
-    setup.profile(profile, location, setup.G1 * r, setup.A1 * r);
+    setup.profile(profile, location, setup.G1 * r, setup.A1 * r).unwrap();
+
+    // setup client, the token keys are drawn from the seeded rng
+    let mut client = Client::with_rng(st, setup.public_params().clone(), ChaCha20Rng::from_rng(&mut *rng).unwrap());
 
-    // collect stats for runs
-    let mut c_init = Duration::from_millis(0);
-    let mut round1_1 = Duration::from_millis(0);
-    let mut round1_2 = Duration::from_millis(0);
-    let mut round2_1 = Duration::from_millis(0);
-    let mut round2_2 = Duration::from_millis(0);
-    let mut round3 = Duration::from_millis(0);
+    // NOTE: the simulation runs the work of all nodes in a single thread, the timer accounts the work of each node
+    let timer = Arc::new(Mutex::new(PhaseTimer::default()));
+    setup.set_timer(Box::new(timer.clone()));
+    client.set_timer(Box::new(timer.clone()));
 
     let mut seq = 1usize;
     for _ in 0..runs {
-        let init = Instant::now();
-            // client init
-            seq += 1;
-            let time = Instant::now();
-            let session = format!("{}-{:?}", seq, time);
-            let k = rnd_scalar(); // client-token key
-
-            let seq_bytes = seq.to_le_bytes();
-            let time_str = format!("{:?}", time);
-            let data = &[profile.as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()];
-            let sig = ExtSignature::sign(&st, &setup.G1.into(), data);
-        let c_init_i = Instant::now() - init;
-
-        let init = Instant::now();
-            // start session (round 1)
-                let (Mi, PIi) = setup.start(sig, profile, seq, time);
-            let round1_1_i = Instant::now() - init;
-
-                let M = Mi.interpolate();
-                let Mk = M * k;
-                let PI = PIi.interpolate();
-
-                let M_comp = G1Affine::from(M).to_compressed();
-                let Mk_comp = G1Affine::from(Mk).to_compressed();
-                let PI_comp = G1Affine::from(PI).to_compressed();
-
-                let c = hash(&[&M_comp, &Mk_comp, &PI_comp]);
-                let Kc = setup.G1 * (k * c);
-                let Akc = setup.A1 * (k * c);
-            let round1_2_i = (Instant::now() - init) - round1_1_i;
-
-            // request token (round 2)
-                let Tki = setup.request(&session, &Akc.into(), &Kc.into());
-            let round2_1_i = (Instant::now() - init) - round1_1_i - round1_2_i;
-
-                let Tk = Tki.interpolate();
-                let token = Token::new(k, Tk.into(), M.into(), PI.into());
-            let round2_2_i = (Instant::now() - init) - round1_1_i - round1_2_i - round2_1_i;
-
-            // verify token (round 3)
-                assert!(token.verify(&setup));
-            let round3_i = (Instant::now() - init) - round1_1_i - round1_2_i - round2_1_i - round2_2_i;
-
-        c_init += c_init_i;
-        round1_1 += round1_1_i;
-        round1_2 += round1_2_i;
-        round2_1 += round2_1_i;
-        round2_2 += round2_2_i;
-        round3 += round3_i;
-    }
+        seq += 1;
+        let time = SystemTime::now();
+
+        // client init
+        let (sig, _) = client.start_request(profile, seq, time);
+
+        // start session (round 1)
+        let (Mi, PIi) = setup.start(sig, profile, seq, time).unwrap();
+        let msg = client.complete_round1(&Mi, &PIi).unwrap();
 
-    // NOTE: "start" and "request" are simulated in a single thread, but in reality this is a parallel task. It must be divided by (t + 1)
-    let stat_init = (c_init/runs as u32).as_micros() as f64/1000.0;
-    let stat1_1 = (round1_1/runs as u32).as_micros() as f64/(1000.0 * (threshold + 1) as f64);
-    let stat1_2 = (round1_2/runs as u32).as_micros() as f64/1000.0;
-    let stat2_1 = (round2_1/runs as u32).as_micros() as f64/(1000.0 * (threshold + 1) as f64);
-    let stat2_2 = (round2_2/runs as u32).as_micros() as f64/1000.0;
-    let stat3 = (round3/runs as u32).as_micros() as f64/1000.0;
-    let stat_total = stat_init + stat1_1 + stat1_2 + stat2_1 + stat2_2 + stat3;
+        // request token (round 2)
+        let Tki = setup.request(msg.session.as_str(), &msg.Akc, &msg.Kc).unwrap();
+        let token = client.finalize(&Tki).unwrap();
 
+        // verify token (round 3)
+        assert!(client.verify(&token));
+    }
+
+    let timer = timer.lock().unwrap();
+    let ms = |phase: Phase| (timer.latency(phase) / runs as u32).as_micros() as f64 / 1000.0;
 
-    println!("Stats: (init: {:.3}ms, start-net: {:.3}ms, start-cli: {:.3}ms, request-net: {:.3}ms, request-cli: {:.3}ms, verify: {:.3}ms, total: {:.3}ms)",
-        stat_init, stat1_1, stat1_2, stat2_1, stat2_2, stat3, stat_total);
+    Stats {
+        init: ms(Phase::ClientInit), start_net: ms(Phase::NetworkStart), start_cli: ms(Phase::ClientStart),
+        request_net: ms(Phase::NetworkRequest), request_cli: ms(Phase::ClientRequest), verify: ms(Phase::ClientVerify),
+        single: (timer.elapsed() / runs as u32).as_micros() as f64 / 1000.0
+    }
 }
@@ -0,0 +1,261 @@
+// NOTE: prost messages for proto/tatadr.proto. These are kept in sync by hand, so the build doesn't depend on protoc.
+use std::convert::TryFrom;
+
+use crate::crypto::{self, decode_scalar_slice, decompress_g1_slice, CryptoError};
+use crate::tatadr::{self, Claims};
+use bls12_381::{G1Affine, G1Projective};
+
+//-----------------------------------------------------------------------------------------------------------
+// Messages
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PointShare {
+    #[prost(uint32, tag = "1")]
+    pub i: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub point: Vec<u8>
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Signature {
+    #[prost(bytes = "vec", tag = "1")]
+    pub c: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub p: Vec<u8>
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExtSignature {
+    #[prost(bytes = "vec", tag = "1")]
+    pub public_key: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub sig: Option<Signature>
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StartRequest {
+    #[prost(message, optional, tag = "1")]
+    pub sig: Option<ExtSignature>,
+    #[prost(string, tag = "2")]
+    pub profile: String,
+    #[prost(uint64, tag = "3")]
+    pub seq: u64,
+    #[prost(string, tag = "4")]
+    pub time: String
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StartReply {
+    #[prost(message, repeated, tag = "1")]
+    pub mi: Vec<PointShare>,
+    #[prost(message, repeated, tag = "2")]
+    pub pii: Vec<PointShare>
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RequestMsg {
+    #[prost(string, tag = "1")]
+    pub session: String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub akc: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub kc: Vec<u8>
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RequestReply {
+    #[prost(message, repeated, tag = "1")]
+    pub tki: Vec<PointShare>
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Token {
+    #[prost(bytes = "vec", tag = "1")]
+    pub tk: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub m: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub pi: Vec<u8>,
+    #[prost(message, optional, tag = "4")]
+    pub sig: Option<ExtSignature>,
+    #[prost(bytes = "vec", repeated, tag = "5")]
+    pub attrs: Vec<Vec<u8>>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub holder: Vec<u8>,
+    #[prost(bytes = "vec", tag = "7")]
+    pub scope: Vec<u8>,
+    #[prost(bytes = "vec", tag = "8")]
+    pub nonce: Vec<u8>,
+    #[prost(uint64, tag = "9")]
+    pub issued_at: u64
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Field encodings, the canonical point/scalar byte forms
+//-----------------------------------------------------------------------------------------------------------
+fn g1(P: &G1Affine) -> Vec<u8> {
+    P.to_compressed().to_vec()
+}
+
+fn shares(shares: &crypto::PointShareVector) -> Vec<PointShare> {
+    shares.0.iter().map(|s| PointShare { i: s.i, point: g1(&s.Yi.into()) }).collect()
+}
+
+fn from_shares(shares: &[PointShare]) -> Result<crypto::PointShareVector, CryptoError> {
+    let shares = shares.iter()
+        .map(|s| Ok(crypto::PointShare { i: s.i, Yi: G1Projective::from(decompress_g1_slice(&s.point)?) }))
+        .collect::<Result<Vec<_>, CryptoError>>()?;
+
+    Ok(crypto::PointShareVector(shares))
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Conversions
+//-----------------------------------------------------------------------------------------------------------
+impl From<&crypto::ExtSignature> for ExtSignature {
+    fn from(sig: &crypto::ExtSignature) -> Self {
+        let inner = Signature { c: sig.sig.c.to_bytes().to_vec(), p: sig.sig.p.to_bytes().to_vec() };
+        Self { public_key: g1(&sig.P1), sig: Some(inner) }
+    }
+}
+
+impl TryFrom<&ExtSignature> for crypto::ExtSignature {
+    type Error = CryptoError;
+
+    fn try_from(msg: &ExtSignature) -> Result<Self, CryptoError> {
+        let sig = msg.sig.as_ref().ok_or(CryptoError::InvalidEncoding)?;
+        let sig = crypto::Signature { c: decode_scalar_slice(&sig.c)?, p: decode_scalar_slice(&sig.p)? };
+        Ok(Self { P1: decompress_g1_slice(&msg.public_key)?, sig })
+    }
+}
+
+impl StartRequest {
+    // NOTE: the time is sent as it was signed, see encode_time
+    pub fn new(sig: &crypto::ExtSignature, profile: &str, seq: usize, time: &str) -> Self {
+        Self { sig: Some(sig.into()), profile: profile.into(), seq: seq as u64, time: time.into() }
+    }
+
+    pub fn signature(&self) -> Result<crypto::ExtSignature, CryptoError> {
+        let sig = self.sig.as_ref().ok_or(CryptoError::InvalidEncoding)?;
+        crypto::ExtSignature::try_from(sig)
+    }
+}
+
+impl StartReply {
+    pub fn new(Mi: &crypto::PointShareVector, PIi: &crypto::PointShareVector) -> Self {
+        Self { mi: shares(Mi), pii: shares(PIi) }
+    }
+
+    pub fn shares(&self) -> Result<(crypto::PointShareVector, crypto::PointShareVector), CryptoError> {
+        Ok((from_shares(&self.mi)?, from_shares(&self.pii)?))
+    }
+}
+
+impl RequestMsg {
+    pub fn new(session: &str, Akc: &G1Affine, Kc: &G1Affine) -> Self {
+        Self { session: session.into(), akc: g1(Akc), kc: g1(Kc) }
+    }
+
+    pub fn points(&self) -> Result<(G1Affine, G1Affine), CryptoError> {
+        Ok((decompress_g1_slice(&self.akc)?, decompress_g1_slice(&self.kc)?))
+    }
+}
+
+impl RequestReply {
+    pub fn new(Tki: &crypto::PointShareVector) -> Self {
+        Self { tki: shares(Tki) }
+    }
+
+    pub fn shares(&self) -> Result<crypto::PointShareVector, CryptoError> {
+        from_shares(&self.tki)
+    }
+}
+
+impl From<&tatadr::Token> for Token {
+    fn from(token: &tatadr::Token) -> Self {
+        Self {
+            tk: g1(&token.Tk), m: g1(&token.M), pi: g1(&token.PI),
+            sig: Some((&token.sig).into()),
+            attrs: token.claims.attrs.iter().map(|a| a.to_vec()).collect(),
+            holder: token.claims.holder.as_ref().map(g1).unwrap_or_default(),
+            scope: token.claims.scope.clone(),
+            nonce: token.claims.nonce.to_vec(),
+            issued_at: token.claims.issued_at
+        }
+    }
+}
+
+impl TryFrom<&Token> for tatadr::Token {
+    type Error = CryptoError;
+
+    fn try_from(msg: &Token) -> Result<Self, CryptoError> {
+        let sig = msg.sig.as_ref().ok_or(CryptoError::InvalidEncoding)?;
+        let attrs = msg.attrs.iter()
+            .map(|a| <[u8; 32]>::try_from(a.as_slice()).map_err(|_| CryptoError::InvalidLength))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let holder = if msg.holder.is_empty() { None } else { Some(decompress_g1_slice(&msg.holder)?) };
+        let nonce = <[u8; 32]>::try_from(msg.nonce.as_slice()).map_err(|_| CryptoError::InvalidLength)?;
+        let claims = Claims { attrs, holder, scope: msg.scope.clone(), nonce, issued_at: msg.issued_at };
+
+        Ok(Self {
+            Tk: decompress_g1_slice(&msg.tk)?, M: decompress_g1_slice(&msg.m)?, PI: decompress_g1_slice(&msg.pi)?,
+            claims, sig: crypto::ExtSignature::try_from(sig)?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::*;
+    use crate::tatadr::{encode_time, NetworkSetup, SessionId};
+    use prost::Message;
+    use std::time::SystemTime;
+
+    #[test]
+    fn round_trip() {
+        let mut setup = NetworkSetup::new(2);
+        let r = rnd_scalar();
+        setup.location("Hospital", setup.Y * rnd_scalar());
+        setup.profile("EHR", "Hospital", setup.G1 * r, setup.A1 * r).unwrap();
+
+        let (st, k) = (rnd_scalar(), rnd_scalar());
+        let time = SystemTime::now();
+        let time_str = encode_time(time);
+        let seq_bytes = 1usize.to_le_bytes();
+        let sig = crypto::ExtSignature::sign(&st, &setup.G1.into(), &["EHR".as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()]);
+
+        // start
+        let msg = StartRequest::decode(StartRequest::new(&sig, "EHR", 1, &time_str).encode_to_vec().as_slice()).unwrap();
+        assert!(msg.profile == "EHR" && msg.seq == 1 && msg.time == time_str);
+        let (Mi, PIi) = setup.start(msg.signature().unwrap(), &msg.profile, msg.seq as usize, time).unwrap();
+
+        let reply = StartReply::decode(StartReply::new(&Mi, &PIi).encode_to_vec().as_slice()).unwrap();
+        let (Mi, PIi) = reply.shares().unwrap();
+        let (M, PI) = (Mi.interpolate(), PIi.interpolate());
+
+        // request
+        let c = tatadr::token_challenge(&setup.public_params().setup_id, &M.into(), &(M * k).into(), &PI.into());
+        let (Akc, Kc) = (setup.A1 * (k * c), setup.G1 * (k * c));
+        let session = SessionId::new(1, time);
+        let msg = RequestMsg::decode(RequestMsg::new(session.as_str(), &Akc.into(), &Kc.into()).encode_to_vec().as_slice()).unwrap();
+        let (Akc, Kc) = msg.points().unwrap();
+        let Tki = setup.request(&msg.session, &Akc, &Kc).unwrap();
+
+        let reply = RequestReply::decode(RequestReply::new(&Tki).encode_to_vec().as_slice()).unwrap();
+        let Tk = reply.shares().unwrap().interpolate();
+        let token = tatadr::Token::new(k, Tk.into(), M.into(), PI.into(), &setup.public_params().setup_id);
+
+        // token
+        let bytes = Token::from(&token).encode_to_vec();
+        let res = tatadr::Token::try_from(&Token::decode(bytes.as_slice()).unwrap()).unwrap();
+        assert!(res.to_bytes() == token.to_bytes());
+        assert!(res.verify(setup.public_params()));
+
+        // truncated point
+        let mut msg = Token::decode(bytes.as_slice()).unwrap();
+        msg.tk.truncate(47);
+        assert!(tatadr::Token::try_from(&msg).err() == Some(CryptoError::InvalidLength));
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+//-----------------------------------------------------------------------------------------------------------
+// Store of seen token nonces, consulted by the resource server to reject replays
+//-----------------------------------------------------------------------------------------------------------
+pub trait NonceStore {
+    // NOTE: records the nonce, returns false if it was already seen and has not expired
+    fn insert(&mut self, nonce: [u8; 32], now: SystemTime) -> bool;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// NOTE: nonces are kept for "ttl" after first seen, this should match the token lifetime
+#[derive(Debug, Clone)]
+pub struct MemoryNonceStore {
+    ttl: Duration,
+    seen: HashMap<[u8; 32], SystemTime>
+}
+
+impl MemoryNonceStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, seen: HashMap::new() }
+    }
+}
+
+impl NonceStore for MemoryNonceStore {
+    fn insert(&mut self, nonce: [u8; 32], now: SystemTime) -> bool {
+        let ttl = self.ttl;
+        self.seen.retain(|_, seen| *seen + ttl > now);
+
+        if self.seen.contains_key(&nonce) {
+            return false
+        }
+
+        self.seen.insert(nonce, now);
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.seen.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry() {
+        let now = SystemTime::now();
+        let mut store = MemoryNonceStore::new(Duration::from_secs(60));
+
+        assert!(store.insert([1u8; 32], now));
+        assert!(!store.insert([1u8; 32], now + Duration::from_secs(59)));
+        assert!(store.insert([2u8; 32], now));
+        assert!(store.len() == 2);
+
+        // expired nonces are dropped
+        assert!(store.insert([1u8; 32], now + Duration::from_secs(60)));
+        assert!(store.len() == 1);
+    }
+}
@@ -1,75 +1,677 @@
 use std::collections::HashMap;
-use std::time::{Instant, Duration};
+use std::time::{Instant, Duration, SystemTime};
 
 use crate::crypto::*;
-use bls12_381::{pairing, Scalar, G1Affine, G1Projective, G2Affine, G2Projective, G2Prepared};
+use crate::audit::*;
+use crate::replay::*;
+use crate::timer::*;
+use crate::clock::*;
+#[cfg(feature = "transcript")]
+use crate::transcript::*;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+#[cfg(not(feature = "no-zeroize"))]
+use clear_on_drop::clear::Clear;
+use bls12_381::{Scalar, G1Affine, G1Projective, G2Affine, G2Projective, G2Prepared};
+
+//-----------------------------------------------------------------------------------------------------------
+// Attribute
+//-----------------------------------------------------------------------------------------------------------
+// NOTE: salted hash commitment, the commitment hides the attribute until the holder discloses (salt, name, value)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: String,
+    pub value: Vec<u8>,
+    pub salt: [u8; 32]
+}
+
+impl Attribute {
+    pub fn new(name: &str, value: &[u8]) -> Self {
+        Self { name: name.into(), value: value.into(), salt: rnd_scalar().to_bytes() }
+    }
+
+    pub fn commitment(&self) -> [u8; 32] {
+        hash(&[b"attribute", &self.salt, self.name.as_bytes(), &self.value]).to_bytes()
+    }
+}
+
+// NOTE: the digest d of the committed attributes is bound by the network into Tk as Ar + A1 * d, so PI + Y * d is certified
+pub fn attributes_digest(attrs: &[[u8; 32]]) -> Scalar {
+    if attrs.is_empty() {
+        return Scalar::zero()
+    }
+
+    let mut all = vec![b"attributes".as_ref()];
+    all.extend(attrs.iter().map(|a| a.as_ref()));
+    hash(&all)
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Session id, the key of a started session in the network
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionId(String);
+
+impl SessionId {
+    pub fn new(seq: usize, time: SystemTime) -> Self {
+        SessionId(format!("{}-{}", seq, encode_time(time)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // NOTE: per profile session of a batch start, each profile has its own mi shares
+    pub fn profile(&self, name: &str) -> SessionId {
+        SessionId(format!("{}/{}", self.0, name))
+    }
+}
+
+// NOTE: start time in the signed data and the session id, as unix seconds and nanoseconds. Times before the epoch
+// encode as zero, they are never fresh.
+pub fn encode_time(time: SystemTime) -> String {
+    let since = time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+    format!("{}.{:09}", since.as_secs(), since.subsec_nanos())
+}
+
+// NOTE: length-prefixed profile names, the signed data of a batch start
+pub fn encode_profiles(profiles: &[&str]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for name in profiles {
+        bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+
+    bytes
+}
+
+// NOTE: deterministic client-token key, a client can regenerate k from its seed for recovery or debugging
+pub fn derive_token_key(seed: &[u8], session: &SessionId) -> Scalar {
+    hash(&[b"token-key", seed, session.as_str().as_bytes()])
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Client rounds
+//-----------------------------------------------------------------------------------------------------------
+// NOTE: (M, PI) reconstructed from the start responses, the challenge c = H(setup, M, M*k, PI) and the request inputs
+// (Kc, Akc) = (k*c*G1, k*c*A1) for the request round
+#[derive(Debug, Clone)]
+pub struct Round1Output {
+    pub M: G1Affine,
+    pub PI: G1Affine,
+    pub c: Scalar,
+    pub Kc: G1Affine,
+    pub Akc: G1Affine
+}
+
+pub fn client_round1(Mi: &PointShareVector, PIi: &PointShareVector, k: &Scalar, params: &PublicParams) -> Round1Output {
+    let M = G1Affine::from(Mi.interpolate());
+    let PI = G1Affine::from(PIi.interpolate());
+    let Mk = G1Affine::from(M * k);
+
+    let c = token_challenge(&params.setup_id, &M, &Mk, &PI);
+    let commit = commit_token_key(k, &c, params);
+
+    Round1Output { M, PI, c, Kc: commit.Kc, Akc: commit.Akc }
+}
+
+// NOTE: the request inputs (Kc, Akc) = (kc*G1, kc*A1), with kc = k*c computed once. Compressed forms for transport.
+#[derive(Debug, Clone)]
+pub struct KeyCommitment {
+    pub Kc: G1Affine,
+    pub Akc: G1Affine,
+    pub Kc_comp: [u8; G1_COMPRESSED_LEN],
+    pub Akc_comp: [u8; G1_COMPRESSED_LEN]
+}
+
+pub fn commit_token_key(k: &Scalar, c: &Scalar, params: &PublicParams) -> KeyCommitment {
+    let kc = k * c;
+
+    let mut points = [G1Affine::identity(); 2];
+    G1Projective::batch_normalize(&[params.G1 * kc, params.A1 * kc], &mut points);
+    let [Kc, Akc] = points;
+
+    KeyCommitment { Kc, Akc, Kc_comp: Kc.to_compressed(), Akc_comp: Akc.to_compressed() }
+}
+
+// NOTE: Tk reconstructed from the request responses, with (M, PI) of round 1
+pub fn client_round2(Tki: &PointShareVector, k: Scalar, M: G1Affine, PI: G1Affine, params: &PublicParams) -> Token {
+    Token::new(k, Tki.interpolate().into(), M, PI, &params.setup_id)
+}
+
+// NOTE: client state machine over the rounds above, start_request -> complete_round1 -> finalize. Holds the client
+// key st, the token key k (fresh per request) and the round 1 output. Calls out of order give OutOfOrder.
+pub struct Client {
+    st: Scalar,
+    params: PublicParams,
+    session: Option<SessionId>,
+    k: Option<Scalar>,
+    round1: Option<Round1Output>,
+    timer: Box<dyn Timer + Send>,
+    rng: Box<dyn RngCore + Send>
+}
+
+// NOTE: the request inputs for the network, (Akc, Kc) of round 1
+#[derive(Debug, Clone)]
+pub struct Round1Msg {
+    pub session: SessionId,
+    pub Akc: G1Affine,
+    pub Kc: G1Affine
+}
+
+impl Client {
+    pub fn new(st: Scalar, params: PublicParams) -> Self {
+        Self::with_rng(st, params, ChaCha20Rng::from_entropy())
+    }
+
+    // NOTE: the token keys k are drawn from rng, a seeded rng gives reproducible requests
+    pub fn with_rng<R: RngCore + Send + 'static>(st: Scalar, params: PublicParams, rng: R) -> Self {
+        Self { st, params, session: None, k: None, round1: None, timer: Box::new(NoTimer), rng: Box::new(rng) }
+    }
+
+    // NOTE: times the ClientInit, ClientStart, ClientRequest and ClientVerify phases
+    pub fn set_timer(&mut self, timer: Box<dyn Timer + Send>) {
+        self.timer = timer;
+    }
+
+    // NOTE: the start signature over (profile, seq, time) and the session id of the pending request
+    pub fn start_request(&mut self, profile: &str, seq: usize, time: SystemTime) -> (ExtSignature, SessionId) {
+        self.timer.phase_start(Phase::ClientInit);
+        let time_str = encode_time(time);
+        let sig = ExtSignature::sign(&self.st, &self.params.G1.into(), &[profile.as_bytes(), &seq.to_le_bytes(), time_str.as_bytes()]);
+
+        let session = SessionId::new(seq, time);
+        self.session = Some(session.clone());
+        self.k = Some(rnd_scalar_with(&mut self.rng));
+        self.round1 = None;
+        self.timer.phase_end(Phase::ClientInit);
+        (sig, session)
+    }
+
+    pub fn complete_round1(&mut self, Mi: &PointShareVector, PIi: &PointShareVector) -> Result<Round1Msg, ProtocolError> {
+        let (session, k) = match (&self.session, self.k) {
+            (Some(session), Some(k)) => (session.clone(), k),
+            _ => return Err(ProtocolError::OutOfOrder)
+        };
+
+        self.timer.phase_start(Phase::ClientStart);
+        let out = client_round1(Mi, PIi, &k, &self.params);
+        let msg = Round1Msg { session, Akc: out.Akc, Kc: out.Kc };
+        self.round1 = Some(out);
+        self.timer.phase_end(Phase::ClientStart);
+        Ok(msg)
+    }
+
+    // NOTE: the request is completed and its state cleared, a new request starts with a new k
+    pub fn finalize(&mut self, Tki: &PointShareVector) -> Result<Token, ProtocolError> {
+        let (out, k) = match (self.round1.take(), self.k.take()) {
+            (Some(out), Some(k)) => (out, k),
+            (out, k) => {
+                self.round1 = out;
+                self.k = k;
+                return Err(ProtocolError::OutOfOrder)
+            }
+        };
+        self.session = None;
+
+        let params = &self.params;
+        Ok(timed(self.timer.as_mut(), Phase::ClientRequest, || client_round2(Tki, k, out.M, out.PI, params)))
+    }
+
+    // NOTE: the issued token against the client's setup (round 3)
+    pub fn verify(&mut self, token: &Token) -> bool {
+        let params = &self.params;
+        timed(self.timer.as_mut(), Phase::ClientVerify, || token.verify(params))
+    }
+}
+
+#[cfg(not(feature = "no-zeroize"))]
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.st.clear();
+        if let Some(k) = self.k.as_mut() {
+            k.clear();
+        }
+
+        if let Some(out) = self.round1.as_mut() {
+            out.c.clear();
+        }
+    }
+}
 
 //-----------------------------------------------------------------------------------------------------------
 // Token
 //-----------------------------------------------------------------------------------------------------------
+// NOTE: major version in the high nibble, minor in the low nibble. Minor versions only append fields.
+pub const TOKEN_VERSION: u8 = 0x12;
+
+// NOTE: compressed size of a token without attributes, holder and scope. Version and encoding tag, (Tk, M, PI, P1),
+// the signature (c, p), the attribute count, the holder flag, the scope length, the nonce and the issuance time.
+pub const TOKEN_BASE_LEN: usize = 2 + 4 * G1_COMPRESSED_LEN + 2 * SCALAR_LEN + 4 + 1 + 4 + 32 + 8;
+
+// NOTE: optional token claims. Attributes and holder are certified by the network, the scope and the issuance time
+// (seconds since the UNIX epoch, signed by the client) are chosen by the client.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Claims {
+    pub attrs: Vec<[u8; 32]>,
+    pub holder: Option<G1Affine>,
+    pub scope: Vec<u8>,
+    pub nonce: [u8; 32],
+    pub issued_at: u64
+}
+
+// NOTE: seconds since the UNIX epoch, times before the epoch give 0
+pub fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenVerifyError {
+    InvalidEncoding(CryptoError),
+    InvalidPoints(CryptoError),
+    InvalidToken,
+    NoSetups,
+    NoMatch
+}
+
 pub struct Token {
     pub Tk: G1Affine,
     pub M: G1Affine,
     pub PI: G1Affine,
+    pub claims: Claims,
     pub sig: ExtSignature
 }
 
 impl Token {
-    pub fn new(k: Scalar, Tk: G1Affine, M: G1Affine, PI: G1Affine) -> Self {
-        let Tk_comp = Tk.to_compressed();
-        let PI_comp = PI.to_compressed();
+    // NOTE: the token is bound to the issuing setup (PublicParams::setup_id), it doesn't verify against other setups
+    pub fn new(k: Scalar, Tk: G1Affine, M: G1Affine, PI: G1Affine, setup_id: &[u8; 32]) -> Self {
+        let claims = Claims { nonce: rnd_scalar().to_bytes(), issued_at: unix_time(SystemTime::now()), ..Claims::default() };
+        Self::with_claims(k, Tk, M, PI, claims, setup_id)
+    }
 
-        let data = &[Tk_comp.as_ref(), PI_comp.as_ref()];
-        let sig = ExtSignature::sign(&k, &M, data);
+    pub fn with_claims(k: Scalar, Tk: G1Affine, M: G1Affine, PI: G1Affine, claims: Claims, setup_id: &[u8; 32]) -> Self {
+        Self::with_base(k, Tk, M, PI, claims, &M, setup_id)
+    }
 
-        Token { Tk, M, PI, sig }
+    // NOTE: signs with P1 = k*base instead of k*M, M stays in the challenge and the token id. Tk must be issued for
+    // the same base, e(Tk, G2) == e(PI + c*k*base, A2), and the token only verifies with verify_with_base.
+    pub fn with_base(k: Scalar, Tk: G1Affine, M: G1Affine, PI: G1Affine, claims: Claims, base: &G1Affine, setup_id: &[u8; 32]) -> Self {
+        let sig = token_data(setup_id, &Tk, &PI, &claims, |data| ExtSignature::sign(&k, base, data));
+        Token { Tk, M, PI, claims, sig }
     }
 
-    pub fn verify(&self, setup: &NetworkSetup) -> bool {
-        let Tk_comp = self.Tk.to_compressed();
-        let Mk_comp = self.sig.P1.to_compressed();
-        let M_comp = self.M.to_compressed();
-        let PI_comp = self.PI.to_compressed();
-    
-        // verification of Schnorr's signature
-        let data = &[Tk_comp.as_ref(), PI_comp.as_ref()];
-        if !self.sig.verify(&self.M, data) {
-            return false
+    // NOTE: same as with_claims, with the signature computed on the selected backend
+    pub fn sign_with<E: PairingEngine>(k: Scalar, Tk: G1Affine, M: G1Affine, PI: G1Affine, claims: Claims, setup_id: &[u8; 32]) -> Result<Self, CryptoError> {
+        let M_e = E::g1_from_affine(&M);
+        let sig = token_data(setup_id, &Tk, &PI, &claims, |data| ExtSignature::sign_with::<E>(&k, &M_e, data))?;
+        Ok(Token { Tk, M, PI, claims, sig })
+    }
+
+    // NOTE: client-side selection of the attributes to disclose, the remaining ones stay hidden behind the commitments
+    pub fn disclose(attrs: &[Attribute], names: &[&str]) -> Vec<Attribute> {
+        attrs.iter().filter(|a| names.contains(&a.name.as_str())).cloned().collect()
+    }
+
+    pub fn verify_disclosure(&self, params: &PublicParams, disclosed: &[Attribute]) -> bool {
+        disclosed.iter().all(|a| self.claims.attrs.contains(&a.commitment())) && self.verify(params)
+    }
+
+    // NOTE: proof-of-possession of the holder key (the client key used in start) over a verifier challenge
+    pub fn present(&self, st: &Scalar, challenge: &[u8]) -> Option<Signature> {
+        let holder = self.claims.holder?;
+        Some(Signature::sign(st, &G1Affine::generator(), &holder, &[&self.id(), challenge]))
+    }
+
+    pub fn verify_presentation(&self, params: &PublicParams, challenge: &[u8], proof: &Signature) -> bool {
+        match self.claims.holder {
+            Some(holder) => proof.verify(&params.G1.into(), &holder, &[&self.id(), challenge]) && self.verify(params),
+            None => false
         }
-    
-        // verification of pairing signature
-        let c = hash(&[&M_comp, &Mk_comp, &PI_comp]);
-        pairing(&self.Tk, &setup.G2A) == multi_pairing(&[self.PI, (self.sig.P1 * c).into()], &setup.A2P)
     }
+
+    // NOTE: rejects replays, the nonce is recorded only for valid tokens
+    pub fn verify_once(&self, params: &PublicParams, store: &mut dyn NonceStore, now: SystemTime) -> bool {
+        self.verify(params) && store.insert(self.claims.nonce, now)
+    }
+
+    // NOTE: rejects tokens issued in the future or more than max_age before now. Tokens have no absolute expiry, the
+    // verifier's age cap is the only lifetime limit.
+    pub fn verify_with_max_age(&self, params: &PublicParams, now: SystemTime, max_age: Duration) -> bool {
+        let now = unix_time(now);
+        self.claims.issued_at <= now && now - self.claims.issued_at <= max_age.as_secs() && self.verify(params)
+    }
+
+    // NOTE: rejects tokens issued for a different resource
+    pub fn verify_for(&self, params: &PublicParams, scope: &[u8]) -> bool {
+        self.claims.scope == scope && self.verify(params)
+    }
+
+    // NOTE: M is unique per session and chosen by the network, so the issuer can derive the id without seeing the token
+    pub fn id(&self) -> [u8; 32] {
+        token_id(&self.M)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(PointEncoding::Compressed)
+    }
+
+    // NOTE: number of group elements, (Tk, M, PI, P1) and the optional holder
+    pub fn group_elements(&self) -> usize {
+        4 + self.claims.holder.iter().count()
+    }
+
+    pub fn serialized_len(&self) -> usize {
+        self.serialized_len_with(PointEncoding::Compressed)
+    }
+
+    // NOTE: must be kept in sync with to_bytes_with
+    pub fn serialized_len_with(&self, enc: PointEncoding) -> usize {
+        let points = self.group_elements() * enc.g1_len();
+        2 + points + 2 * SCALAR_LEN + 4 + 32 * self.claims.attrs.len() + 1 + 4 + self.claims.scope.len() + 32 + 8
+    }
+
+    pub fn to_bytes_with(&self, enc: PointEncoding) -> Vec<u8> {
+        let mut bytes = vec![TOKEN_VERSION];
+        let mut encoder = Encoder::new(enc);
+        encoder.g1(&self.Tk).g1(&self.M).g1(&self.PI)
+            .g1(&self.sig.P1).scalar(&self.sig.sig.c).scalar(&self.sig.sig.p);
+
+        encoder.u32(self.claims.attrs.len() as u32);
+        for attr in self.claims.attrs.iter() {
+            encoder.bytes32(attr);
+        }
+
+        match self.claims.holder {
+            Some(holder) => encoder.u8(1).g1(&holder),
+            None => encoder.u8(0)
+        };
+
+        encoder.bytes(&self.claims.scope).bytes32(&self.claims.nonce).u64(self.claims.issued_at);
+        bytes.extend(encoder.finish());
+        bytes
+    }
+
+    // NOTE: the point encoding is detected from the tag byte after the version.
+    // Unknown major versions are rejected, fields appended by newer minor versions are ignored.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let (version, bytes) = bytes.split_first().ok_or(CryptoError::InvalidLength)?;
+        if version >> 4 != TOKEN_VERSION >> 4 {
+            return Err(CryptoError::UnsupportedVersion)
+        }
+
+        let mut decoder = Decoder::new(bytes)?;
+        let Tk = decoder.g1()?;
+        let M = decoder.g1()?;
+        let PI = decoder.g1()?;
+        let P1 = decoder.g1()?;
+        let c = decoder.scalar()?;
+        let p = decoder.scalar()?;
+
+        let n = decoder.u32()?;
+        let attrs = (0..n).map(|_| decoder.bytes32()).collect::<Result<Vec<_>, _>>()?;
+        let holder = match decoder.u8()? {
+            0 => None,
+            1 => Some(decoder.g1()?),
+            _ => return Err(CryptoError::InvalidEncoding)
+        };
+
+        let scope = decoder.bytes()?;
+        let nonce = decoder.bytes32()?;
+
+        // NOTE: minor version 1 tokens have no issuance time
+        let issued_at = if version & 0x0f >= 2 { decoder.u64()? } else { 0 };
+        if *version <= TOKEN_VERSION {
+            decoder.finish()?;
+        }
+
+        let claims = Claims { attrs, holder, scope, nonce, issued_at };
+        Ok(Token { Tk, M, PI, claims, sig: ExtSignature { P1, sig: Signature { c, p } } })
+    }
+
+    // NOTE: the token points are public inputs of the signature and pairing equations, none can be the identity
+    pub fn check_points(&self) -> Result<(), CryptoError> {
+        for P in [&self.Tk, &self.M, &self.PI, &self.sig.P1].iter().chain(self.claims.holder.as_ref().iter()) {
+            check_public_g1(P)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn verify(&self, params: &PublicParams) -> bool {
+        self.verify_with_base(params, &self.M)
+    }
+
+    pub fn verify_with_base(&self, params: &PublicParams, base: &G1Affine) -> bool {
+        self.verify_terms(params, base, &[])
+    }
+
+    // NOTE: e(Tk, G2) == e(PI + c*Mk, A2) * e(P1, B1) * ... * e(Pn, Bn), for variants where Tk gains terms under other
+    // G2 bases. All pairings share a single final exponentiation.
+    pub fn verify_with_terms(&self, params: &PublicParams, extra: &[(G1Affine, &G2Prepared)]) -> bool {
+        self.verify_terms(params, &self.M, extra)
+    }
+
+    fn verify_terms(&self, params: &PublicParams, base: &G1Affine, extra: &[(G1Affine, &G2Prepared)]) -> bool {
+        let rhs = match self.verified_point::<Bls12Engine>(&params.setup_id, &params.Y, base) {
+            Some(P) => G1Affine::from(P),
+            None => return false
+        };
+
+        // NOTE: e(Tk, G2) == e(PI + c*Mk, A2), with both G2 bases prepared in the params.
+        // 2 miller loops and 1 final exponentiation, instead of 3 and 2 for e(Tk, G2) == e(PI, A2) * e(c*Mk, A2)
+        if extra.is_empty() {
+            return pairing_eq(&self.Tk, &params.G2P, &rhs, &params.A2P)
+        }
+
+        let negated = extra.iter().map(|(P, _)| -P).collect::<Vec<_>>();
+        let neg_rhs = -rhs;
+        let mut terms = vec![(&self.Tk, &params.G2P), (&neg_rhs, &params.A2P)];
+        terms.extend(negated.iter().zip(extra.iter()).map(|(P, (_, B))| (P, *B)));
+        pairing_check(&terms)
+    }
+
+    // NOTE: PI + c*Mk of the pairing equation, with the certified attributes and holder in PI
+    pub fn pairing_point(&self, params: &PublicParams) -> G1Affine {
+        G1Affine::from(self.fold_point::<Bls12Engine>(&params.setup_id, &params.Y, &self.sig.P1.into()))
+    }
+
+    fn fold_point<E: PairingEngine>(&self, setup_id: &[u8; 32], Y: &E::G1, Mk: &E::G1) -> E::G1 {
+        let c = token_challenge(setup_id, &self.M, &self.sig.P1, &self.PI);
+        let mut PI = E::g1_from_affine(&self.PI);
+        if !self.claims.attrs.is_empty() {
+            PI = E::g1_add(&PI, &E::g1_mul(Y, &attributes_digest(&self.claims.attrs)));
+        }
+
+        if let Some(holder) = self.claims.holder {
+            PI = E::g1_add(&PI, &E::g1_from_affine(&holder));
+        }
+
+        E::g1_add(&PI, &E::g1_mul(Mk, &c))
+    }
+
+    // NOTE: the checks shared by verify and verify_with, the token points and Schnorr's signature (signed with base).
+    // Returns the pairing point of a token that passes them.
+    fn verified_point<E: PairingEngine>(&self, setup_id: &[u8; 32], Y: &E::G1, base: &G1Affine) -> Option<E::G1> {
+        if self.check_points().is_err() || check_public_g1(base).is_err() {
+            return None
+        }
+
+        // verification of Schnorr's signature, the points are checked above
+        let (base, Mk) = (E::g1_from_affine(base), E::g1_from_affine(&self.sig.P1));
+        let c = token_data(setup_id, &self.Tk, &self.PI, &self.claims, |data| self.sig.sig.challenge_with::<E>(&base, &Mk, data));
+        if c != self.sig.sig.c {
+            return None
+        }
+
+        Some(self.fold_point::<E>(setup_id, Y, &Mk))
+    }
+
+    // NOTE: dual control, a token issued by the issuer and co-signed by each cosigner (see NetworkSetup::cosign and
+    // cosigned). e(Tk, G2) == e(PI + c*Mk, A2 + A2' + ...), as the extra terms e(PI + c*Mk, A2') of verify_with_terms.
+    pub fn verify_joint(&self, issuer: &PublicParams, cosigners: &[&PublicParams]) -> bool {
+        let P = self.pairing_point(issuer);
+        let extra = cosigners.iter().map(|params| (P, &params.A2P)).collect::<Vec<_>>();
+        self.verify_with_terms(issuer, &extra)
+    }
+
+    // NOTE: client side, adds the cosigner contributions to Tk and signs again with k. The challenge and the pairing
+    // point don't depend on Tk, each contribution is a'*(PI + c*Mk).
+    pub fn cosigned(&self, k: Scalar, contributions: &[PointShareVector], issuer: &PublicParams) -> Token {
+        let Tk = contributions.iter().fold(G1Projective::from(self.Tk), |acc, shares| acc + shares.interpolate());
+        Token::with_claims(k, Tk.into(), self.M, self.PI, self.claims.clone(), &issuer.setup_id)
+    }
+
+    // NOTE: quick check of a serialized token, decoded with the checked point and scalar decoders (see from_bytes)
+    pub fn verify_bytes(bytes: &[u8], params: &PublicParams) -> Result<(), TokenVerifyError> {
+        let token = Token::from_bytes(bytes).map_err(TokenVerifyError::InvalidEncoding)?;
+        token.check_points().map_err(TokenVerifyError::InvalidPoints)?;
+        if token.verify(params) { Ok(()) } else { Err(TokenVerifyError::InvalidToken) }
+    }
+
+    // NOTE: for rotation windows, returns the index of the first setup that validates the token
+    pub fn verify_any(&self, setups: &[&PublicParams]) -> Result<usize, TokenVerifyError> {
+        self.check_points().map_err(TokenVerifyError::InvalidPoints)?;
+        if setups.is_empty() {
+            return Err(TokenVerifyError::NoSetups)
+        }
+
+        setups.iter().position(|params| self.verify(params)).ok_or(TokenVerifyError::NoMatch)
+    }
+
+    // NOTE: same as verify, with all group operations running on the selected backend. e(Tk, G2) * e(-(PI + c*Mk), A2)
+    // is checked with 2 miller loops and 1 final exponentiation.
+    pub fn verify_with<E: PairingEngine>(&self, params: &EngineParams<E>) -> bool {
+        match self.verified_point::<E>(&params.setup_id, &params.Y, &self.M) {
+            Some(P) => E::pairing_check(&[(E::g1_from_affine(&self.Tk), params.G2), (E::g1_neg(&P), params.A2)]),
+            None => false
+        }
+    }
+}
+
+pub fn token_id(M: &G1Affine) -> [u8; 32] {
+    hash(&[b"token-id", &M.to_compressed()]).to_bytes()
+}
+
+// NOTE: the data signed with the token key, (setup, Tk, PI, nonce, scope, issued_at). Shared by signing and all verifiers.
+fn token_data<T>(setup_id: &[u8; 32], Tk: &G1Affine, PI: &G1Affine, claims: &Claims, f: impl FnOnce(&[&[u8]]) -> T) -> T {
+    let Tk_comp = Tk.to_compressed();
+    let PI_comp = PI.to_compressed();
+    let issued_at = claims.issued_at.to_le_bytes();
+    f(&[setup_id.as_ref(), Tk_comp.as_ref(), PI_comp.as_ref(), &claims.nonce, &claims.scope, &issued_at])
+}
+
+// NOTE: c = H(setup, M, Mk, PI), the setup id prevents a token from verifying against another setup with the same generators
+pub fn token_challenge(setup_id: &[u8; 32], M: &G1Affine, Mk: &G1Affine, PI: &G1Affine) -> Scalar {
+    hash(&[setup_id.as_ref(), &M.to_compressed(), &Mk.to_compressed(), &PI.to_compressed()])
 }
 
 //-----------------------------------------------------------------------------------------------------------
 // Simulation of a (t,n)-network
 //-----------------------------------------------------------------------------------------------------------
+// NOTE: default max_past and max_future of the start time
+pub const FRESHNESS_WINDOW: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    InvalidSignature,
+    InvalidFreshness,
+    InvalidPairing,
+    UnknownProfile,
+    UnknownLocation,
+    UnknownSession,
+    InsufficientShares,
+    InvalidProfile,
+    InvalidShares,
+    ProfileExists,
+    SessionAlreadyConsumed,
+    OutOfOrder
+}
+
+#[derive(Clone)]
 struct Session {
     pub mi: ShareVector,
-    pub profile: Profile
+    pub Pt: G1Affine,
+    pub name: String,
+    pub profile: Profile,
+    pub started: Instant,
+    pub metadata: Vec<u8>,
+    pub requested: Option<(G1Affine, bool)>
+}
+
+// NOTE: what request does with a completed session. RemoveOnUse keeps the id only, KeepUntilExpiry keeps the session
+// bound to the first (Akc, bound) inputs so that a retry gets the same Tki. Both are cleared by expire_sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionRetention {
+    RemoveOnUse,
+    KeepUntilExpiry
+}
+
+// NOTE: Feldman commitments to the mi and y*R polynomials of a session, for the client to check the (Mi, PIi) shares
+// of start before interpolation. In a deployment they come from the DKG of mi and y, the simulation reconstructs them.
+#[derive(Debug, Clone)]
+pub struct StartCommitments {
+    pub M: PointPolynomial,
+    pub PI: PointPolynomial
+}
+
+impl StartCommitments {
+    // NOTE: indices of the failing (Mi, PIi) shares, the client rejects the response and retries
+    pub fn verify(&self, Mi: &PointShareVector, PIi: &PointShareVector) -> Result<(), Vec<u32>> {
+        let mut failed = self.M.verify_all(Mi).err().unwrap_or_default().into_iter()
+            .chain(self.PI.verify_all(PIi).err().unwrap_or_default())
+            .map(|(i, _, _)| i).collect::<Vec<_>>();
+
+        failed.sort_unstable();
+        failed.dedup();
+        if failed.is_empty() { Ok(()) } else { Err(failed) }
+    }
+}
+
+// NOTE: read-only counters for monitoring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetupMetrics {
+    pub sessions: usize,
+    pub profiles: usize,
+    pub locations: usize
 }
 
 #[derive(Clone)]
 pub struct Location {
     pub Yl: G1Projective,
-    pub Yl_comp: [u8; 48]
+    pub Yl_comp: [u8; G1_COMPRESSED_LEN]
+}
+
+// NOTE: locations of a profile, issuance requires at least "quorum" of them registered in the network
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationQuorum {
+    pub names: Vec<String>,
+    pub quorum: usize
+}
+
+impl LocationQuorum {
+    pub fn single(name: &str) -> Self {
+        Self { names: vec![name.into()], quorum: 1 }
+    }
 }
 
 #[derive(Clone)]
 pub struct Profile {
-    pub loc: String,
+    pub threshold: usize,
+    pub locs: LocationQuorum,
     pub R: G1Projective,
     pub Ar: G1Projective,
-    pub Ar_comp: [u8; 48]
+    pub Ar_comp: [u8; G1_COMPRESSED_LEN],
+    pub attrs: Vec<[u8; 32]>,
+    pub Ard: G1Projective
 }
 
 pub struct NetworkSetup {
     pub threshold: usize,
+    pub parties: usize,
 
     pub G1: G1Projective,
     pub G2A: G2Affine,
+    pub G2P: G2Prepared,
     
     pub Y: G1Projective,
     pub A1: G1Projective,
@@ -77,24 +679,96 @@ pub struct NetworkSetup {
     pub A2A: G2Affine,
     pub A2P: G2Prepared,
 
-    pub Y_comp: [u8; 48],
+    pub Y_comp: [u8; G1_COMPRESSED_LEN],
     pub yi: ShareVector,
     pub ai: ShareVector,
 
+    params: PublicParams,
+    prf_keys: Vec<[u8; 32]>,
+    complete_removed: bool,
+    retention: SessionRetention,
+    consumed: HashMap<String, Instant>,
+    max_past: Duration,
+    max_future: Duration,
     last: usize,
+    reshared: HashMap<usize, (ShareVector, ShareVector)>,
+    audit: Box<dyn AuditLog + Send>,
+    timer: Box<dyn Timer + Send>,
+    rng: Box<dyn RngCore + Send>,
+    clock: Box<dyn Clock + Send>,
+    #[cfg(feature = "transcript")]
+    transcript: Transcript,
     sessions: HashMap<String, Session>,
     profiles: HashMap<String, Profile>,
     locations: HashMap<String, Location>
 }
 
+// NOTE: deep copy for tests, the copy draws from a fresh rng and starts with an empty audit log, no timer and the
+// system clock
+impl Clone for NetworkSetup {
+    fn clone(&self) -> Self {
+        Self {
+            threshold: self.threshold, parties: self.parties,
+            G1: self.G1, G2A: self.G2A, G2P: self.G2A.into(),
+            Y: self.Y, A1: self.A1, A2: self.A2, A2A: self.A2A, A2P: self.A2A.into(),
+            Y_comp: self.Y_comp, yi: self.yi.clone(), ai: self.ai.clone(),
+            params: self.params.clone(),
+            prf_keys: self.prf_keys.clone(),
+            complete_removed: self.complete_removed,
+            retention: self.retention, consumed: self.consumed.clone(),
+            max_past: self.max_past, max_future: self.max_future,
+            last: self.last,
+            reshared: self.reshared.clone(),
+            audit: Box::new(MemoryAuditLog::default()),
+            timer: Box::new(NoTimer),
+            rng: Box::new(ChaCha20Rng::from_entropy()),
+            clock: Box::new(SystemClock),
+            #[cfg(feature = "transcript")]
+            transcript: self.transcript.clone(),
+            sessions: self.sessions.clone(), profiles: self.profiles.clone(), locations: self.locations.clone()
+        }
+    }
+}
+
 impl NetworkSetup {
-    // NOTE: simulates a network of "threshold + 1" nodes
+    // NOTE: simulates a network of "threshold + 1" nodes, the minimum committee (n = t + 1) where all nodes must answer.
+    // The threshold must be at least 1, a degree-0 polynomial gives every node the secret itself.
     pub fn new(threshold: usize) -> Self {
+        Self::with_rng(threshold, ChaCha20Rng::from_entropy())
+    }
+
+    // NOTE: all network randomness (keys, shares and mi PRF keys) is drawn from rng, a seeded rng gives reproducible runs
+    pub fn with_rng<R: RngCore + Send + 'static>(threshold: usize, rng: R) -> Self {
+        Self::with_committee(threshold, threshold + 1, rng)
+    }
+
+    // NOTE: reproducible setup for tests, all keys and shares are derived from the seed (ChaCha20)
+    pub fn deterministic(threshold: usize, seed: [u8; 32]) -> Self {
+        Self::with_rng(threshold, ChaCha20Rng::from_seed(seed))
+    }
+
+    // NOTE: disaster recovery, the authority is re-derivable from the backed-up master seed alone. All draws come from
+    // SeedKdf in order: y, a, the y and a polynomial coefficients (degree 1 up to threshold) and the mi PRF keys.
+    // Later reshares and rotations also draw from the KDF, so they are reproducible when replayed in the same order.
+    pub fn from_master_seed(threshold: usize, seed: [u8; 32]) -> Self {
+        Self::with_rng(threshold, SeedKdf::new(seed))
+    }
+
+    // NOTE: simulates a network of "parties" nodes, extra nodes over "threshold + 1" allow robust requests
+    pub fn with_committee<R: RngCore + Send + 'static>(threshold: usize, parties: usize, mut rng: R) -> Self {
+        if threshold == 0 {
+            panic!("Threshold must be at least 1!");
+        }
+
+        if parties < threshold + 1 {
+            panic!("Committee must have at least threshold + 1 parties!");
+        }
+
         let G1: G1Projective = G1Projective::generator();
         let G2A: G2Affine = G2Affine::generator();
 
-        let y = rnd_scalar();
-        let a = rnd_scalar();
+        let y = rnd_scalar_with(&mut rng);
+        let a = rnd_scalar_with(&mut rng);
         
         let Y = G1 * y;
         let A1 = G1 * a;
@@ -102,101 +776,2170 @@ impl NetworkSetup {
         let A2A = G2Affine::from(A2);
         let A2P: G2Prepared = A2A.into();
     
-        let y_poly = Polynomial::rnd(y, threshold);
-        let a_poly = Polynomial::rnd(a, threshold);
+        let y_poly = Polynomial::rnd_with(y, threshold, &mut rng);
+        let a_poly = Polynomial::rnd_with(a, threshold, &mut rng);
         
         let Y_comp = G1Affine::from(Y).to_compressed();
-        let yi = y_poly.shares(threshold + 1);
-        let ai = a_poly.shares(threshold + 1);
+        let yi = y_poly.shares(parties);
+        let ai = a_poly.shares(parties);
+        let params = PublicParams::new(Y.into(), A1.into(), A2A, &yi * G1);
+
+        // NOTE: per-node PRF keys for the mi shares, drawn last so the key shares don't depend on the committee size
+        let prf_keys = (0..parties).map(|_| rnd_scalar_with(&mut rng).to_bytes()).collect();
 
         Self {
-            threshold,
-            G1, G2A,
+            threshold, parties,
+            G1, G2A, G2P: G2A.into(),
             Y, A1, A2, A2A, A2P,
             Y_comp, yi, ai,
+            params, prf_keys,
+            complete_removed: false,
+            retention: SessionRetention::RemoveOnUse, consumed: HashMap::new(),
+            max_past: FRESHNESS_WINDOW, max_future: FRESHNESS_WINDOW,
             last: 0,
+            reshared: HashMap::new(),
+            audit: Box::new(MemoryAuditLog::default()),
+            timer: Box::new(NoTimer),
+            rng: Box::new(rng),
+            clock: Box::new(SystemClock),
+            #[cfg(feature = "transcript")]
+            transcript: Transcript::new(),
             sessions: HashMap::new(), profiles: HashMap::new(), locations: HashMap::new()
         }
     }
 
-    // NOTE: simulates insertion of a location
-    pub fn location(&mut self, name: &str, Yl: G1Projective) {
-        let Yl_comp = G1Affine::from(Yl).to_compressed();
-        self.locations.insert(name.into(), Location { Yl, Yl_comp });
+    // NOTE: verify-only setup from the network public keys, it has no shares and cannot issue tokens
+    pub fn verifier(Y: G1Affine, A2: G2Affine) -> Self {
+        let A2P: G2Prepared = A2.into();
+
+        Self {
+            threshold: 0, parties: 0,
+            G1: G1Projective::generator(), G2A: G2Affine::generator(), G2P: G2Affine::generator().into(),
+            Y: Y.into(), A1: G1Projective::identity(), A2: A2.into(), A2A: A2, A2P,
+            Y_comp: Y.to_compressed(), yi: ShareVector(Vec::new()), ai: ShareVector(Vec::new()),
+            params: PublicParams::from_keys(Y, A2),
+            prf_keys: Vec::new(),
+            complete_removed: false,
+            retention: SessionRetention::RemoveOnUse, consumed: HashMap::new(),
+            max_past: FRESHNESS_WINDOW, max_future: FRESHNESS_WINDOW,
+            last: 0,
+            reshared: HashMap::new(),
+            audit: Box::new(MemoryAuditLog::default()),
+            timer: Box::new(NoTimer),
+            rng: Box::new(ChaCha20Rng::from_entropy()),
+            clock: Box::new(SystemClock),
+            #[cfg(feature = "transcript")]
+            transcript: Transcript::new(),
+            sessions: HashMap::new(), profiles: HashMap::new(), locations: HashMap::new()
+        }
     }
 
-    // NOTE: simulates insertion of a profile
-    pub fn profile(&mut self, name: &str, loc: &str, R: G1Projective, Ar: G1Projective) {
-        if !self.locations.contains_key(loc) {
-            panic!("Location doesn't exist!");
+    // NOTE: setup from explicit public params and (yi, ai) shares, e.g. from an external dealer. The shares must interpolate
+    // to (Y, A1) and match the published commitments, if any.
+    pub fn from_shares(params: PublicParams, threshold: usize, yi: ShareVector, ai: ShareVector) -> Result<Self, ProtocolError> {
+        if threshold == 0 {
+            return Err(ProtocolError::InvalidShares)
         }
 
-        // NOTE: (Ar, R) input validation
-        if pairing(&Ar.into(), &self.G2A) != pairing(&R.into(), &self.A2A) {
-            panic!("Ar not valid!");
+        let parties = yi.0.len();
+        if parties < threshold + 1 || ai.0.len() != parties {
+            return Err(ProtocolError::InsufficientShares)
         }
 
-        let Ar_comp = G1Affine::from(Ar).to_compressed();
-        self.profiles.insert(name.into(), Profile { loc: loc.into(), R, Ar, Ar_comp });
-    }
+        if !pairing_eq(&params.A1.into(), &params.G2P, &params.G1.into(), &params.A2P) {
+            return Err(ProtocolError::InvalidPairing)
+        }
 
-    // NOTE: start-session returns (Mi, PIi) shares for reconstruction
-    pub fn start(&mut self, sig: ExtSignature, profile: &str, seq: usize, time: Instant) -> (PointShareVector, PointShareVector) {
-        //NOTE: verification of client signature
-        let seq_bytes = seq.to_le_bytes();
-        let time_str = format!("{:?}", time);
-        let data = &[profile.as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()];
-        if !sig.verify(&self.G1.into(), data) {
-            panic!("Invalid inputs!");
+        let commitments = &yi * params.G1;
+        let published = &params.commitments.0;
+        if !published.is_empty() && (published.len() != parties || published.iter().zip(commitments.0.iter()).any(|(c, s)| c.i != s.i || c.Yi != s.Yi)) {
+            return Err(ProtocolError::InvalidShares)
         }
 
-        //NOTE: verification of client identity and authorizations should be here. However, these stats are not included in the measurements.
-        // * verify if sig.P1 has access?
-        
-        let wall = Duration::from_secs(30);
-        let now = Instant::now();
+        let params = PublicParams::new(params.Y.into(), params.A1.into(), params.A2A, commitments);
+        let mut rng = ChaCha20Rng::from_entropy();
+        let prf_keys = (0..parties).map(|_| rnd_scalar_with(&mut rng).to_bytes()).collect();
+
+        let setup = Self {
+            threshold, parties,
+            G1: params.G1, G2A: params.G2A, G2P: params.G2A.into(),
+            Y: params.Y, A1: params.A1, A2: params.A2A.into(), A2A: params.A2A, A2P: params.A2A.into(),
+            Y_comp: params.Y_comp, yi, ai,
+            params, prf_keys,
+            complete_removed: false,
+            retention: SessionRetention::RemoveOnUse, consumed: HashMap::new(),
+            max_past: FRESHNESS_WINDOW, max_future: FRESHNESS_WINDOW,
+            last: 0,
+            reshared: HashMap::new(),
+            audit: Box::new(MemoryAuditLog::default()),
+            timer: Box::new(NoTimer),
+            rng: Box::new(rng),
+            clock: Box::new(SystemClock),
+            #[cfg(feature = "transcript")]
+            transcript: Transcript::new(),
+            sessions: HashMap::new(), profiles: HashMap::new(), locations: HashMap::new()
+        };
+
+        setup.validate()?;
+        Ok(setup)
+    }
 
-        // NOTE: "seq" and "time" in the correct ranges?
-        if time < now - wall || time > now + wall || seq <= self.last {
-            panic!("Invalid inputs!");
+    // NOTE: federated issuance, each node adds its (yi, ai) shares of both setups. Profiles and locations are not copied.
+    pub fn combine(&self, other: &NetworkSetup) -> Result<Self, ProtocolError> {
+        if self.threshold != other.threshold || self.parties != other.parties
+            || self.yi.0.iter().zip(other.yi.0.iter()).any(|(a, b)| a.i != b.i) {
+            return Err(ProtocolError::InvalidShares)
         }
-        
-        let session = format!("{}-{:?}", seq, time);
-        let profile = self.profiles.get(profile).expect("Profile doesn't exist!");
-        let location = self.locations.get(&profile.loc).expect("Location doesn't exist!");
 
-        // NOTE: mi shares may be re-calculated or stored in the session (stateless vs stateful)
-        let Pt_comp = sig.P1.to_compressed();
-        let mi = self.mi_shares(&session, Pt_comp.as_ref(), location.Yl_comp.as_ref(), profile.Ar_comp.as_ref());
+        let params = self.params.combine(&other.params);
+        Self::from_shares(params, self.threshold, &self.yi + &other.yi, &self.ai + &other.ai)
+    }
 
-        let res = (&mi * self.G1, &self.yi * profile.R);
-        self.last += 1;
-        self.sessions.insert(session.into(), Session { mi, profile: profile.clone() });
-        
-        res
+    // NOTE: the public part of the setup, all a verifier needs
+    pub fn public_params(&self) -> &PublicParams {
+        &self.params
     }
 
-    // NOTE: request-token returns Tki shares for reconstruction
-    pub fn request(&mut self, session: &str, Akc: &G1Affine, Kc: &G1Affine) -> PointShareVector {
-        // NOTE: (Akc, Kc) input validation
-        if pairing(Akc, &self.G2A) != pairing(Kc, &self.A2A) {
-            panic!("Akc not valid!");
-        }
+    pub fn audit(&self) -> &dyn AuditLog {
+        self.audit.as_ref()
+    }
 
-        let session = self.sessions.remove(session.into()).unwrap();
+    pub fn set_audit(&mut self, audit: Box<dyn AuditLog + Send>) {
+        self.audit = audit;
+    }
 
-        // NOTE: all inputs are validated (yi, mi, Ar, Akc)
-        &self.yi * session.profile.Ar + &session.mi * G1Projective::from(Akc)
+    // NOTE: times the NetworkStart and NetworkRequest phases. A batch start is one phase, a batch request one per profile.
+    pub fn set_timer(&mut self, timer: Box<dyn Timer + Send>) {
+        self.timer = timer;
     }
 
-    fn mi_shares(&self, session: &str, Pt: &[u8], Yl: &[u8], Ar: &[u8]) -> ShareVector {
-        let mut mi = Vec::<Share>::new();
-        for i in 1..=self.threshold+1 {
-            let ni = rnd_scalar();
-            let yi = hash(&[ni.to_bytes().as_ref(), session.as_bytes(), Pt, self.Y_comp.as_ref(), Yl, Ar]);
-            mi.push(Share { i: i as u32, yi });
-        }
-    
-        ShareVector(mi)
+    pub fn session_count(&self) -> usize {
+        self.sessions.len()
     }
-}
\ No newline at end of file
+
+    // NOTE: age of the oldest in-flight session, measured from its start on this node
+    pub fn oldest_session_age(&self) -> Option<Duration> {
+        self.sessions.values().map(|s| s.started.elapsed()).max()
+    }
+
+    pub fn metrics_snapshot(&self) -> SetupMetrics {
+        SetupMetrics { sessions: self.sessions.len(), profiles: self.profiles.len(), locations: self.locations.len() }
+    }
+
+    // NOTE: drops sessions started more than "max_age" ago, clients that never request would grow the map. Returns the evicted count.
+    pub fn expire_sessions(&mut self, max_age: Duration) -> usize {
+        let before = self.sessions.len();
+        self.sessions.retain(|_, s| s.started.elapsed() <= max_age);
+        self.consumed.retain(|_, started| started.elapsed() <= max_age);
+        before - self.sessions.len()
+    }
+
+    // NOTE: retried requests (e.g. after a network timeout) get SessionAlreadyConsumed with RemoveOnUse (the default),
+    // and the same Tki with KeepUntilExpiry. Expire sessions periodically in both modes.
+    pub fn set_session_retention(&mut self, retention: SessionRetention) {
+        self.retention = retention;
+    }
+
+    // NOTE: allows in-flight sessions of a removed profile to complete, by default request rejects them
+    pub fn set_complete_removed(&mut self, allow: bool) {
+        self.complete_removed = allow;
+    }
+
+    // NOTE: accepted start times are in [now - max_past, now + max_future], clients usually lag behind the network
+    pub fn set_freshness(&mut self, max_past: Duration, max_future: Duration) {
+        self.max_past = max_past;
+        self.max_future = max_future;
+    }
+
+    // NOTE: the reference time of the freshness check, a fixed clock with a fixed start time gives reproducible sessions
+    pub fn set_clock(&mut self, clock: Box<dyn Clock + Send>) {
+        self.clock = clock;
+    }
+
+    // NOTE: replaces the authority key (a, A1, A2) with fresh shares of a new a, for all thresholds. Tokens issued before
+    // the rotation no longer verify. In-flight sessions are dropped and profiles are migrated to the new key.
+    pub fn rotate_authority(&mut self) -> Result<(), ProtocolError> {
+        let a = rnd_scalar_with(&mut self.rng);
+        let a_poly = Polynomial::rnd_with(a, self.threshold, &mut self.rng);
+        self.ai = a_poly.shares(self.parties);
+
+        let thresholds = self.reshared.keys().cloned().collect::<Vec<_>>();
+        for threshold in thresholds {
+            let n = self.committee(threshold);
+            let ai = self.ai.reshare_with(threshold, n, &mut self.rng);
+            self.reshared.get_mut(&threshold).unwrap().1 = ai;
+        }
+
+        self.A1 = self.G1 * a;
+        self.A2 = self.G2A * a;
+        self.A2A = self.A2.into();
+        self.A2P = self.A2A.into();
+        self.params = PublicParams::new(self.Y.into(), self.A1.into(), self.A2A, self.params.commitments.clone());
+
+        self.sessions.clear();
+        self.migrate_profiles()
+    }
+
+    // NOTE: re-derives Ar = a*R of all profiles from the current ai shares (each node contributes ai*R) and re-validates them
+    pub fn migrate_profiles(&mut self) -> Result<(), ProtocolError> {
+        let names = self.profiles.keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            let profile = self.profiles[&name].clone();
+            let Ar = (self.ai(profile.threshold) * profile.R).interpolate();
+            self.set_profile(&name, profile.locs, profile.R, Ar, profile.threshold, profile.attrs)?;
+        }
+
+        Ok(())
+    }
+
+    // NOTE: values recorded by start/request, client code can append its own to the same transcript
+    #[cfg(feature = "transcript")]
+    pub fn transcript(&mut self) -> &mut Transcript {
+        &mut self.transcript
+    }
+
+    #[cfg(feature = "transcript")]
+    fn trace(&mut self, label: &str, value: &[u8]) {
+        self.transcript.append(label, value);
+    }
+
+    #[cfg(not(feature = "transcript"))]
+    fn trace(&mut self, _: &str, _: &[u8]) {}
+
+    // NOTE: simulates insertion of a location
+    pub fn location(&mut self, name: &str, Yl: G1Projective) {
+        let Yl_comp = G1Affine::from(Yl).to_compressed();
+        self.locations.insert(name.into(), Location { Yl, Yl_comp });
+    }
+
+    // NOTE: profiles referencing the location are kept, start fails for those without a location quorum
+    pub fn remove_location(&mut self, name: &str) -> Result<(), ProtocolError> {
+        self.locations.remove(name).map(|_| ()).ok_or(ProtocolError::UnknownLocation)
+    }
+
+    // NOTE: simulates insertion of a profile, an existing profile is never replaced
+    pub fn profile(&mut self, name: &str, loc: &str, R: G1Projective, Ar: G1Projective) -> Result<(), ProtocolError> {
+        self.absent(name)?;
+        self.insert_profile(name, LocationQuorum::single(loc), R, Ar, self.threshold, Vec::new())
+    }
+
+    // NOTE: replaces an existing profile, sessions started with the old one are dropped
+    pub fn profile_overwrite(&mut self, name: &str, loc: &str, R: G1Projective, Ar: G1Projective) -> Result<(), ProtocolError> {
+        self.insert_profile(name, LocationQuorum::single(loc), R, Ar, self.threshold, Vec::new())
+    }
+
+    // NOTE: simulates insertion of a profile with its own threshold (<= setup threshold)
+    pub fn profile_with_threshold(&mut self, name: &str, loc: &str, R: G1Projective, Ar: G1Projective, threshold: usize) -> Result<(), ProtocolError> {
+        self.absent(name)?;
+        self.insert_profile(name, LocationQuorum::single(loc), R, Ar, threshold, Vec::new())
+    }
+
+    // NOTE: (R, Ar) as compressed bytes from external config, invalid encodings give InvalidProfile. Never panics.
+    pub fn profile_compressed(&mut self, name: &str, loc: &str, R: &[u8; G1_COMPRESSED_LEN], Ar: &[u8; G1_COMPRESSED_LEN]) -> Result<(), ProtocolError> {
+        if !self.locations.contains_key(loc) {
+            return Err(ProtocolError::UnknownLocation)
+        }
+
+        let (R, Ar) = match (decompress_g1(R), decompress_g1(Ar)) {
+            (Ok(R), Ok(Ar)) => (R, Ar),
+            _ => return Err(ProtocolError::InvalidProfile)
+        };
+
+        self.profile(name, loc, R.into(), Ar.into())
+    }
+
+    // NOTE: simulates insertion of a profile with committed attributes, the network only learns the commitments
+    pub fn profile_with_attributes(&mut self, name: &str, loc: &str, R: G1Projective, Ar: G1Projective, attrs: &[[u8; 32]]) -> Result<(), ProtocolError> {
+        self.absent(name)?;
+        self.insert_profile(name, LocationQuorum::single(loc), R, Ar, self.threshold, attrs.to_vec())
+    }
+
+    // NOTE: simulates insertion of a profile spanning several locations, "quorum" of them must be registered at start
+    pub fn profile_with_locations(&mut self, name: &str, locs: &[&str], quorum: usize, R: G1Projective, Ar: G1Projective) -> Result<(), ProtocolError> {
+        if quorum == 0 || quorum > locs.len() {
+            return Err(ProtocolError::InvalidProfile)
+        }
+
+        self.absent(name)?;
+        let locs = LocationQuorum { names: locs.iter().map(|loc| loc.to_string()).collect(), quorum };
+        self.insert_profile(name, locs, R, Ar, self.threshold, Vec::new())
+    }
+
+    fn absent(&self, name: &str) -> Result<(), ProtocolError> {
+        match self.profiles.contains_key(name) {
+            true => Err(ProtocolError::ProfileExists),
+            false => Ok(())
+        }
+    }
+
+    fn insert_profile(&mut self, name: &str, locs: LocationQuorum, R: G1Projective, Ar: G1Projective, threshold: usize, attrs: Vec<[u8; 32]>) -> Result<(), ProtocolError> {
+        if threshold == 0 || threshold > self.threshold {
+            return Err(ProtocolError::InvalidProfile)
+        }
+
+        if locs.names.iter().any(|loc| !self.locations.contains_key(loc)) {
+            return Err(ProtocolError::UnknownLocation)
+        }
+
+        // NOTE: (yi, ai) are reshared once per distinct threshold, only the first "committee(threshold)" nodes participate
+        if threshold != self.threshold && !self.reshared.contains_key(&threshold) {
+            let n = self.committee(threshold);
+            let yi = self.yi.reshare_with(threshold, n, &mut self.rng);
+            let ai = self.ai.reshare_with(threshold, n, &mut self.rng);
+            self.reshared.insert(threshold, (yi, ai));
+        }
+
+        self.set_profile(name, locs, R, Ar, threshold, attrs)
+    }
+
+    // NOTE: in-flight sessions are kept, request decides if they can complete (see set_complete_removed)
+    pub fn remove_profile(&mut self, name: &str) -> Result<(), ProtocolError> {
+        self.profiles.remove(name).map(|_| ()).ok_or(ProtocolError::UnknownProfile)
+    }
+
+    // NOTE: replaces (R, Ar) of an existing profile, the location, threshold and attributes are kept
+    pub fn update_profile(&mut self, name: &str, R: G1Projective, Ar: G1Projective) -> Result<(), ProtocolError> {
+        let profile = self.profiles.get(name).ok_or(ProtocolError::UnknownProfile)?;
+        let (locs, threshold, attrs) = (profile.locs.clone(), profile.threshold, profile.attrs.clone());
+        self.set_profile(name, locs, R, Ar, threshold, attrs)
+    }
+
+    // NOTE: all profile mutations go through here, (R, Ar) is always re-checked and derived values are recomputed
+    fn set_profile(&mut self, name: &str, locs: LocationQuorum, R: G1Projective, Ar: G1Projective, threshold: usize, attrs: Vec<[u8; 32]>) -> Result<(), ProtocolError> {
+        let Ar_comp = G1Affine::from(Ar).to_compressed();
+        let Ard = if attrs.is_empty() { Ar } else { Ar + self.A1 * attributes_digest(&attrs) };
+        let profile = Profile { threshold, locs, R, Ar, Ar_comp, attrs, Ard };
+        self.check_profile(&profile)?;
+
+        // NOTE: in-flight sessions of a replaced (or removed and re-inserted) profile would issue tokens for the old (R, Ar)
+        self.profiles.insert(name.into(), profile);
+        self.sessions.retain(|_, session| session.name != name);
+
+        Ok(())
+    }
+
+    // NOTE: (Ar, R) input validation, e(Ar, G2) == e(R, A2)
+    fn check_profile(&self, profile: &Profile) -> Result<(), ProtocolError> {
+        if check_public_g1(&profile.R.into()).is_err() || check_public_g1(&profile.Ar.into()).is_err() {
+            return Err(ProtocolError::InvalidProfile)
+        }
+
+        let Ard = if profile.attrs.is_empty() { profile.Ar } else { profile.Ar + self.A1 * attributes_digest(&profile.attrs) };
+        if G1Affine::from(profile.Ar).to_compressed() != profile.Ar_comp || Ard != profile.Ard {
+            return Err(ProtocolError::InvalidProfile)
+        }
+
+        if !pairing_eq(&profile.Ar.into(), &self.G2P, &profile.R.into(), &self.A2P) {
+            return Err(ProtocolError::InvalidPairing)
+        }
+
+        Ok(())
+    }
+
+    // NOTE: invariant check of the internal state, all profiles are valid and the (yi, ai) shares of every threshold
+    // interpolate to (Y, A1). Verify-only setups have no shares to check.
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        for profile in self.profiles.values() {
+            self.check_profile(profile)?;
+        }
+
+        if self.yi.0.is_empty() {
+            return Ok(())
+        }
+
+        let shares = std::iter::once((&self.yi, &self.ai)).chain(self.reshared.values().map(|(yi, ai)| (yi, ai)));
+        for (yi, ai) in shares {
+            if self.G1 * yi.interpolate() != self.Y || self.G1 * ai.interpolate() != self.A1 {
+                return Err(ProtocolError::InvalidShares)
+            }
+        }
+
+        Ok(())
+    }
+
+    // NOTE: the extra nodes of the setup committee are kept for all thresholds
+    fn committee(&self, threshold: usize) -> usize {
+        self.parties - self.threshold + threshold
+    }
+
+    fn yi(&self, threshold: usize) -> &ShareVector {
+        if threshold == self.threshold { &self.yi } else { &self.reshared[&threshold].0 }
+    }
+
+    fn ai(&self, threshold: usize) -> &ShareVector {
+        if threshold == self.threshold { &self.ai } else { &self.reshared[&threshold].1 }
+    }
+
+    // NOTE: start-session returns (Mi, PIi) shares for reconstruction
+    pub fn start(&mut self, sig: ExtSignature, profile: &str, seq: usize, time: SystemTime) -> Result<(PointShareVector, PointShareVector), ProtocolError> {
+        self.start_with_metadata(sig, profile, seq, time, Vec::new())
+    }
+
+    // NOTE: opaque application metadata (e.g. a request id) kept in the session and returned by request_with_metadata.
+    // It's never signed or hashed into the session values.
+    pub fn start_with_metadata(&mut self, sig: ExtSignature, profile: &str, seq: usize, time: SystemTime, metadata: Vec<u8>) -> Result<(PointShareVector, PointShareVector), ProtocolError> {
+        self.timer.phase_start(Phase::NetworkStart);
+        let res = self.start_session(sig, profile, seq, time, metadata);
+        self.timer.phase_end(Phase::NetworkStart);
+        res
+    }
+
+    fn start_session(&mut self, sig: ExtSignature, profile: &str, seq: usize, time: SystemTime, metadata: Vec<u8>) -> Result<(PointShareVector, PointShareVector), ProtocolError> {
+        let seq_bytes = seq.to_le_bytes();
+        let time_str = encode_time(time);
+        let now = self.check_start(&sig, &[profile.as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()], seq, time)?;
+        
+        let session = SessionId::new(seq, time);
+        self.trace("start.profile", profile.as_bytes());
+        self.trace("start.seq", &seq_bytes);
+        self.trace("start.time", time_str.as_bytes());
+        self.trace("start.session", session.as_str().as_bytes());
+
+        let (res, state) = self.start_profile(session.as_str(), profile, &sig.P1, now)?;
+        self.last += 1;
+        self.sessions.insert(session.0, Session { metadata, ..state });
+        
+        Ok(res)
+    }
+
+    // NOTE: same as start, with the commitments to check the shares (see StartCommitments). They are interpolated in the
+    // exponent from the public shares mi*G1 and yi*R, neither m nor y is rebuilt.
+    pub fn start_verifiable(&mut self, sig: ExtSignature, profile: &str, seq: usize, time: SystemTime) -> Result<((PointShareVector, PointShareVector), StartCommitments), ProtocolError> {
+        let res = self.start(sig, profile, seq, time)?;
+
+        let session = &self.sessions[SessionId::new(seq, time).as_str()];
+        let M = (&session.mi * self.G1).reconstruct();
+        let PI = (self.yi(session.profile.threshold) * session.profile.R).reconstruct();
+
+        Ok((res, StartCommitments { M, PI }))
+    }
+
+    // NOTE: starts one session per profile (see SessionId::profile) with a single signature and freshness check.
+    // The client signs (encode_profiles(profiles), seq, time). No session is started if any profile fails.
+    pub fn start_batch(&mut self, sig: ExtSignature, profiles: &[&str], seq: usize, time: SystemTime) -> Result<HashMap<String, (PointShareVector, PointShareVector)>, ProtocolError> {
+        self.timer.phase_start(Phase::NetworkStart);
+        let res = self.start_batch_sessions(sig, profiles, seq, time);
+        self.timer.phase_end(Phase::NetworkStart);
+        res
+    }
+
+    fn start_batch_sessions(&mut self, sig: ExtSignature, profiles: &[&str], seq: usize, time: SystemTime) -> Result<HashMap<String, (PointShareVector, PointShareVector)>, ProtocolError> {
+        let names = encode_profiles(profiles);
+        let seq_bytes = seq.to_le_bytes();
+        let time_str = encode_time(time);
+        let now = self.check_start(&sig, &[names.as_ref(), seq_bytes.as_ref(), time_str.as_bytes()], seq, time)?;
+
+        if profiles.is_empty() || profiles.iter().enumerate().any(|(i, p)| profiles[..i].contains(p)) {
+            return Err(ProtocolError::InvalidProfile)
+        }
+
+        let session = SessionId::new(seq, time);
+        let mut started = Vec::new();
+        for name in profiles {
+            started.push((name, self.start_profile(session.profile(name).as_str(), name, &sig.P1, now)?));
+        }
+
+        self.last += 1;
+        let mut res = HashMap::new();
+        for (name, (shares, state)) in started {
+            self.sessions.insert(session.profile(name).0, state);
+            res.insert(name.to_string(), shares);
+        }
+
+        Ok(res)
+    }
+
+    fn check_start(&self, sig: &ExtSignature, data: &[&[u8]], seq: usize, time: SystemTime) -> Result<Instant, ProtocolError> {
+        //NOTE: verification of client signature
+        if !sig.verify(&self.G1.into(), data) {
+            return Err(ProtocolError::InvalidSignature)
+        }
+
+        //NOTE: verification of client identity and authorizations should be here. However, these stats are not included in the measurements.
+        // * verify if sig.P1 has access?
+        
+        let now = self.clock.now();
+
+        // NOTE: "seq" and "time" in the correct ranges? A window bound out of the time range leaves that side unbounded.
+        let too_old = now.checked_sub(self.max_past).is_some_and(|min| time < min);
+        let too_new = now.checked_add(self.max_future).is_some_and(|max| time > max);
+        if too_old || too_new || seq <= self.last {
+            return Err(ProtocolError::InvalidFreshness)
+        }
+
+        // NOTE: session ages are measured on the local monotonic clock
+        Ok(Instant::now())
+    }
+
+    fn start_profile(&mut self, session: &str, name: &str, Pt: &G1Affine, now: Instant) -> Result<((PointShareVector, PointShareVector), Session), ProtocolError> {
+        let profile = self.profiles.get(name).ok_or(ProtocolError::UnknownProfile)?.clone();
+        let Yl_comp = self.location_keys(&profile.locs)?;
+
+        // NOTE: mi shares may be re-calculated or stored in the session (stateless vs stateful)
+        let Pt_comp = Pt.to_compressed();
+        self.trace("start.Pt", &Pt_comp);
+        self.trace("start.Yl", &Yl_comp);
+        self.trace("start.Ar", &profile.Ar_comp);
+        let mi = self.mi_shares(session, profile.threshold, Pt_comp.as_ref(), Yl_comp.as_ref(), profile.Ar_comp.as_ref());
+
+        // NOTE: each node answers (mi*G1, yi*R) with its own shares
+        let mut res = (PointShareVector(Vec::with_capacity(mi.0.len())), PointShareVector(Vec::with_capacity(mi.0.len())));
+        for (k, m) in mi.0.iter().enumerate() {
+            self.timer.node_start(m.i);
+            let y = &self.yi(profile.threshold).0[k];
+            res.0.0.push(PointShare { i: m.i, Yi: self.G1 * m.yi });
+            res.1.0.push(PointShare { i: y.i, Yi: profile.R * y.yi });
+            self.timer.node_end(m.i);
+        }
+
+        if cfg!(feature = "transcript") {
+            self.trace("start.M", &G1Affine::from(res.0.interpolate()).to_compressed());
+            self.trace("start.PI", &G1Affine::from(res.1.interpolate()).to_compressed());
+        }
+
+        Ok((res, Session { mi, Pt: *Pt, name: name.into(), profile, started: now, metadata: Vec::new(), requested: None }))
+    }
+
+    // NOTE: concatenated Yl of the registered locations, in the profile order. A single location gives its Yl only.
+    fn location_keys(&self, locs: &LocationQuorum) -> Result<Vec<u8>, ProtocolError> {
+        let present = locs.names.iter().filter_map(|loc| self.locations.get(loc)).collect::<Vec<_>>();
+        if present.len() < locs.quorum {
+            return Err(ProtocolError::UnknownLocation)
+        }
+
+        Ok(present.iter().flat_map(|loc| loc.Yl_comp.iter().cloned()).collect())
+    }
+
+    // NOTE: request-token returns Tki shares for reconstruction
+    pub fn request(&mut self, session: &str, Akc: &G1Affine, Kc: &G1Affine) -> Result<PointShareVector, ProtocolError> {
+        self.request_shares(session, Akc, Kc, false).map(|res| res.0)
+    }
+
+    // NOTE: same as request, also returns the metadata of start_with_metadata
+    pub fn request_with_metadata(&mut self, session: &str, Akc: &G1Affine, Kc: &G1Affine) -> Result<(PointShareVector, Vec<u8>), ProtocolError> {
+        self.request_shares(session, Akc, Kc, false)
+    }
+
+    // NOTE: request-token for the profiles of a batch start, with the (Akc, Kc) of each profile. The sessions are consumed
+    // in order, a failure consumes the ones before it.
+    pub fn request_batch(&mut self, session: &SessionId, requests: &[(&str, G1Affine, G1Affine)]) -> Result<HashMap<String, PointShareVector>, ProtocolError> {
+        let mut res = HashMap::new();
+        for (name, Akc, Kc) in requests {
+            let (Tki, _) = self.request_shares(session.profile(name).as_str(), Akc, Kc, false)?;
+            res.insert(name.to_string(), Tki);
+        }
+
+        Ok(res)
+    }
+
+    // NOTE: request-token bound to the client key of start, the token is only usable with a proof-of-possession
+    pub fn request_bound(&mut self, session: &str, Akc: &G1Affine, Kc: &G1Affine) -> Result<PointShareVector, ProtocolError> {
+        self.request_shares(session, Akc, Kc, true).map(|res| res.0)
+    }
+
+    fn request_shares(&mut self, session: &str, Akc: &G1Affine, Kc: &G1Affine, bound: bool) -> Result<(PointShareVector, Vec<u8>), ProtocolError> {
+        self.timer.phase_start(Phase::NetworkRequest);
+        let res = self.request_session(session, Akc, Kc, bound);
+        self.timer.phase_end(Phase::NetworkRequest);
+        res
+    }
+
+    fn request_session(&mut self, session: &str, Akc: &G1Affine, Kc: &G1Affine, bound: bool) -> Result<(PointShareVector, Vec<u8>), ProtocolError> {
+        // NOTE: (Akc, Kc) input validation, kc = 0 gives an identity pair that passes the pairing check
+        if check_public_g1(Kc).is_err() || check_public_g1(Akc).is_err() || !pairing_eq(Akc, &self.G2P, Kc, &self.A2P) {
+            return Err(ProtocolError::InvalidPairing)
+        }
+
+        let id = session;
+        let session = match self.sessions.get(id) {
+            Some(session) => session.clone(),
+            None if self.consumed.contains_key(id) => return Err(ProtocolError::SessionAlreadyConsumed),
+            None => return Err(ProtocolError::UnknownSession)
+        };
+
+        // NOTE: a kept session only answers retries with the same inputs, it never issues a second token
+        if session.requested.is_some_and(|req| req != (*Akc, bound)) {
+            return Err(ProtocolError::SessionAlreadyConsumed)
+        }
+
+        // NOTE: the session of a removed profile is dropped in both modes
+        if !self.complete_removed && !self.profiles.contains_key(&session.name) {
+            self.sessions.remove(id);
+            return Err(ProtocolError::UnknownProfile)
+        }
+
+        match self.retention {
+            SessionRetention::RemoveOnUse => {
+                self.sessions.remove(id);
+                self.consumed.insert(id.into(), session.started);
+            },
+            SessionRetention::KeepUntilExpiry => {
+                self.sessions.get_mut(id).unwrap().requested = Some((*Akc, bound));
+            }
+        }
+
+        self.trace("request.Akc", &Akc.to_compressed());
+        self.trace("request.Kc", &Kc.to_compressed());
+
+        // NOTE: all inputs are validated (yi, mi, Ar, Akc). Each node answers yi*Ard + mi*Akc (+ ai*Pt) with its own shares.
+        let threshold = session.profile.threshold;
+        let (Ard, Akc, Pt) = (session.profile.Ard, G1Projective::from(Akc), G1Projective::from(session.Pt));
+        let mut Tki = PointShareVector(Vec::with_capacity(session.mi.0.len()));
+        for (k, m) in session.mi.0.iter().enumerate() {
+            self.timer.node_start(m.i);
+            let mut Yi = Ard * self.yi(threshold).0[k].yi + Akc * m.yi;
+            if bound {
+                Yi += Pt * self.ai(threshold).0[k].yi;
+            }
+
+            Tki.0.push(PointShare { i: m.i, Yi });
+            self.timer.node_end(m.i);
+        }
+
+        if cfg!(feature = "transcript") {
+            self.trace("request.Tk", &G1Affine::from(Tki.interpolate()).to_compressed());
+        }
+
+        if session.requested.is_none() {
+            // NOTE: M from the public Mi, as the client does. The secret m is never rebuilt, and only issued sessions pay for it.
+            let M = G1Affine::from((&session.mi * self.G1).interpolate());
+            self.audit.record(AuditRecord { profile: session.name, time: self.clock.now(), token: token_id(&M) });
+        }
+
+        Ok((Tki, session.metadata))
+    }
+
+    // NOTE: ni = PRF(key_i, session), the shares are re-derivable from the session alone and nodes can be stateless.
+    // The inputs common to all parties are hashed once, each party only appends its ni.
+    fn mi_shares(&mut self, session: &str, threshold: usize, Pt: &[u8], Yl: &[u8], Ar: &[u8]) -> ShareVector {
+        let common = PrefixHash::new(&[session.as_bytes(), Pt, self.Y_comp.as_ref(), Yl, Ar]);
+
+        let mut mi = Vec::<Share>::new();
+        for i in 1..=threshold+1 {
+            self.timer.node_start(i as u32);
+            let ni = hash(&[b"mi-prf", self.prf_keys[i - 1].as_ref(), session.as_bytes()]);
+            let ni_bytes = ni.to_bytes();
+            let yi = common.hash(&[ni_bytes.as_ref()]);
+            self.trace("start.ni", &ni_bytes);
+            mi.push(Share { i: i as u32, yi });
+            self.timer.node_end(i as u32);
+        }
+
+        // NOTE: extra nodes must hold shares of the same degree, simulates a DKG of mi for larger committees. The extra
+        // shares are Lagrange evaluations of the first "threshold + 1", m is never rebuilt.
+        let n = self.committee(threshold);
+        if n > threshold + 1 {
+            return ShareVector(mi).extend_to(n)
+        }
+
+        ShareVector(mi)
+    }
+
+    // NOTE: dual control, the contribution a*(PI + c*Mk) of this network (as a cosigner) for a token of the issuer.
+    // Only tokens that verify against the issuer alone are co-signed, a deployment would also apply its own policy.
+    pub fn cosign(&self, token: &Token, issuer: &PublicParams) -> Result<PointShareVector, ProtocolError> {
+        if !token.verify(issuer) {
+            return Err(ProtocolError::InvalidSignature)
+        }
+
+        Ok(&self.ai * G1Projective::from(token.pairing_point(issuer)))
+    }
+
+    // NOTE: published commitments Yi = yi*G1 of the y shares for the profile
+    pub fn commitments(&self, profile: &str) -> Result<PointShareVector, ProtocolError> {
+        let profile = self.profiles.get(profile).ok_or(ProtocolError::UnknownProfile)?;
+        Ok(self.yi(profile.threshold) * self.G1)
+    }
+
+    // NOTE: client side of a robust request round, with "t + 2f + 1" Tki shares. Each Tki is checked against the start
+    // shares and the published commitments, e(Tki, G2) == e(PIi + d*Yi + kc*Mi, A2). Returns Tk and the faulty indices.
+    // Bound requests are not supported, the ai*Pt term has no published commitment.
+    pub fn combine_robust(&self, profile: &str, kc: &Scalar, Mi: &PointShareVector, PIi: &PointShareVector, Tki: &PointShareVector, f: usize) -> Result<(G1Projective, Vec<u32>), ProtocolError> {
+        let Yi = self.commitments(profile)?;
+        let profile = &self.profiles[profile];
+        if Tki.0.len() < profile.threshold + 2 * f + 1 {
+            return Err(ProtocolError::InsufficientShares)
+        }
+
+        let d = attributes_digest(&profile.attrs);
+        let find = |shares: &PointShareVector, i: u32| shares.0.iter().find(|s| s.i == i).map(|s| s.Yi);
+
+        let mut valid = Vec::<PointShare>::new();
+        let mut faulty = Vec::<u32>::new();
+        for share in Tki.0.iter() {
+            let ok = match (find(Mi, share.i), find(PIi, share.i), find(&Yi, share.i)) {
+                (Some(Mi), Some(PIi), Some(Yi)) => {
+                    let rhs = G1Affine::from(PIi + Yi * d + Mi * kc);
+                    pairing_eq(&share.Yi.into(), &self.G2P, &rhs, &self.A2P)
+                },
+                _ => false
+            };
+
+            if ok { valid.push(*share) } else { faulty.push(share.i) }
+        }
+
+        if valid.len() < profile.threshold + 1 {
+            return Err(ProtocolError::InsufficientShares)
+        }
+
+        Ok((PointShareVector(valid).interpolate(), faulty))
+    }
+}
+
+//-----------------------------------------------------------------------------------------------------------
+// Public view of the network
+//-----------------------------------------------------------------------------------------------------------
+// NOTE: generators, public keys and the Yi = yi*G1 commitments of the setup shares. Can be published to verifiers.
+#[derive(Debug, Clone)]
+pub struct PublicSetup {
+    pub threshold: usize,
+    pub G1: G1Affine,
+    pub G2: G2Affine,
+    pub Y: G1Affine,
+    pub A1: G1Affine,
+    pub A2: G2Affine,
+    pub commitments: PointShareVector
+}
+
+impl PublicSetup {
+    pub fn verifier(&self) -> PublicParams {
+        PublicParams::new(self.Y, self.A1, self.A2, self.commitments.clone())
+    }
+}
+
+// NOTE: the published setup for external auditors, with the Feldman commitments (coefficients times G1) of the yi and
+// ai polynomials instead of the shares. The commitment of party i is the evaluation at i, and the constant terms are
+// (Y, A1). Serializable with the "cbor" feature.
+#[derive(Debug, Clone)]
+pub struct PublicBundle {
+    pub threshold: usize,
+    pub parties: usize,
+    pub G1: G1Affine,
+    pub G2A: G2Affine,
+    pub Y: G1Affine,
+    pub A1: G1Affine,
+    pub A2A: G2Affine,
+    pub y_commitment: PointPolynomial,
+    pub a_commitment: PointPolynomial
+}
+
+impl PublicBundle {
+    // NOTE: with the Yi = yi*G1 commitments of parties 1..=n, as in NetworkSetup::public_params
+    pub fn verifier(&self) -> PublicParams {
+        let commitments = (1..=self.parties as u32).map(|i| PointShare { i, Yi: self.y_commitment.evaluate(Scalar::from(i as u64)) }).collect();
+        PublicParams::new(self.Y, self.A1, self.A2A, PointShareVector(commitments))
+    }
+
+    // NOTE: the polynomials have the setup degree and secrets (y, a), and a is the same in G1 and G2
+    pub fn is_consistent(&self) -> bool {
+        self.y_commitment.0.len() == self.threshold + 1 && self.a_commitment.0.len() == self.threshold + 1
+            && self.y_commitment.0[0] == G1Projective::from(self.Y) && self.a_commitment.0[0] == G1Projective::from(self.A1)
+            && pairing_eq(&self.A1, &self.G2A.into(), &self.G1, &self.A2A.into())
+    }
+
+    // NOTE: a published share yi*G1 of party i
+    pub fn verify_y_share(&self, share: &PointShare) -> bool {
+        self.y_commitment.evaluate(Scalar::from(share.i as u64)) == share.Yi
+    }
+
+    // NOTE: a published share ai*G1 of party i
+    pub fn verify_a_share(&self, share: &PointShare) -> bool {
+        self.a_commitment.evaluate(Scalar::from(share.i as u64)) == share.Yi
+    }
+}
+
+// NOTE: verification parameters, without any secret of the setup. The G2 bases are prepared once for all verifications.
+#[derive(Debug, Clone)]
+pub struct PublicParams {
+    pub G1: G1Projective,
+    pub G2A: G2Affine,
+    pub G2P: G2Prepared,
+
+    pub Y: G1Projective,
+    pub Y_comp: [u8; G1_COMPRESSED_LEN],
+    pub A1: G1Projective,
+    pub A2A: G2Affine,
+    pub A2P: G2Prepared,
+    pub commitments: PointShareVector,
+
+    // NOTE: hash of the public keys (Y, A2), bound into the challenge and signature of issued tokens
+    pub setup_id: [u8; 32]
+}
+
+impl PublicParams {
+    pub fn new(Y: G1Affine, A1: G1Affine, A2: G2Affine, commitments: PointShareVector) -> Self {
+        let G2A = G2Affine::generator();
+        Self {
+            G1: G1Projective::generator(), G2A, G2P: G2A.into(),
+            Y: Y.into(), Y_comp: Y.to_compressed(), A1: A1.into(), A2A: A2, A2P: A2.into(),
+            commitments, setup_id: hash(&[b"setup-id", &Y.to_compressed(), &A2.to_compressed()]).to_bytes()
+        }
+    }
+
+    // NOTE: from the network public keys only, enough to verify tokens
+    pub fn from_keys(Y: G1Affine, A2: G2Affine) -> Self {
+        Self::new(Y, G1Affine::identity(), A2, PointShareVector(Vec::new()))
+    }
+
+    // NOTE: params of a federated network, the sum of two independent setups over the same party indexes.
+    // Commitments are summed only when both are published.
+    pub fn combine(&self, other: &PublicParams) -> PublicParams {
+        let commitments = if self.commitments.0.is_empty() || other.commitments.0.is_empty() {
+            PointShareVector(Vec::new())
+        } else {
+            &self.commitments + &other.commitments
+        };
+
+        let A2 = G2Projective::from(self.A2A) + G2Projective::from(other.A2A);
+        Self::new((self.Y + other.Y).into(), (self.A1 + other.A1).into(), A2.into(), commitments)
+    }
+
+    // NOTE: the keys decoded once for a backend, reused by all Token::verify_with calls
+    pub fn engine<E: PairingEngine>(&self) -> Result<EngineParams<E>, CryptoError> {
+        Ok(EngineParams {
+            setup_id: self.setup_id,
+            Y: E::g1_from_affine(&self.Y.into()),
+            G2: E::g2_decompress(&self.G2A.to_compressed())?,
+            A2: E::g2_decompress(&self.A2A.to_compressed())?
+        })
+    }
+}
+
+// NOTE: verification parameters on a pairing backend, see PublicParams::engine
+pub struct EngineParams<E: PairingEngine> {
+    pub setup_id: [u8; 32],
+    pub Y: E::G1,
+    pub G2: E::G2,
+    pub A2: E::G2
+}
+
+impl NetworkSetup {
+    pub fn public(&self) -> PublicSetup {
+        PublicSetup {
+            threshold: self.threshold,
+            G1: self.G1.into(), G2: self.G2A,
+            Y: self.Y.into(), A1: self.A1.into(), A2: self.A2A,
+            commitments: self.params.commitments.clone()
+        }
+    }
+
+    // NOTE: excludes all shares, the polynomials are reconstructed from them and only their commitments are exported
+    pub fn export_public_bundle(&self) -> PublicBundle {
+        PublicBundle {
+            threshold: self.threshold, parties: self.parties,
+            G1: self.G1.into(), G2A: self.G2A,
+            Y: self.Y.into(), A1: self.A1.into(), A2A: self.A2A,
+            y_commitment: &self.yi.reconstruct() * self.G1,
+            a_commitment: &self.ai.reconstruct() * self.G1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = "EHR";
+    const LOCATION: &str = "Hospital";
+
+    fn network(threshold: usize) -> NetworkSetup {
+        let l = rnd_scalar();
+        let r = rnd_scalar();
+
+        let mut setup = NetworkSetup::new(threshold);
+        setup.location(LOCATION, setup.Y * l);
+        setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+        setup
+    }
+
+    fn start_at(setup: &mut NetworkSetup, st: &Scalar, seq: usize, time: SystemTime) -> Result<(PointShareVector, PointShareVector), ProtocolError> {
+        let sig = ExtSignature::sign(st, &setup.G1.into(), &[PROFILE.as_bytes(), &seq.to_le_bytes(), encode_time(time).as_bytes()]);
+        setup.start(sig, PROFILE, seq, time)
+    }
+
+    // NOTE: full client roundtrip as in main
+    fn issue(setup: &mut NetworkSetup, profile: &str, seq: usize) -> Result<Token, ProtocolError> {
+        issue_for(setup, profile, seq, b"")
+    }
+
+    fn issue_for(setup: &mut NetworkSetup, profile: &str, seq: usize, scope: &[u8]) -> Result<Token, ProtocolError> {
+        issue_with(setup, profile, seq, &TestClient::rnd(), scope, false)
+    }
+
+    // NOTE: client key, client-token key and start time
+    struct TestClient {
+        st: Scalar,
+        k: Scalar,
+        nonce: [u8; 32],
+        time: SystemTime,
+        issued_at: u64
+    }
+
+    impl TestClient {
+        fn rnd() -> Self {
+            Self { st: rnd_scalar(), k: rnd_scalar(), nonce: rnd_scalar().to_bytes(), time: SystemTime::now(), issued_at: unix_time(SystemTime::now()) }
+        }
+    }
+
+    fn issue_with(setup: &mut NetworkSetup, profile: &str, seq: usize, client: &TestClient, scope: &[u8], bound: bool) -> Result<Token, ProtocolError> {
+        let (st, k, time) = (&client.st, client.k, client.time);
+        let session = SessionId::new(seq, time);
+
+        let seq_bytes = seq.to_le_bytes();
+        let time_str = encode_time(time);
+        let data = &[profile.as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()];
+        let sig = ExtSignature::sign(st, &setup.G1.into(), data);
+        let Pt = sig.P1;
+
+        let (Mi, PIi) = setup.start(sig, profile, seq, time)?;
+        let M = Mi.interpolate();
+        let Mk = M * k;
+        let PI = PIi.interpolate();
+
+        let M_comp = G1Affine::from(M).to_compressed();
+        let Mk_comp = G1Affine::from(Mk).to_compressed();
+        let PI_comp = G1Affine::from(PI).to_compressed();
+
+        let c = hash(&[setup.params.setup_id.as_ref(), &M_comp, &Mk_comp, &PI_comp]);
+        #[cfg(feature = "transcript")]
+        setup.transcript().append("client.Mk", &Mk_comp).append("client.c", &c.to_bytes());
+
+        let Kc = setup.G1 * (k * c);
+        let Akc = setup.A1 * (k * c);
+
+        let Tki = if bound {
+            setup.request_bound(session.as_str(), &Akc.into(), &Kc.into())?
+        } else {
+            setup.request(session.as_str(), &Akc.into(), &Kc.into())?
+        };
+
+        let Tk = Tki.interpolate();
+        let holder = if bound { Some(Pt) } else { None };
+        let claims = Claims { attrs: setup.profiles[profile].attrs.clone(), holder, scope: scope.to_vec(), nonce: client.nonce, issued_at: client.issued_at };
+        Ok(Token::with_claims(k, Tk.into(), M.into(), PI.into(), claims, &setup.params.setup_id))
+    }
+
+    fn rnd_token() -> Token {
+        let G1 = G1Projective::generator();
+        let Tk = G1 * rnd_scalar();
+        let M = G1 * rnd_scalar();
+        let PI = G1 * rnd_scalar();
+
+        Token::new(rnd_scalar(), Tk.into(), M.into(), PI.into(), &[0u8; 32])
+    }
+
+    #[test]
+    fn token_encoding() {
+        let token = rnd_token();
+
+        for enc in [PointEncoding::Compressed, PointEncoding::Uncompressed].iter() {
+            let bytes = token.to_bytes_with(*enc);
+            assert!(bytes.len() == 2 + 4 * enc.g1_len() + 2 * 32 + 4 + 1 + 4 + 32 + 8);
+            assert!(bytes.len() == token.serialized_len_with(*enc));
+
+            let res = Token::from_bytes(&bytes).unwrap();
+            assert!(res.to_bytes_with(*enc) == bytes);
+            assert!(res.sig.verify(&res.M, &[[0u8; 32].as_ref(), res.Tk.to_compressed().as_ref(), res.PI.to_compressed().as_ref(), &token.claims.nonce, &[], &token.claims.issued_at.to_le_bytes()]));
+        }
+
+        let mut bytes = token.to_bytes();
+        bytes[1] = 0xff;
+        assert!(Token::from_bytes(&bytes).err() == Some(CryptoError::InvalidEncoding));
+
+        // the group order q is a non-canonical encoding of zero
+        let mut bytes = token.to_bytes();
+        let offset = 2 + 4 * G1_COMPRESSED_LEN;
+        let mut q = (-Scalar::one()).to_bytes();
+        q[0] += 1;
+        bytes[offset..offset + 32].copy_from_slice(&q);
+        assert!(Token::from_bytes(&bytes).err() == Some(CryptoError::NonCanonicalScalar));
+    }
+
+    #[test]
+    fn token_versions() {
+        let token = rnd_token();
+        let bytes = token.to_bytes();
+        assert!(bytes[0] == TOKEN_VERSION);
+
+        // a newer minor version with an appended field
+        let mut minor = bytes.clone();
+        minor[0] = TOKEN_VERSION + 1;
+        minor.extend_from_slice(&[1, 2, 3]);
+        assert!(Token::from_bytes(&minor).unwrap().to_bytes() == bytes);
+
+        // trailing bytes are still rejected for the current version
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Token::from_bytes(&trailing).err() == Some(CryptoError::InvalidLength));
+
+        let mut major = bytes;
+        major[0] = TOKEN_VERSION + 0x10;
+        assert!(Token::from_bytes(&major).err() == Some(CryptoError::UnsupportedVersion));
+        assert!(Token::from_bytes(&[]).err() == Some(CryptoError::InvalidLength));
+    }
+
+    #[test]
+    fn audit_log() {
+        let mut setup = network(2);
+        let from = SystemTime::now();
+
+        let t1 = issue(&mut setup, PROFILE, 1).unwrap();
+        let t2 = issue(&mut setup, PROFILE, 2).unwrap();
+        assert!(t1.verify(setup.public_params()) && t2.verify(setup.public_params()));
+
+        let records = setup.audit().by_profile(PROFILE);
+        assert!(records.len() == 2);
+        assert!(records[0].token == t1.id() && records[1].token == t2.id());
+        assert!(setup.audit().by_time(from, SystemTime::now()).len() == 2);
+        assert!(setup.audit().by_profile("Other").is_empty());
+
+        // failed issuances are not recorded
+        assert!(issue(&mut setup, "Other", 3).err() == Some(ProtocolError::UnknownProfile));
+
+        let Kc = setup.G1 * rnd_scalar();
+        let Akc = setup.A1 * rnd_scalar();
+        assert!(setup.request("none", &Akc.into(), &Kc.into()).err() == Some(ProtocolError::InvalidPairing));
+
+        let x = rnd_scalar();
+        let (Kc, Akc) = (setup.G1 * x, setup.A1 * x);
+        assert!(setup.request("none", &Akc.into(), &Kc.into()).err() == Some(ProtocolError::UnknownSession));
+        assert!(setup.audit().len() == 2);
+
+        // records are stamped by the setup clock, as the start freshness
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        setup.set_clock(Box::new(FixedClock(time)));
+        let t3 = issue_with(&mut setup, PROFILE, 4, &TestClient { time, ..TestClient::rnd() }, b"", false).unwrap();
+        let records = setup.audit().by_time(time, time);
+        assert!(records.len() == 1 && records[0].token == t3.id());
+    }
+
+    #[test]
+    fn profile_thresholds() {
+        let mut setup = network(3);
+
+        let r = rnd_scalar();
+        setup.profile_with_threshold("Low", LOCATION, setup.G1 * r, setup.A1 * r, 1).unwrap();
+
+        let high = issue(&mut setup, PROFILE, 1).unwrap();
+        let low = issue(&mut setup, "Low", 2).unwrap();
+        assert!(high.verify(setup.public_params()) && low.verify(setup.public_params()));
+
+        assert!(setup.profile_with_threshold("Zero", LOCATION, setup.G1 * r, setup.A1 * r, 0) == Err(ProtocolError::InvalidProfile));
+        assert!(setup.profile_with_threshold("High", LOCATION, setup.G1 * r, setup.A1 * r, 4) == Err(ProtocolError::InvalidProfile));
+        assert!(setup.profile_with_threshold("Nowhere", "unknown", setup.G1 * r, setup.A1 * r, 1) == Err(ProtocolError::UnknownLocation));
+    }
+
+    #[test]
+    fn engine_verification() {
+        let mut setup = network(2);
+        let token = issue(&mut setup, PROFILE, 1).unwrap();
+        assert!(token.verify(setup.public_params()) && token.verify_with(&setup.public_params().engine::<Bls12Engine>().unwrap()));
+
+        let mut tampered = issue(&mut setup, PROFILE, 2).unwrap();
+        tampered.Tk = token.Tk;
+        assert!(!tampered.verify(setup.public_params()) && !tampered.verify_with(&setup.public_params().engine::<Bls12Engine>().unwrap()));
+
+        // the holder is folded into the pairing point on each backend
+        let bound = issue_with(&mut setup, PROFILE, 3, &TestClient::rnd(), b"", true).unwrap();
+        assert!(bound.verify_with(&setup.public_params().engine::<Bls12Engine>().unwrap()));
+
+        #[cfg(feature = "blst")] {
+            let params = setup.public_params().engine::<BlstEngine>().unwrap();
+            assert!(token.verify_with(&params) && bound.verify_with(&params));
+            assert!(!tampered.verify_with(&params));
+        }
+    }
+
+    #[test]
+    fn engine_tokens() {
+        let G1 = G1Projective::generator();
+        let (k, Tk, M, PI) = (rnd_scalar(), G1 * rnd_scalar(), G1 * rnd_scalar(), G1 * rnd_scalar());
+        let claims = Claims { nonce: rnd_scalar().to_bytes(), issued_at: 1_600_000_000, ..Claims::default() };
+        let setup_id = [7u8; 32];
+
+        let token = Token::with_claims(k, Tk.into(), M.into(), PI.into(), claims.clone(), &setup_id);
+        let token_e = Token::sign_with::<Bls12Engine>(k, Tk.into(), M.into(), PI.into(), claims.clone(), &setup_id).unwrap();
+        assert!(token_e.to_bytes() == token.to_bytes());
+
+        #[cfg(feature = "blst")] {
+            let token_b = Token::sign_with::<BlstEngine>(k, Tk.into(), M.into(), PI.into(), claims, &setup_id).unwrap();
+            assert!(token_b.to_bytes() == token.to_bytes());
+        }
+    }
+
+    #[test]
+    fn selective_disclosure() {
+        let mut setup = network(2);
+
+        let attrs = vec![Attribute::new("role", b"doctor"), Attribute::new("department", b"cardiology"), Attribute::new("level", b"3")];
+        let commitments = attrs.iter().map(|a| a.commitment()).collect::<Vec<_>>();
+
+        let r = rnd_scalar();
+        setup.profile_with_attributes("Attrs", LOCATION, setup.G1 * r, setup.A1 * r, &commitments).unwrap();
+        let token = issue(&mut setup, "Attrs", 1).unwrap();
+        assert!(token.verify(setup.public_params()) && token.verify_with(&setup.public_params().engine::<Bls12Engine>().unwrap()));
+
+        let disclosed = Token::disclose(&attrs, &["role"]);
+        assert!(disclosed.len() == 1 && token.verify_disclosure(setup.public_params(), &disclosed));
+
+        // undisclosed attributes can't be confirmed by guessing without the salt
+        let guess = Attribute::new("department", b"cardiology");
+        assert!(!token.verify_disclosure(setup.public_params(), &[guess]));
+
+        let forged = Attribute { value: b"admin".to_vec(), ..disclosed[0].clone() };
+        assert!(!token.verify_disclosure(setup.public_params(), &[forged]));
+
+        // attributes are certified by the network
+        let mut stripped = Token::from_bytes(&token.to_bytes()).unwrap();
+        stripped.claims.attrs.pop();
+        assert!(!stripped.verify(setup.public_params()));
+    }
+
+    #[test]
+    fn scopes() {
+        let mut setup = network(2);
+        let token = issue_for(&mut setup, PROFILE, 1, b"records.example.org").unwrap();
+
+        assert!(token.verify_for(setup.public_params(), b"records.example.org"));
+        assert!(!token.verify_for(setup.public_params(), b"billing.example.org"));
+        assert!(!token.verify_for(setup.public_params(), b""));
+
+        // the scope is bound into the signature
+        let mut moved = Token::from_bytes(&token.to_bytes()).unwrap();
+        moved.claims.scope = b"billing.example.org".to_vec();
+        assert!(!moved.verify_for(setup.public_params(), b"billing.example.org"));
+    }
+
+    #[test]
+    fn holder_of_key() {
+        let mut setup = network(2);
+
+        let client = TestClient::rnd();
+        let st = client.st;
+        let token = issue_with(&mut setup, PROFILE, 1, &client, b"", true).unwrap();
+        assert!(token.claims.holder == Some((setup.G1 * st).into()));
+        assert!(token.verify(setup.public_params()) && token.verify_with(&setup.public_params().engine::<Bls12Engine>().unwrap()));
+
+        let proof = token.present(&st, b"challenge").unwrap();
+        assert!(token.verify_presentation(setup.public_params(), b"challenge", &proof));
+        assert!(!token.verify_presentation(setup.public_params(), b"other", &proof));
+
+        let wrong = token.present(&rnd_scalar(), b"challenge").unwrap();
+        assert!(!token.verify_presentation(setup.public_params(), b"challenge", &wrong));
+
+        // the holder key is certified by the network
+        let mut stolen = Token::from_bytes(&token.to_bytes()).unwrap();
+        let thief = rnd_scalar();
+        stolen.claims.holder = Some((setup.G1 * thief).into());
+        let proof = stolen.present(&thief, b"challenge").unwrap();
+        assert!(!stolen.verify_presentation(setup.public_params(), b"challenge", &proof));
+
+        // unbound tokens have no holder to prove
+        let unbound = issue(&mut setup, PROFILE, 2).unwrap();
+        assert!(unbound.present(&st, b"challenge").is_none());
+    }
+
+    #[test]
+    fn seeded_runs() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let (time, issued_at) = (SystemTime::now(), unix_time(SystemTime::now()));
+        let run = |seed: u64| {
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            let (l, r) = (rnd_scalar_with(&mut rng), rnd_scalar_with(&mut rng));
+            let (st, k) = (rnd_scalar_with(&mut rng), rnd_scalar_with(&mut rng));
+            let client = TestClient { st, k, nonce: rnd_scalar_with(&mut rng).to_bytes(), time, issued_at };
+
+            let mut setup = NetworkSetup::with_rng(2, rng);
+            setup.location(LOCATION, setup.Y * l);
+            setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+            issue_with(&mut setup, PROFILE, 1, &client, b"", false).unwrap().to_bytes()
+        };
+
+        assert!(run(7) == run(7));
+        assert!(run(7) != run(8));
+    }
+
+    #[test]
+    fn master_seed() {
+        let snapshot = |setup: &NetworkSetup| {
+            let mut bytes = setup.params.setup_id.to_vec();
+            bytes.extend_from_slice(&setup.A2A.to_compressed());
+            for share in setup.yi.0.iter().chain(setup.ai.0.iter()) {
+                bytes.extend_from_slice(&share.i.to_le_bytes());
+                bytes.extend_from_slice(&share.yi.to_bytes());
+            }
+
+            bytes.extend(setup.prf_keys.iter().flatten());
+            bytes
+        };
+
+        let seed = [9u8; 32];
+        let (first, second) = (NetworkSetup::from_master_seed(2, seed), NetworkSetup::from_master_seed(2, seed));
+        assert!(snapshot(&first) == snapshot(&second));
+        assert!(snapshot(&first) != snapshot(&NetworkSetup::from_master_seed(2, [8u8; 32])));
+
+        // y is the first KDF draw
+        let y = rnd_scalar_with(&mut SeedKdf::new(seed));
+        assert!(first.Y == first.G1 * y && first.yi.interpolate() == y);
+    }
+
+    #[test]
+    fn deterministic_setups() {
+        let client = TestClient { nonce: [7u8; 32], ..TestClient::rnd() };
+        let run = |seed: [u8; 32]| {
+            let mut setup = NetworkSetup::deterministic(2, seed);
+            setup.location(LOCATION, setup.Y);
+            setup.profile(PROFILE, LOCATION, setup.G1, setup.A1).unwrap();
+            let token = issue_with(&mut setup, PROFILE, 1, &client, b"", false).unwrap();
+            assert!(token.verify(setup.public_params()));
+            (setup.public_params().setup_id, token.to_bytes())
+        };
+
+        assert!(run([1u8; 32]) == run([1u8; 32]));
+        assert!(run([1u8; 32]) != run([2u8; 32]));
+    }
+
+    #[test]
+    fn cached_pairings() {
+        use bls12_381::pairing;
+
+        // NOTE: the pairing equation before precomputation
+        let naive = |token: &Token, setup: &NetworkSetup| {
+            let c = token_challenge(&setup.params.setup_id, &token.M, &token.sig.P1, &token.PI);
+            pairing(&token.Tk, &setup.G2A) == pairing(&token.PI, &setup.A2A) + pairing(&(token.sig.P1 * c).into(), &setup.A2A)
+        };
+
+        let mut setup = network(2);
+        let token = issue(&mut setup, PROFILE, 1).unwrap();
+        assert!(token.verify(setup.public_params()) && naive(&token, &setup));
+
+        let mut forged = Token::from_bytes(&token.to_bytes()).unwrap();
+        forged.Tk = (forged.Tk + setup.G1).into();
+        assert!(!forged.verify(setup.public_params()) && !naive(&forged, &setup));
+
+        // profile and request validations
+        let r = rnd_scalar();
+        setup.profile_with_threshold("OK", LOCATION, setup.G1 * r, setup.A1 * r, 1).unwrap();
+        let Kc = setup.G1 * r;
+        assert!(setup.request("none", &(setup.A1 * rnd_scalar()).into(), &Kc.into()).err() == Some(ProtocolError::InvalidPairing));
+        assert!(setup.request("none", &(setup.A1 * r).into(), &Kc.into()).err() == Some(ProtocolError::UnknownSession));
+    }
+
+    #[test]
+    fn robust_request() {
+        let (threshold, f) = (2, 1);
+        let mut setup = NetworkSetup::with_committee(threshold, threshold + 2 * f + 1, ChaCha20Rng::from_entropy());
+
+        let r = rnd_scalar();
+        setup.location(LOCATION, setup.Y * rnd_scalar());
+        setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+
+        let TestClient { st, k, time, .. } = TestClient::rnd();
+        let session = SessionId::new(1, time);
+        let seq_bytes = 1usize.to_le_bytes();
+        let time_str = encode_time(time);
+        let data = &[PROFILE.as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()];
+        let sig = ExtSignature::sign(&st, &setup.G1.into(), data);
+
+        let (Mi, PIi) = setup.start(sig, PROFILE, 1, time).unwrap();
+        assert!(Mi.0.len() == 5);
+        let M = Mi.interpolate();
+        let PI = PIi.interpolate();
+
+        let c = token_challenge(&setup.params.setup_id, &M.into(), &(M * k).into(), &PI.into());
+        let kc = k * c;
+        let mut Tki = setup.request(session.as_str(), &(setup.A1 * kc).into(), &(setup.G1 * kc).into()).unwrap();
+
+        // node 2 returns garbage
+        Tki.0[1].Yi = setup.G1 * rnd_scalar();
+        assert!(!Token::new(k, Tki.interpolate().into(), M.into(), PI.into(), &setup.params.setup_id).verify(setup.public_params()));
+
+        let (Tk, faulty) = setup.combine_robust(PROFILE, &kc, &Mi, &PIi, &Tki, f).unwrap();
+        assert!(faulty == vec![2]);
+        assert!(Token::new(k, Tk.into(), M.into(), PI.into(), &setup.params.setup_id).verify(setup.public_params()));
+
+        // not enough shares for the tolerated faults
+        let partial = PointShareVector(Tki.0[0..4].to_vec());
+        assert!(setup.combine_robust(PROFILE, &kc, &Mi, &PIi, &partial, f).err() == Some(ProtocolError::InsufficientShares));
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_token() {
+        let mut setup = network(2);
+        let token = issue_for(&mut setup, PROFILE, 1, b"resource").unwrap();
+
+        let bytes = token.to_cbor();
+        assert!(bytes.len() < token.to_bytes().len() + 32);
+
+        // verifiers only need the published view
+        let verifier = PublicSetup::from_cbor(&setup.public().to_cbor()).unwrap().verifier();
+        let res = Token::from_cbor(&bytes).unwrap();
+        assert!(res.to_bytes() == token.to_bytes());
+        assert!(res.verify(&verifier));
+    }
+
+    #[test]
+    fn public_bundle() {
+        let mut setup = network(2);
+        let token = issue(&mut setup, PROFILE, 1).unwrap();
+
+        let bundle = setup.export_public_bundle();
+        assert!(bundle.is_consistent());
+        assert!(token.verify(&bundle.verifier()));
+
+        for (yi, ai) in setup.yi.0.iter().zip(setup.ai.0.iter()) {
+            assert!(bundle.verify_y_share(&PointShare { i: yi.i, Yi: setup.G1 * yi.yi }));
+            assert!(bundle.verify_a_share(&PointShare { i: ai.i, Yi: setup.G1 * ai.yi }));
+            assert!(!bundle.verify_y_share(&PointShare { i: yi.i, Yi: setup.G1 * ai.yi }));
+        }
+
+        // the verifier commitments are the ones of the setup
+        let published = bundle.verifier().commitments;
+        assert!(published.0.iter().zip(setup.public_params().commitments.0.iter()).all(|(a, b)| a.i == b.i && a.Yi == b.Yi));
+
+        #[cfg(feature = "cbor")]
+        {
+            let res = PublicBundle::from_cbor(&bundle.to_cbor()).unwrap();
+            assert!(res.is_consistent() && res.y_commitment == bundle.y_commitment && res.a_commitment == bundle.a_commitment);
+            assert!(token.verify(&res.verifier()));
+        }
+    }
+
+    #[test]
+    fn max_age() {
+        let mut setup = network(2);
+        let token = issue(&mut setup, PROFILE, 1).unwrap();
+        let issued = SystemTime::UNIX_EPOCH + Duration::from_secs(token.claims.issued_at);
+        let hour = Duration::from_secs(3600);
+
+        assert!(token.verify_with_max_age(setup.public_params(), issued + Duration::from_secs(60), hour));
+        assert!(token.verify_with_max_age(setup.public_params(), issued + hour, hour));
+        assert!(!token.verify_with_max_age(setup.public_params(), issued + hour + Duration::from_secs(1), hour));
+        assert!(!token.verify_with_max_age(setup.public_params(), issued - Duration::from_secs(1), hour));
+
+        // the issuance time is signed
+        let mut older = Token::from_bytes(&token.to_bytes()).unwrap();
+        older.claims.issued_at -= 1;
+        assert!(!older.verify(setup.public_params()));
+    }
+
+    #[test]
+    fn replays() {
+        let mut setup = network(2);
+        let mut store = MemoryNonceStore::new(Duration::from_secs(60));
+        let now = SystemTime::now();
+
+        let token = issue(&mut setup, PROFILE, 1).unwrap();
+        assert!(token.verify_once(setup.public_params(), &mut store, now));
+        assert!(!token.verify_once(setup.public_params(), &mut store, now));
+
+        // a different nonce breaks the signature
+        let mut other = Token::from_bytes(&token.to_bytes()).unwrap();
+        other.claims.nonce = rnd_scalar().to_bytes();
+        assert!(!other.verify_once(setup.public_params(), &mut store, now));
+        assert!(store.len() == 1);
+
+        let fresh = issue(&mut setup, PROFILE, 2).unwrap();
+        assert!(fresh.verify_once(setup.public_params(), &mut store, now));
+    }
+
+    #[cfg(feature = "transcript")]
+    #[test]
+    fn transcript() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let r = rnd_scalar_with(&mut rng);
+        let client = TestClient { st: rnd_scalar_with(&mut rng), k: rnd_scalar_with(&mut rng), nonce: [0u8; 32], ..TestClient::rnd() };
+
+        let mut setup = NetworkSetup::with_rng(2, rng);
+        setup.location(LOCATION, setup.Y);
+        setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+        let token = issue_with(&mut setup, PROFILE, 1, &client, b"", false).unwrap();
+        let setup_id = setup.params.setup_id;
+
+        let transcript = setup.transcript();
+        assert!(transcript.labels() == vec![
+            "start.profile", "start.seq", "start.time", "start.session", "start.Pt", "start.Yl", "start.Ar",
+            "start.ni", "start.ni", "start.ni", "start.M", "start.PI",
+            "client.Mk", "client.c",
+            "request.Akc", "request.Kc", "request.Tk"
+        ]);
+
+        // ni, M and Tk depend on the session time, the remaining values are fixed by the seed
+        let hex = |label: &str| transcript.get(label).unwrap().iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        assert!(hex("start.PI") == "8af9067b56819513876113093407001db7a276491bc4e1a94b0f581745efe00b37b0cebf0bcbcc13b0b1fa14a3a14eae");
+
+        // the recorded challenge is reproducible from the recorded inputs
+        let c = hash(&[setup_id.as_ref(), transcript.get("start.M").unwrap(), transcript.get("client.Mk").unwrap(), transcript.get("start.PI").unwrap()]);
+        assert!(transcript.get("client.c") == Some(c.to_bytes().as_ref()));
+        assert!(transcript.get("request.Tk") == Some(token.Tk.to_compressed().as_ref()));
+    }
+
+    #[test]
+    fn validation() {
+        let mut setup = network(2);
+        setup.profile_with_threshold("low", LOCATION, setup.G1, setup.A1, 1).unwrap();
+        assert!(setup.validate().is_ok());
+
+        let r = rnd_scalar();
+        assert!(setup.update_profile(PROFILE, setup.G1 * r, setup.A1 * r).is_ok());
+        assert!(setup.update_profile(PROFILE, setup.G1 * r, setup.A1 * rnd_scalar()) == Err(ProtocolError::InvalidPairing));
+        assert!(setup.update_profile("unknown", setup.G1, setup.A1) == Err(ProtocolError::UnknownProfile));
+        assert!(issue(&mut setup, PROFILE, 1).unwrap().verify(setup.public_params()));
+
+        // corrupted profile
+        let A1 = setup.A1;
+        setup.profiles.get_mut(PROFILE).unwrap().Ar = A1 * rnd_scalar();
+        assert!(setup.validate() == Err(ProtocolError::InvalidProfile));
+        setup.profiles.get_mut(PROFILE).unwrap().Ar = A1 * r;
+        assert!(setup.validate().is_ok());
+
+        // corrupted shares, also for a reshared threshold
+        setup.yi.0[0].yi += Scalar::one();
+        assert!(setup.validate() == Err(ProtocolError::InvalidShares));
+        setup.yi.0[0].yi -= Scalar::one();
+        let (_, ai) = setup.reshared.get_mut(&1).unwrap();
+        ai.0[0].yi += Scalar::one();
+        assert!(setup.validate() == Err(ProtocolError::InvalidShares));
+    }
+
+    #[test]
+    fn serialized_len() {
+        let mut setup = network(2);
+        let attrs = [[1u8; 32], [2u8; 32]];
+        setup.profile_with_attributes("attrs", LOCATION, setup.G1, setup.A1, &attrs).unwrap();
+
+        assert!(rnd_token().serialized_len() == TOKEN_BASE_LEN);
+
+        for (seq, scope) in [b"".as_ref(), b"resource", &[7u8; 300]].iter().enumerate() {
+            let tokens = [
+                issue_with(&mut setup, PROFILE, 3 * seq + 1, &TestClient::rnd(), scope, false).unwrap(),
+                issue_with(&mut setup, PROFILE, 3 * seq + 2, &TestClient::rnd(), scope, true).unwrap(),
+                issue_with(&mut setup, "attrs", 3 * seq + 3, &TestClient::rnd(), scope, true).unwrap()
+            ];
+
+            for token in tokens.iter() {
+                assert!(token.to_bytes().len() == token.serialized_len());
+                assert!(token.to_bytes_with(PointEncoding::Uncompressed).len() == token.serialized_len_with(PointEncoding::Uncompressed));
+            }
+        }
+    }
+
+    #[test]
+    fn mi_prf() {
+        let mut setup = NetworkSetup::with_committee(2, 5, ChaCha20Rng::from_entropy());
+        let session = SessionId::new(1, SystemTime::now());
+        let (Pt, Yl, Ar) = ([1u8; G1_COMPRESSED_LEN], [2u8; G1_COMPRESSED_LEN], [3u8; G1_COMPRESSED_LEN]);
+
+        // the same session re-derives the same shares, e.g. after a node restart
+        let mi = setup.mi_shares(session.as_str(), 2, &Pt, &Yl, &Ar);
+        let ni = mi.0.iter().map(|s| s.yi).collect::<Vec<_>>();
+        let again = setup.mi_shares(session.as_str(), 2, &Pt, &Yl, &Ar);
+        assert!(ni.len() == 5 && again.0.iter().map(|s| s.yi).collect::<Vec<_>>() == ni);
+
+        // independent values per node and per session
+        assert!((0..ni.len()).all(|i| (i + 1..ni.len()).all(|j| ni[i] != ni[j])));
+
+        let other = setup.mi_shares(SessionId::new(2, SystemTime::now()).as_str(), 2, &Pt, &Yl, &Ar);
+        assert!(other.interpolate() != mi.interpolate());
+    }
+
+    #[test]
+    fn mi_common_prefix() {
+        let mut setup = NetworkSetup::new(3);
+        let session = SessionId::new(1, SystemTime::now());
+        let (Pt, Yl, Ar) = ([1u8; G1_COMPRESSED_LEN], [2u8; G1_COMPRESSED_LEN], [3u8; G1_COMPRESSED_LEN]);
+
+        // same values as hashing all inputs per party
+        let mi = setup.mi_shares(session.as_str(), 3, &Pt, &Yl, &Ar);
+        for (share, key) in mi.0.iter().zip(setup.prf_keys.iter()) {
+            let ni = hash(&[b"mi-prf", key.as_ref(), session.as_str().as_bytes()]).to_bytes();
+            assert!(share.yi == hash(&[session.as_str().as_bytes(), &Pt, setup.Y_comp.as_ref(), &Yl, &Ar, &ni]));
+        }
+    }
+
+    #[test]
+    fn token_keys() {
+        let mut setup = network(2);
+        let time = SystemTime::now();
+        let session = SessionId::new(1, time);
+
+        let k = derive_token_key(b"client seed", &session);
+        assert!(k == derive_token_key(b"client seed", &SessionId::new(1, time)));
+        assert!(k != derive_token_key(b"client seed", &SessionId::new(2, time)));
+        assert!(k != derive_token_key(b"other seed", &session));
+
+        let client = TestClient { k, time, ..TestClient::rnd() };
+        let token = issue_with(&mut setup, PROFILE, 1, &client, b"", false).unwrap();
+        assert!(token.verify(setup.public_params()));
+
+        // the regenerated key gives the same token
+        let claims = token.claims.clone();
+        let res = Token::with_claims(derive_token_key(b"client seed", &session), token.Tk, token.M, token.PI, claims, &setup.params.setup_id);
+        assert!(res.to_bytes() == token.to_bytes());
+    }
+
+    #[test]
+    fn profile_overwrite() {
+        let mut setup = network(2);
+        let r = rnd_scalar();
+        assert!(setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r) == Err(ProtocolError::ProfileExists));
+        assert!(setup.profile_with_threshold(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r, 1) == Err(ProtocolError::ProfileExists));
+
+        // sessions of the replaced profile are invalidated
+        let TestClient { st, time, .. } = TestClient::rnd();
+        let seq_bytes = 1usize.to_le_bytes();
+        let time_str = encode_time(time);
+        let sig = ExtSignature::sign(&st, &setup.G1.into(), &[PROFILE.as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()]);
+        setup.start(sig, PROFILE, 1, time).unwrap();
+
+        assert!(setup.profile_overwrite(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).is_ok());
+        let kc = rnd_scalar();
+        let res = setup.request(SessionId::new(1, time).as_str(), &(setup.A1 * kc).into(), &(setup.G1 * kc).into());
+        assert!(res.err() == Some(ProtocolError::UnknownSession));
+
+        assert!(issue(&mut setup, PROFILE, 2).unwrap().verify(setup.public_params()));
+    }
+
+    #[test]
+    fn public_params() {
+        let mut setup = network(2);
+        let token = issue(&mut setup, PROFILE, 1).unwrap();
+
+        // the verifier only holds public data
+        let params = setup.public_params().clone();
+        drop(setup);
+        assert!(token.verify(&params) && token.verify_with(&params.engine::<Bls12Engine>().unwrap()));
+        assert!(params.commitments.0.len() == 3);
+
+        let keys = PublicParams::from_keys(params.Y.into(), params.A2A);
+        assert!(token.verify(&keys));
+
+        let other = network(2);
+        assert!(!token.verify(other.public_params()));
+    }
+
+    #[test]
+    fn client_state_machine() {
+        let mut setup = network(2);
+        let mut client = Client::new(rnd_scalar(), setup.public_params().clone());
+
+        for seq in 1..3 {
+            let time = SystemTime::now();
+            let (sig, session) = client.start_request(PROFILE, seq, time);
+            let (Mi, PIi) = setup.start(sig, PROFILE, seq, time).unwrap();
+            let msg = client.complete_round1(&Mi, &PIi).unwrap();
+            assert!(msg.session == session);
+
+            let Tki = setup.request(msg.session.as_str(), &msg.Akc, &msg.Kc).unwrap();
+            assert!(client.finalize(&Tki).unwrap().verify(setup.public_params()));
+        }
+    }
+
+    #[test]
+    fn client_out_of_order() {
+        let mut setup = network(2);
+        let mut client = Client::new(rnd_scalar(), setup.public_params().clone());
+        let empty = PointShareVector(Vec::new());
+        assert!(client.complete_round1(&empty, &empty).err() == Some(ProtocolError::OutOfOrder));
+
+        let time = SystemTime::now();
+        let (sig, _) = client.start_request(PROFILE, 1, time);
+        assert!(client.finalize(&empty).err() == Some(ProtocolError::OutOfOrder));
+
+        // the pending request is kept
+        let (Mi, PIi) = setup.start(sig, PROFILE, 1, time).unwrap();
+        let msg = client.complete_round1(&Mi, &PIi).unwrap();
+        let Tki = setup.request(msg.session.as_str(), &msg.Akc, &msg.Kc).unwrap();
+        assert!(client.finalize(&Tki).unwrap().verify(setup.public_params()));
+        assert!(client.finalize(&Tki).err() == Some(ProtocolError::OutOfOrder));
+    }
+
+    #[test]
+    fn timed_phases() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct MockTimer(Vec<(bool, Phase)>);
+
+        impl Timer for MockTimer {
+            fn phase_start(&mut self, phase: Phase) {
+                self.0.push((true, phase));
+            }
+
+            fn phase_end(&mut self, phase: Phase) {
+                self.0.push((false, phase));
+            }
+        }
+
+        let timer = Arc::new(Mutex::new(MockTimer::default()));
+        let mut setup = network(2);
+        let mut client = Client::new(rnd_scalar(), setup.public_params().clone());
+        setup.set_timer(Box::new(timer.clone()));
+        client.set_timer(Box::new(timer.clone()));
+
+        let time = SystemTime::now();
+        let (sig, session) = client.start_request(PROFILE, 1, time);
+        let (Mi, PIi) = setup.start(sig, PROFILE, 1, time).unwrap();
+        let msg = client.complete_round1(&Mi, &PIi).unwrap();
+        let Tki = setup.request(msg.session.as_str(), &msg.Akc, &msg.Kc).unwrap();
+        let token = client.finalize(&Tki).unwrap();
+        assert!(client.verify(&token));
+
+        // a failed phase also ends
+        assert!(setup.request(session.as_str(), &msg.Akc, &msg.Kc).is_err());
+
+        let expected = [Phase::ClientInit, Phase::NetworkStart, Phase::ClientStart, Phase::NetworkRequest, Phase::ClientRequest, Phase::ClientVerify, Phase::NetworkRequest];
+        let expected = expected.iter().flat_map(|p| vec![(true, *p), (false, *p)]).collect::<Vec<_>>();
+        assert!(timer.lock().unwrap().0 == expected);
+    }
+
+    #[test]
+    fn send_setup() {
+        fn assert_send<T: Send>(_: &T) {}
+
+        let setup = network(2);
+        assert_send(&setup);
+        assert_send(&setup.clone());
+        assert_send(&NetworkSetup::verifier(setup.public_params().Y.into(), setup.public_params().A2A));
+        assert_send(&Client::new(rnd_scalar(), setup.public_params().clone()));
+    }
+
+    #[test]
+    fn node_timing() {
+        use std::sync::{Arc, Mutex};
+
+        let timer = Arc::new(Mutex::new(PhaseTimer::default()));
+        let mut setup = network(2);
+        let mut client = Client::new(rnd_scalar(), setup.public_params().clone());
+        setup.set_timer(Box::new(timer.clone()));
+        client.set_timer(Box::new(timer.clone()));
+
+        let time = SystemTime::now();
+        let (sig, _) = client.start_request(PROFILE, 1, time);
+        let (Mi, PIi) = setup.start(sig, PROFILE, 1, time).unwrap();
+        let msg = client.complete_round1(&Mi, &PIi).unwrap();
+        let Tki = setup.request(msg.session.as_str(), &msg.Akc, &msg.Kc).unwrap();
+        let token = client.finalize(&Tki).unwrap();
+        assert!(client.verify(&token));
+
+        // the sum of the phase times is the measured total, the nodes of a network phase run in parallel
+        let timer = timer.lock().unwrap();
+        let phases = [Phase::ClientInit, Phase::NetworkStart, Phase::ClientStart, Phase::NetworkRequest, Phase::ClientRequest, Phase::ClientVerify];
+        assert!(timer.elapsed() == phases.iter().map(|p| timer.total(*p)).sum());
+        for phase in phases.iter() {
+            match phase.is_network() {
+                true => assert!(timer.latency(*phase) < timer.total(*phase)),
+                false => assert!(timer.latency(*phase) == timer.total(*phase))
+            }
+        }
+    }
+
+    #[test]
+    fn retried_requests() {
+        let mut setup = network(2);
+        let client = TestClient::rnd();
+        let (Akc, Kc) = ((setup.A1 * client.k).into(), (setup.G1 * client.k).into());
+
+        // removed on use, a retry is reported as consumed
+        start_at(&mut setup, &client.st, 1, client.time).unwrap();
+        let session = SessionId::new(1, client.time);
+        assert!(setup.request(session.as_str(), &Akc, &Kc).is_ok());
+        assert!(setup.request(session.as_str(), &Akc, &Kc).err() == Some(ProtocolError::SessionAlreadyConsumed));
+        assert!(setup.request("none", &Akc, &Kc).err() == Some(ProtocolError::UnknownSession));
+
+        // kept until expiry, a retry with the same inputs gets the same shares
+        setup.set_session_retention(SessionRetention::KeepUntilExpiry);
+        start_at(&mut setup, &client.st, 2, client.time).unwrap();
+        let session = SessionId::new(2, client.time);
+        let first = setup.request(session.as_str(), &Akc, &Kc).unwrap();
+        let retry = setup.request(session.as_str(), &Akc, &Kc).unwrap();
+        assert!(first.interpolate() == retry.interpolate());
+        assert!(setup.audit.len() == 2);
+
+        let other = rnd_scalar();
+        let res = setup.request(session.as_str(), &(setup.A1 * other).into(), &(setup.G1 * other).into());
+        assert!(res.err() == Some(ProtocolError::SessionAlreadyConsumed));
+        assert!(setup.request_bound(session.as_str(), &Akc, &Kc).err() == Some(ProtocolError::SessionAlreadyConsumed));
+
+        assert!(setup.expire_sessions(Duration::from_secs(0)) == 1);
+        assert!(setup.request(session.as_str(), &Akc, &Kc).err() == Some(ProtocolError::UnknownSession));
+    }
+
+    #[test]
+    fn removed_profile() {
+        let mut setup = network(2);
+        let client = TestClient::rnd();
+        let (Akc, Kc) = ((setup.A1 * client.k).into(), (setup.G1 * client.k).into());
+
+        let start = |setup: &mut NetworkSetup, seq: usize| {
+            start_at(setup, &client.st, seq, client.time).unwrap();
+            SessionId::new(seq, client.time)
+        };
+
+        // the session is consumed and fails, the profile is gone
+        let session = start(&mut setup, 1);
+        assert!(setup.remove_profile(PROFILE).is_ok());
+        assert!(setup.remove_profile(PROFILE) == Err(ProtocolError::UnknownProfile));
+        assert!(setup.request(session.as_str(), &Akc, &Kc).err() == Some(ProtocolError::UnknownProfile));
+        assert!(setup.request(session.as_str(), &Akc, &Kc).err() == Some(ProtocolError::UnknownSession));
+
+        // in-flight sessions may complete if allowed
+        let r = rnd_scalar();
+        setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+        let session = start(&mut setup, 2);
+        setup.remove_profile(PROFILE).unwrap();
+        setup.set_complete_removed(true);
+        assert!(setup.request(session.as_str(), &Akc, &Kc).is_ok());
+
+        // but never against a re-inserted profile
+        setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+        let session = start(&mut setup, 3);
+        setup.remove_profile(PROFILE).unwrap();
+        setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+        assert!(setup.request(session.as_str(), &Akc, &Kc).err() == Some(ProtocolError::UnknownSession));
+    }
+
+    #[test]
+    fn compressed_profile() {
+        let mut setup = network(2);
+        let r = rnd_scalar();
+        let R = G1Affine::from(setup.G1 * r).to_compressed();
+        let Ar = G1Affine::from(setup.A1 * r).to_compressed();
+
+        assert!(setup.profile_compressed("config", LOCATION, &R, &Ar).is_ok());
+        assert!(issue(&mut setup, "config", 1).unwrap().verify(setup.public_params()));
+
+        let mut corrupted = Ar;
+        corrupted[0] &= 0x7f;
+        assert!(setup.profile_compressed("bad", LOCATION, &R, &corrupted) == Err(ProtocolError::InvalidProfile));
+        assert!(setup.profile_compressed("bad", LOCATION, &R, &[0xff; 48]) == Err(ProtocolError::InvalidProfile));
+
+        // a valid point with the wrong pairing
+        let other = G1Affine::from(setup.A1 * rnd_scalar()).to_compressed();
+        assert!(setup.profile_compressed("bad", LOCATION, &R, &other) == Err(ProtocolError::InvalidPairing));
+        assert!(setup.profile_compressed("config", LOCATION, &R, &Ar) == Err(ProtocolError::ProfileExists));
+        assert!(setup.profile_compressed("other", "unknown", &R, &Ar) == Err(ProtocolError::UnknownLocation));
+    }
+
+    #[test]
+    fn batch_issuance() {
+        let mut setup = network(2);
+        let r = rnd_scalar();
+        setup.profile("other", LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+
+        let client = TestClient::rnd();
+        let profiles = [PROFILE, "other"];
+        let (seq_bytes, time_str) = (1usize.to_le_bytes(), encode_time(client.time));
+        let data: &[&[u8]] = &[&encode_profiles(&profiles), &seq_bytes, time_str.as_bytes()];
+        let sig = ExtSignature::sign(&client.st, &setup.G1.into(), data);
+
+        let started = setup.start_batch(sig.clone(), &profiles, 1, client.time).unwrap();
+        assert!(started.len() == 2 && setup.session_count() == 2);
+        assert!(setup.start_batch(sig, &profiles, 1, client.time).err() == Some(ProtocolError::InvalidFreshness));
+
+        let rounds = profiles.iter().map(|p| (*p, client_round1(&started[*p].0, &started[*p].1, &client.k, setup.public_params()))).collect::<Vec<_>>();
+        assert!(rounds[0].1.M != rounds[1].1.M);
+
+        let requests = rounds.iter().map(|(p, out)| (*p, out.Akc, out.Kc)).collect::<Vec<_>>();
+        let Tki = setup.request_batch(&SessionId::new(1, client.time), &requests).unwrap();
+        for (p, out) in rounds.iter() {
+            let token = client_round2(&Tki[*p], client.k, out.M, out.PI, setup.public_params());
+            assert!(token.verify(setup.public_params()));
+        }
+
+        // all or nothing, an unknown profile starts no session
+        let profiles = [PROFILE, "unknown"];
+        let data: &[&[u8]] = &[&encode_profiles(&profiles), &2usize.to_le_bytes(), time_str.as_bytes()];
+        let sig = ExtSignature::sign(&client.st, &setup.G1.into(), data);
+        assert!(setup.start_batch(sig, &profiles, 2, client.time).err() == Some(ProtocolError::UnknownProfile));
+        assert!(setup.session_count() == 0);
+    }
+
+    #[test]
+    fn session_metadata() {
+        let mut setup = network(2);
+        let client = TestClient::rnd();
+        let time_str = encode_time(client.time);
+        let sign = |seq: usize| ExtSignature::sign(&client.st, &setup.G1.into(), &[PROFILE.as_bytes(), &seq.to_le_bytes(), time_str.as_bytes()]);
+
+        let (sig1, sig2) = (sign(1), sign(2));
+        let (Mi, PIi) = setup.start_with_metadata(sig1, PROFILE, 1, client.time, b"request-42".to_vec()).unwrap();
+        let out = client_round1(&Mi, &PIi, &client.k, setup.public_params());
+        let (Mi2, _) = setup.start(sig2, PROFILE, 2, client.time).unwrap();
+        let session = SessionId::new(1, client.time);
+        let (Tki, metadata) = setup.request_with_metadata(session.as_str(), &out.Akc, &out.Kc).unwrap();
+        assert!(metadata == b"request-42".to_vec());
+        assert!(client_round2(&Tki, client.k, out.M, out.PI, setup.public_params()).verify(setup.public_params()));
+
+        // sessions started without metadata return an empty one
+        let out2 = client_round1(&Mi2, &PIi, &client.k, setup.public_params());
+        let (_, metadata) = setup.request_with_metadata(SessionId::new(2, client.time).as_str(), &out2.Akc, &out2.Kc).unwrap();
+        assert!(metadata.is_empty());
+    }
+
+    #[test]
+    fn verifiable_start() {
+        let mut setup = network(2);
+        let client = TestClient::rnd();
+        let time_str = encode_time(client.time);
+        let sig = ExtSignature::sign(&client.st, &setup.G1.into(), &[PROFILE.as_bytes(), &1usize.to_le_bytes(), time_str.as_bytes()]);
+
+        let ((mut Mi, PIi), commitments) = setup.start_verifiable(sig, PROFILE, 1, client.time).unwrap();
+        assert!(commitments.verify(&Mi, &PIi).is_ok());
+
+        // a corrupted Mi share is rejected before interpolation
+        Mi.0[1].Yi += setup.G1;
+        assert!(commitments.verify(&Mi, &PIi) == Err(vec![2]));
+    }
+
+    #[test]
+    fn session_metrics() {
+        let mut setup = network(2);
+        let client = TestClient::rnd();
+        assert!(setup.metrics_snapshot() == SetupMetrics { sessions: 0, profiles: 1, locations: 1 });
+        assert!(setup.oldest_session_age().is_none());
+
+        for seq in 1..4 {
+            start_at(&mut setup, &client.st, seq, client.time).unwrap();
+        }
+        assert!(setup.session_count() == 3 && setup.metrics_snapshot().sessions == 3);
+
+        std::thread::sleep(Duration::from_millis(300));
+        start_at(&mut setup, &client.st, 4, client.time).unwrap();
+        assert!(setup.oldest_session_age().unwrap() >= Duration::from_millis(300));
+
+        // only the older sessions are evicted
+        assert!(setup.expire_sessions(Duration::from_millis(150)) == 3);
+        assert!(setup.session_count() == 1);
+        assert!(setup.expire_sessions(Duration::from_secs(0)) == 1);
+        assert!(setup.session_count() == 0 && setup.oldest_session_age().is_none());
+    }
+
+    #[test]
+    fn location_quorum() {
+        let mut setup = network(2);
+        for loc in ["North", "South", "East"].iter() {
+            setup.location(loc, setup.Y * rnd_scalar());
+        }
+
+        let r = rnd_scalar();
+        setup.profile_with_locations("multi", &["North", "South", "East"], 2, setup.G1 * r, setup.A1 * r).unwrap();
+        assert!(issue(&mut setup, "multi", 1).unwrap().verify(setup.public_params()));
+
+        let locs = ["North", "South"];
+        assert!(setup.profile_with_locations("none", &locs, 0, setup.G1 * r, setup.A1 * r) == Err(ProtocolError::InvalidProfile));
+        assert!(setup.profile_with_locations("all", &locs, 3, setup.G1 * r, setup.A1 * r) == Err(ProtocolError::InvalidProfile));
+
+        // a missing location is tolerated down to the quorum
+        setup.remove_location("East").unwrap();
+        assert!(issue(&mut setup, "multi", 2).unwrap().verify(setup.public_params()));
+
+        setup.remove_location("South").unwrap();
+        assert!(issue(&mut setup, "multi", 3).err() == Some(ProtocolError::UnknownLocation));
+        assert!(setup.remove_location("South") == Err(ProtocolError::UnknownLocation));
+
+        // single location profiles are unaffected
+        assert!(issue(&mut setup, PROFILE, 4).is_ok());
+    }
+
+    #[test]
+    fn client_rounds() {
+        let mut setup = network(2);
+        let client = TestClient::rnd();
+
+        let seq = 1usize;
+        let (Mi, PIi) = start_at(&mut setup, &client.st, seq, client.time).unwrap();
+
+        let out = client_round1(&Mi, &PIi, &client.k, setup.public_params());
+        let Mk = G1Affine::from(out.M * client.k);
+        assert!(out.c == token_challenge(&setup.params.setup_id, &out.M, &Mk, &out.PI));
+
+        let Tki = setup.request(SessionId::new(seq, client.time).as_str(), &out.Akc, &out.Kc).unwrap();
+        let token = client_round2(&Tki, client.k, out.M, out.PI, setup.public_params());
+        assert!(token.verify(setup.public_params()));
+        assert!(token.M == out.M && token.PI == out.PI && token.Tk == G1Affine::from(Tki.interpolate()));
+
+        // tampered shares don't give a valid token
+        let token = client_round2(&(&Tki + &Tki), client.k, out.M, out.PI, setup.public_params());
+        assert!(!token.verify(setup.public_params()));
+    }
+
+    #[test]
+    fn freshness_windows() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let (secs, nanos) = (Duration::from_secs, Duration::from_nanos);
+        let st = rnd_scalar();
+
+        let mut setup = network(2);
+        setup.set_clock(Box::new(FixedClock(now)));
+        setup.set_freshness(secs(20), secs(5));
+
+        // the window bounds are inclusive
+        assert!(start_at(&mut setup, &st, 1, now - secs(20)).is_ok());
+        assert!(start_at(&mut setup, &st, 2, now - secs(20) - nanos(1)).err() == Some(ProtocolError::InvalidFreshness));
+        assert!(start_at(&mut setup, &st, 3, now + secs(5)).is_ok());
+        assert!(start_at(&mut setup, &st, 4, now + secs(5) + nanos(1)).err() == Some(ProtocolError::InvalidFreshness));
+
+        // the default is symmetric
+        let mut setup = network(2);
+        setup.set_clock(Box::new(FixedClock(now)));
+        assert!(start_at(&mut setup, &st, 1, now - FRESHNESS_WINDOW).is_ok());
+        assert!(start_at(&mut setup, &st, 2, now + FRESHNESS_WINDOW).is_ok());
+        assert!(start_at(&mut setup, &st, 3, now + FRESHNESS_WINDOW + nanos(1)).err() == Some(ProtocolError::InvalidFreshness));
+
+        // windows past the time range are unbounded on that side
+        setup.set_freshness(Duration::MAX, Duration::MAX);
+        assert!(start_at(&mut setup, &st, 4, SystemTime::UNIX_EPOCH).is_ok());
+        assert!(start_at(&mut setup, &st, 5, now + secs(1 << 40)).is_ok());
+    }
+
+    #[test]
+    fn token_key_commitment() {
+        let setup = network(2);
+        let (k, c) = (rnd_scalar(), rnd_scalar());
+
+        let commit = commit_token_key(&k, &c, setup.public_params());
+        assert!(commit.Kc == G1Affine::from(setup.G1 * (k * c)) && commit.Akc == G1Affine::from(setup.A1 * (k * c)));
+        assert!(commit.Kc_comp == commit.Kc.to_compressed() && commit.Akc_comp == commit.Akc.to_compressed());
+    }
+
+    #[test]
+    fn identity_points() {
+        let mut setup = network(2);
+        let token = issue(&mut setup, PROFILE, 1).unwrap();
+        assert!(token.check_points().is_ok());
+
+        let O = G1Affine::identity();
+        let mut forged = Token { claims: token.claims.clone(), sig: token.sig.clone(), ..token };
+        forged.sig.P1 = O;
+        assert!(forged.check_points() == Err(CryptoError::IdentityPoint));
+        assert!(!forged.verify(setup.public_params()) && !forged.verify_with(&setup.public_params().engine::<Bls12Engine>().unwrap()));
+
+        forged.sig.P1 = token.sig.P1;
+        forged.M = O;
+        assert!(!forged.verify(setup.public_params()));
+
+        // kc = 0 in the request round
+        let time = SystemTime::now();
+        start_at(&mut setup, &rnd_scalar(), 2, time).unwrap();
+        assert!(setup.request(SessionId::new(2, time).as_str(), &O, &O).err() == Some(ProtocolError::InvalidPairing));
+    }
+
+    #[test]
+    fn cloned_setups() {
+        let mut setup = network(2);
+        let mut copy = setup.clone();
+
+        // copies issue independently, each with its own sessions
+        assert!(issue(&mut setup, PROFILE, 1).unwrap().verify(setup.public_params()));
+        assert!(issue(&mut copy, PROFILE, 1).unwrap().verify(copy.public_params()));
+        assert!(setup.audit().len() == 1 && copy.audit().len() == 1);
+
+        copy.profile("other", LOCATION, copy.G1, copy.A1).unwrap();
+        assert!(issue(&mut setup, "other", 2).err() == Some(ProtocolError::UnknownProfile));
+        assert!(issue(&mut copy, "other", 2).is_ok());
+
+        // rebuilt from the public params and shares of the original
+        let mut rebuilt = NetworkSetup::from_shares(setup.public_params().clone(), 2, setup.yi.clone(), setup.ai.clone()).unwrap();
+        rebuilt.location(LOCATION, rebuilt.Y);
+        rebuilt.profile(PROFILE, LOCATION, rebuilt.G1, rebuilt.A1).unwrap();
+        assert!(issue(&mut rebuilt, PROFILE, 1).unwrap().verify(setup.public_params()));
+
+        let wrong = ShareVector(setup.ai.0.iter().map(|s| Share { i: s.i, yi: s.yi + Scalar::one() }).collect());
+        assert!(NetworkSetup::from_shares(setup.public_params().clone(), 2, setup.yi.clone(), wrong).err() == Some(ProtocolError::InvalidShares));
+        let other = network(2);
+        assert!(NetworkSetup::from_shares(other.public_params().clone(), 2, setup.yi.clone(), setup.ai.clone()).err() == Some(ProtocolError::InvalidShares));
+    }
+
+    #[test]
+    fn setup_binding() {
+        let mut a = network(2);
+        let b = network(2);
+        let token = issue(&mut a, PROFILE, 1).unwrap();
+        assert!(token.verify(a.public_params()) && !token.verify(b.public_params()));
+
+        // same authority key A2, the pairing equation alone would accept it
+        let shared = PublicParams::from_keys(b.Y.into(), a.A2A);
+        assert!(shared.setup_id != a.public_params().setup_id);
+        assert!(!token.verify(&shared));
+        assert!(token.verify(&PublicParams::from_keys(a.Y.into(), a.A2A)));
+    }
+
+    #[test]
+    fn combined_setups() {
+        let a = network(2);
+        let b = network(2);
+        let params = a.public_params().combine(b.public_params());
+
+        let mut combined = a.combine(&b).unwrap();
+        assert!(combined.public_params().Y == params.Y && combined.public_params().A2A == params.A2A);
+        combined.location(LOCATION, combined.Y);
+        combined.profile(PROFILE, LOCATION, combined.G1, combined.A1).unwrap();
+
+        let token = issue(&mut combined, PROFILE, 1).unwrap();
+        assert!(token.verify(&params));
+        assert!(!token.verify(a.public_params()) && !token.verify(b.public_params()));
+
+        assert!(a.combine(&network(3)).err() == Some(ProtocolError::InvalidShares));
+    }
+
+    #[test]
+    fn verified_bytes() {
+        let mut setup = network(2);
+        let bytes = issue(&mut setup, PROFILE, 1).unwrap().to_bytes();
+        assert!(Token::verify_bytes(&bytes, setup.public_params()) == Ok(()));
+
+        let uncompressed = Token::from_bytes(&bytes).unwrap().to_bytes_with(PointEncoding::Uncompressed);
+        assert!(Token::verify_bytes(&uncompressed, setup.public_params()) == Ok(()));
+
+        let res = Token::verify_bytes(&bytes[..bytes.len() - 1], setup.public_params());
+        assert!(res == Err(TokenVerifyError::InvalidEncoding(CryptoError::InvalidLength)));
+
+        // Tk (after the version and encoding tags) replaced with a point not on the curve
+        let mut corrupted = bytes.clone();
+        corrupted[2..50].copy_from_slice(&[0xff; 48]);
+        assert!(Token::verify_bytes(&corrupted, setup.public_params()) == Err(TokenVerifyError::InvalidEncoding(CryptoError::InvalidPoint)));
+
+        // a valid encoding of another token
+        let mut forged = Token::from_bytes(&bytes).unwrap();
+        forged.claims.nonce = [1u8; 32];
+        assert!(Token::verify_bytes(&forged.to_bytes(), setup.public_params()) == Err(TokenVerifyError::InvalidToken));
+    }
+
+    #[test]
+    fn dual_control() {
+        let mut issuer = network(2);
+        let (second, third) = (NetworkSetup::new(2), NetworkSetup::new(1));
+        let (params, second_params, third_params) = (issuer.public_params().clone(), second.public_params(), third.public_params());
+
+        let client = TestClient::rnd();
+        let token = issue_with(&mut issuer, PROFILE, 1, &client, b"", false).unwrap();
+        let contributions = [second.cosign(&token, &params).unwrap(), third.cosign(&token, &params).unwrap()];
+
+        let joint = token.cosigned(client.k, &contributions, &params);
+        assert!(joint.verify_joint(&params, &[second_params, third_params]));
+        assert!(!joint.verify(&params));
+
+        // a missing contribution
+        let partial = token.cosigned(client.k, &contributions[..1], &params);
+        assert!(partial.verify_joint(&params, &[second_params]));
+        assert!(!partial.verify_joint(&params, &[second_params, third_params]));
+        assert!(!token.verify_joint(&params, &[second_params]));
+
+        // only valid tokens of the issuer are co-signed
+        assert!(second.cosign(&joint, &params).err() == Some(ProtocolError::InvalidSignature));
+    }
+
+    #[test]
+    fn rotation_window() {
+        let mut setup = network(2);
+        let old_token = issue(&mut setup, PROFILE, 1).unwrap();
+        let old = setup.public_params().clone();
+
+        setup.rotate_authority().unwrap();
+        let new = setup.public_params().clone();
+        let new_token = issue(&mut setup, PROFILE, 2).unwrap();
+
+        assert!(old_token.verify_any(&[&old, &new]) == Ok(0));
+        assert!(new_token.verify_any(&[&old, &new]) == Ok(1));
+        assert!(new_token.verify_any(&[&old]) == Err(TokenVerifyError::NoMatch));
+        assert!(new_token.verify_any(&[]) == Err(TokenVerifyError::NoSetups));
+
+        let mut forged = Token::from_bytes(&new_token.to_bytes()).unwrap();
+        forged.PI = G1Affine::identity();
+        assert!(forged.verify_any(&[&new]) == Err(TokenVerifyError::InvalidPoints(CryptoError::IdentityPoint)));
+    }
+
+    #[test]
+    fn extra_pairing_terms() {
+        let setup = network(2);
+        let params = setup.public_params();
+        let a = setup.ai.interpolate();
+
+        let k = rnd_scalar();
+        let M = G1Affine::from(setup.G1 * rnd_scalar());
+        let PI = G1Affine::from(setup.G1 * rnd_scalar());
+        let c = token_challenge(&params.setup_id, &M, &G1Affine::from(M * k), &PI);
+
+        // Tk with an extra term under the base B2 = b*G2, a*(PI + c*Mk) + b*X
+        let b = rnd_scalar();
+        let X = G1Affine::from(setup.G1 * rnd_scalar());
+        let B2P = G2Prepared::from(G2Affine::from(setup.G2A * b));
+        let Tk = G1Affine::from((PI + M * (k * c)) * a + X * b);
+
+        let token = Token::with_claims(k, Tk, M, PI, Claims::default(), &params.setup_id);
+        assert!(token.verify_with_terms(params, &[(X, &B2P)]));
+        assert!(!token.verify(params));
+        assert!(!token.verify_with_terms(params, &[(G1Affine::from(setup.G1 * rnd_scalar()), &B2P)]));
+        assert!(!token.verify_with_terms(params, &[(X, &params.A2P)]));
+
+        // the default two-term case
+        let plain = Token::with_claims(k, G1Affine::from((PI + M * (k * c)) * a), M, PI, Claims::default(), &params.setup_id);
+        assert!(plain.verify(params) && plain.verify_with_terms(params, &[]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Threshold must be at least 1!")]
+    fn zero_threshold() {
+        NetworkSetup::new(0);
+    }
+
+    #[test]
+    fn minimum_threshold() {
+        let mut setup = network(1);
+        assert!(setup.parties == 2);
+
+        let token = issue(&mut setup, PROFILE, 1).unwrap();
+        assert!(token.verify(setup.public_params()));
+
+        let params = setup.public_params().clone();
+        assert!(NetworkSetup::from_shares(params, 0, setup.yi.clone(), setup.ai.clone()).err() == Some(ProtocolError::InvalidShares));
+    }
+
+    #[test]
+    fn alternate_base() {
+        let setup = network(2);
+        let params = setup.public_params();
+        let a = setup.ai.interpolate();
+
+        let k = rnd_scalar();
+        let M = G1Affine::from(setup.G1 * rnd_scalar());
+        let PI = G1Affine::from(setup.G1 * rnd_scalar());
+        let H = hash_to_g1(&[b"token-base"]);
+
+        // Tk issued for the alternate base, a*(PI + c*k*H)
+        let Hk = G1Affine::from(H * k);
+        let c = token_challenge(&params.setup_id, &M, &Hk, &PI);
+        let Tk = G1Affine::from((PI + Hk * c) * a);
+
+        let token = Token::with_base(k, Tk, M, PI, Claims::default(), &H, &params.setup_id);
+        assert!(token.sig.P1 == Hk);
+        assert!(token.verify_with_base(params, &H));
+        assert!(!token.verify(params));
+        assert!(!token.verify_with_base(params, &setup.G1.into()));
+        assert!(!token.verify_with_base(params, &G1Affine::identity()));
+    }
+
+    #[test]
+    fn authority_rotation() {
+        let mut setup = network(2);
+        setup.profile_with_threshold("low", LOCATION, setup.G1, setup.A1, 1).unwrap();
+        let old = issue(&mut setup, PROFILE, 1).unwrap();
+        let A2 = setup.A2A;
+
+        assert!(setup.rotate_authority().is_ok());
+        assert!(setup.A2A != A2);
+        assert!(setup.validate().is_ok());
+        assert!(!old.verify(setup.public_params()));
+
+        let token = issue(&mut setup, PROFILE, 2).unwrap();
+        assert!(token.verify(setup.public_params()));
+        assert!(issue(&mut setup, "low", 3).unwrap().verify(setup.public_params()));
+    }
+}
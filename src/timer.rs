@@ -0,0 +1,201 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, Duration};
+
+//-----------------------------------------------------------------------------------------------------------
+// Timing hooks of the protocol phases
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    ClientInit,
+    NetworkStart,
+    ClientStart,
+    NetworkRequest,
+    ClientRequest,
+    ClientVerify
+}
+
+impl Phase {
+    // NOTE: network phases run the work of all nodes in the simulation
+    pub fn is_network(&self) -> bool {
+        matches!(self, Phase::NetworkStart | Phase::NetworkRequest)
+    }
+}
+
+// NOTE: called by NetworkSetup and Client around each phase, also when the phase fails. Inside a network phase, the
+// work of each node is also reported with its index, the remaining work is common to all nodes.
+pub trait Timer {
+    fn phase_start(&mut self, phase: Phase);
+    fn phase_end(&mut self, phase: Phase);
+
+    fn node_start(&mut self, _node: u32) {}
+    fn node_end(&mut self, _node: u32) {}
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoTimer;
+
+impl Timer for NoTimer {
+    fn phase_start(&mut self, _phase: Phase) {}
+    fn phase_end(&mut self, _phase: Phase) {}
+}
+
+// NOTE: a shared timer, the same one can be installed in the network and in the client
+impl<T: Timer> Timer for Arc<Mutex<T>> {
+    fn phase_start(&mut self, phase: Phase) {
+        self.lock().unwrap().phase_start(phase);
+    }
+
+    fn phase_end(&mut self, phase: Phase) {
+        self.lock().unwrap().phase_end(phase);
+    }
+
+    fn node_start(&mut self, node: u32) {
+        self.lock().unwrap().node_start(node);
+    }
+
+    fn node_end(&mut self, node: u32) {
+        self.lock().unwrap().node_end(node);
+    }
+}
+
+pub fn timed<T>(timer: &mut dyn Timer, phase: Phase, work: impl FnOnce() -> T) -> T {
+    timer.phase_start(phase);
+    let res = work();
+    timer.phase_end(phase);
+    res
+}
+
+// NOTE: wall-clock time of each completed phase, in order. An end without a matching start is ignored.
+// The latency of a phase runs the nodes in parallel, the common work plus the slowest node.
+#[derive(Debug, Clone, Default)]
+pub struct PhaseTimer {
+    running: Option<(Phase, Instant)>,
+    node: Option<(u32, Instant)>,
+    nodes: Vec<(u32, Duration)>,
+    times: Vec<(Phase, Duration)>,
+    latencies: Vec<(Phase, Duration)>
+}
+
+impl PhaseTimer {
+    pub fn times(&self) -> &[(Phase, Duration)] {
+        &self.times
+    }
+
+    pub fn total(&self, phase: Phase) -> Duration {
+        self.times.iter().filter(|(p, _)| *p == phase).map(|(_, time)| *time).sum()
+    }
+
+    pub fn latency(&self, phase: Phase) -> Duration {
+        self.latencies.iter().filter(|(p, _)| *p == phase).map(|(_, time)| *time).sum()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.times.iter().map(|(_, time)| *time).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.running = None;
+        self.node = None;
+        self.nodes.clear();
+        self.times.clear();
+        self.latencies.clear();
+    }
+}
+
+impl Timer for PhaseTimer {
+    fn phase_start(&mut self, phase: Phase) {
+        self.node = None;
+        self.nodes.clear();
+        self.running = Some((phase, Instant::now()));
+    }
+
+    fn phase_end(&mut self, phase: Phase) {
+        if let Some((running, start)) = self.running.take() {
+            if running == phase {
+                let time = start.elapsed();
+                let nodes = self.nodes.iter().map(|(_, time)| *time).sum::<Duration>();
+                let slowest = self.nodes.iter().map(|(_, time)| *time).max().unwrap_or_default();
+                self.times.push((phase, time));
+                self.latencies.push((phase, time - nodes + slowest));
+            }
+        }
+
+        self.node = None;
+        self.nodes.clear();
+    }
+
+    // NOTE: a node may report several pieces of work in the same phase, they are added
+    fn node_start(&mut self, node: u32) {
+        if self.running.is_some() {
+            self.node = Some((node, Instant::now()));
+        }
+    }
+
+    fn node_end(&mut self, node: u32) {
+        if let Some((running, start)) = self.node.take() {
+            if running == node {
+                match self.nodes.iter_mut().find(|(n, _)| *n == node) {
+                    Some((_, time)) => *time += start.elapsed(),
+                    None => self.nodes.push((node, start.elapsed()))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phase_timer() {
+        let mut timer = PhaseTimer::default();
+        let work = |ms: u64| std::thread::sleep(Duration::from_millis(ms));
+
+        timed(&mut timer, Phase::ClientInit, || work(2));
+        let res = timed(&mut timer, Phase::NetworkStart, || { work(4); 42 });
+        assert!(res == 42);
+
+        // unmatched end
+        timer.phase_end(Phase::ClientStart);
+
+        assert!(timer.times().iter().map(|(p, _)| *p).collect::<Vec<_>>() == vec![Phase::ClientInit, Phase::NetworkStart]);
+        assert!(timer.total(Phase::NetworkStart) >= Duration::from_millis(4));
+        assert!(timer.elapsed() == timer.total(Phase::ClientInit) + timer.total(Phase::NetworkStart));
+        assert!(timer.total(Phase::ClientStart) == Duration::from_millis(0));
+
+        timer.clear();
+        assert!(timer.times().is_empty());
+    }
+
+    #[test]
+    fn node_latency() {
+        let mut timer = PhaseTimer::default();
+        let work = |ms: u64| std::thread::sleep(Duration::from_millis(ms));
+
+        timed(&mut timer, Phase::ClientInit, || work(1));
+
+        // common work, then nodes 1 and 2 in sequence (node 1 in two pieces)
+        timer.phase_start(Phase::NetworkStart);
+        work(1);
+        for (node, ms) in [(1, 2), (2, 4), (1, 1)].iter() {
+            timer.node_start(*node);
+            work(*ms);
+            timer.node_end(*node);
+        }
+        timer.phase_end(Phase::NetworkStart);
+
+        // outside of a phase
+        timer.node_start(1);
+        timer.node_end(1);
+
+        // the sum of the phase times is the measured total, the latency only counts the slowest node
+        let phases = [Phase::ClientInit, Phase::NetworkStart, Phase::ClientStart, Phase::NetworkRequest, Phase::ClientRequest, Phase::ClientVerify];
+        assert!(timer.elapsed() == phases.iter().map(|p| timer.total(*p)).sum());
+        assert!(timer.latency(Phase::ClientInit) == timer.total(Phase::ClientInit));
+
+        let (total, latency) = (timer.total(Phase::NetworkStart), timer.latency(Phase::NetworkStart));
+        assert!(total >= Duration::from_millis(8) && latency >= Duration::from_millis(5));
+        assert!(latency + Duration::from_millis(3) <= total);
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+//-----------------------------------------------------------------------------------------------------------
+// Transcript of a protocol run, records hashed inputs and derived values in order. Contains secrets, debug only!
+//-----------------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub label: String,
+    pub value: Vec<u8>
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript {
+    entries: Vec<Entry>
+}
+
+impl Transcript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn append(&mut self, label: &str, value: &[u8]) -> &mut Self {
+        self.entries.push(Entry { label: label.into(), value: value.to_vec() });
+        self
+    }
+
+    // NOTE: the last value recorded with the label
+    pub fn get(&self, label: &str) -> Option<&[u8]> {
+        self.entries.iter().rev().find(|e| e.label == label).map(|e| e.value.as_slice())
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn labels(&self) -> Vec<&str> {
+        self.entries.iter().map(|e| e.label.as_str()).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+// NOTE: one "label: hex" line per entry, suitable for diffing against other implementations
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for e in self.entries.iter() {
+            write!(f, "{}: ", e.label)?;
+            for b in e.value.iter() {
+                write!(f, "{:02x}", b)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let mut transcript = Transcript::new();
+        transcript.append("a", &[0x01, 0xab]).append("b", b"").append("a", &[0xff]);
+
+        assert!(transcript.labels() == vec!["a", "b", "a"]);
+        assert!(transcript.get("a") == Some([0xffu8].as_ref()));
+        assert!(transcript.get("c").is_none());
+        assert!(transcript.to_string() == "a: 01ab\nb: \na: ff\n");
+    }
+}
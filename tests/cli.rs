@@ -0,0 +1,62 @@
+use std::process::Command;
+
+fn tat_adr(args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tat-adr")).args(args).output().unwrap();
+    (output.status.success(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn issue_verify() {
+    let (ok, out) = tat_adr(&["issue", "--profile", "EHR", "-t", "2"]);
+    assert!(ok);
+
+    let lines = out.lines().collect::<Vec<_>>();
+    assert!(lines.len() == 2);
+    let (token, network) = (lines[0], lines[1]);
+
+    let (ok, out) = tat_adr(&["verify", token, "--network", network]);
+    assert!(ok && out.trim() == "valid");
+
+    // token from a different network
+    let (_, other) = tat_adr(&["issue"]);
+    let other_network = other.lines().nth(1).unwrap();
+    let (ok, _) = tat_adr(&["verify", token, "--network", other_network]);
+    assert!(!ok);
+
+    let (ok, _) = tat_adr(&["verify", "00ff", "--network", network]);
+    assert!(!ok);
+}
+
+#[test]
+fn seeded_issue() {
+    let (ok, first) = tat_adr(&["issue", "--seed", "42"]);
+    assert!(ok);
+    let (_, second) = tat_adr(&["issue", "--seed", "42"]);
+    let (_, other) = tat_adr(&["issue", "--seed", "43"]);
+
+    // NOTE: the seed also fixes the start time and the network clock, the token bytes are reproducible
+    let (first, second, other) = (first.lines().collect::<Vec<_>>(), second.lines().collect::<Vec<_>>(), other.lines().collect::<Vec<_>>());
+    assert!(first.len() == 2 && first == second);
+    assert!(first[0] != other[0] && first[1] != other[1]);
+
+    let (ok, out) = tat_adr(&["verify", first[0], "--network", first[1]]);
+    assert!(ok && out.trim() == "valid");
+
+    let (ok, _) = tat_adr(&["issue", "--seed", "x42"]);
+    assert!(!ok);
+}
+
+#[test]
+fn sweep_csv() {
+    let path = std::env::temp_dir().join(format!("tat-adr-sweep-{}.csv", std::process::id()));
+    let (ok, _) = tat_adr(&["simulate", "--sweep", "1..2", "-r", "1", "--out", path.to_str().unwrap()]);
+    assert!(ok);
+
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert!(lines.len() == 3 && lines[0].starts_with("threshold,"));
+    assert!(lines[1].starts_with("1,") && lines[2].starts_with("2,"));
+    assert!(lines.iter().all(|line| line.split(',').count() == 8));
+
+    std::fs::remove_file(&path).unwrap();
+}
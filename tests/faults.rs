@@ -0,0 +1,184 @@
+#![allow(non_snake_case)]
+
+// Fault-injection testbed for the threshold network. A FaultyNetwork wraps a NetworkSetup and applies a scenario to
+// the outputs of each node: dropped, delayed (dropped if later than the client timeout) or corrupted responses.
+// Corruption is applied to the request round only, the start shares have no published commitment to check against.
+
+use tat_adr::*;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::time::{Duration, SystemTime};
+
+const PROFILE: &str = "EHR";
+const LOCATION: &str = "Hospital";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    Drop,
+    Delay(Duration),
+    Corrupt
+}
+
+// NOTE: a scenario is a list of "<fault>:<node>" separated by spaces, delays are "delay:<node>:<ms>".
+// e.g. "drop:2 corrupt:4 delay:5:200"
+#[derive(Debug, Clone)]
+struct Scenario {
+    faults: Vec<(u32, Fault)>,
+    timeout: Duration
+}
+
+impl Scenario {
+    fn parse(desc: &str, timeout: Duration) -> Self {
+        let faults = desc.split_whitespace().map(|item| {
+            let parts = item.split(':').collect::<Vec<_>>();
+            let node = parts[1].parse::<u32>().expect("Invalid node index!");
+            let fault = match (parts[0], parts.get(2)) {
+                ("drop", None) => Fault::Drop,
+                ("corrupt", None) => Fault::Corrupt,
+                ("delay", Some(ms)) => Fault::Delay(Duration::from_millis(ms.parse().expect("Invalid delay!"))),
+                _ => panic!("Invalid fault: {}", item)
+            };
+
+            (node, fault)
+        }).collect();
+
+        Self { faults, timeout }
+    }
+
+    fn fault(&self, node: u32) -> Option<Fault> {
+        self.faults.iter().find(|(i, _)| *i == node).map(|(_, f)| *f)
+    }
+
+    // NOTE: responses later than the timeout are never seen by the client
+    fn delivered(&self, node: u32) -> bool {
+        match self.fault(node) {
+            Some(Fault::Drop) => false,
+            Some(Fault::Delay(d)) => d <= self.timeout,
+            _ => true
+        }
+    }
+}
+
+enum Outcome {
+    Completed { token: Box<Token>, faulty: Vec<u32> },
+    TimedOut,
+    Failed(ProtocolError)
+}
+
+struct FaultyNetwork {
+    setup: NetworkSetup,
+    scenario: Scenario,
+    f: usize
+}
+
+impl FaultyNetwork {
+    // NOTE: a committee of "threshold + 2f + 1" nodes, tolerates f corrupted responses
+    fn new(threshold: usize, f: usize, scenario: Scenario) -> Self {
+        let mut setup = NetworkSetup::with_committee(threshold, threshold + 2 * f + 1, ChaCha20Rng::from_entropy());
+
+        let r = rnd_scalar();
+        setup.location(LOCATION, setup.Y * rnd_scalar());
+        setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+
+        Self { setup, scenario, f }
+    }
+
+    fn deliver(&self, shares: PointShareVector, corrupt: bool) -> PointShareVector {
+        let G1 = self.setup.G1;
+        PointShareVector(shares.0.into_iter()
+            .filter(|s| self.scenario.delivered(s.i))
+            .map(|s| match self.scenario.fault(s.i) {
+                Some(Fault::Corrupt) if corrupt => s + G1,
+                _ => s
+            }).collect())
+    }
+
+    // NOTE: full client roundtrip, with the robust combination of the request shares
+    fn run(&mut self, seq: usize) -> Outcome {
+        let (st, k, time) = (rnd_scalar(), rnd_scalar(), SystemTime::now());
+        let seq_bytes = seq.to_le_bytes();
+        let time_str = encode_time(time);
+        let sig = ExtSignature::sign(&st, &self.setup.G1.into(), &[PROFILE.as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()]);
+
+        let threshold = self.setup.threshold;
+        let (Mi, PIi) = match self.setup.start(sig, PROFILE, seq, time) {
+            Ok((Mi, PIi)) => (self.deliver(Mi, false), self.deliver(PIi, false)),
+            Err(err) => return Outcome::Failed(err)
+        };
+
+        if Mi.0.len() < threshold + 1 {
+            return Outcome::TimedOut
+        }
+
+        let M = Mi.interpolate();
+        let PI = PIi.interpolate();
+
+        let c = token_challenge(&self.setup.public_params().setup_id, &M.into(), &(M * k).into(), &PI.into());
+        let kc = k * c;
+        let session = SessionId::new(seq, time);
+        let Tki = match self.setup.request(session.as_str(), &(self.setup.A1 * kc).into(), &(self.setup.G1 * kc).into()) {
+            Ok(Tki) => self.deliver(Tki, true),
+            Err(err) => return Outcome::Failed(err)
+        };
+
+        // NOTE: with missing responses, fewer faults can be tolerated
+        let f = self.f.min(Tki.0.len().saturating_sub(threshold + 1) / 2);
+        match self.setup.combine_robust(PROFILE, &kc, &Mi, &PIi, &Tki, f) {
+            Ok((Tk, faulty)) => Outcome::Completed { token: Box::new(Token::new(k, Tk.into(), M.into(), PI.into(), &self.setup.public_params().setup_id)), faulty },
+            Err(ProtocolError::InsufficientShares) => Outcome::TimedOut,
+            Err(err) => Outcome::Failed(err)
+        }
+    }
+}
+
+fn scenario(desc: &str) -> Scenario {
+    Scenario::parse(desc, Duration::from_millis(100))
+}
+
+#[test]
+fn honest() {
+    let mut network = FaultyNetwork::new(2, 1, scenario(""));
+    match network.run(1) {
+        Outcome::Completed { token, faulty } => assert!(faulty.is_empty() && token.verify(network.setup.public_params())),
+        _ => panic!("Protocol did not complete!")
+    }
+}
+
+#[test]
+fn excludes_cheater() {
+    let mut network = FaultyNetwork::new(2, 1, scenario("corrupt:3"));
+    match network.run(1) {
+        Outcome::Completed { token, faulty } => assert!(faulty == vec![3] && token.verify(network.setup.public_params())),
+        _ => panic!("Protocol did not complete!")
+    }
+}
+
+#[test]
+fn tolerates_late_and_dropped() {
+    // 2 of 5 responses missing, the remaining t + 1 are enough without cheaters
+    let mut network = FaultyNetwork::new(2, 1, scenario("drop:1 delay:4:500 delay:5:50"));
+    match network.run(1) {
+        Outcome::Completed { token, faulty } => assert!(faulty.is_empty() && token.verify(network.setup.public_params())),
+        _ => panic!("Protocol did not complete!")
+    }
+}
+
+#[test]
+fn times_out() {
+    let mut network = FaultyNetwork::new(2, 1, scenario("drop:1 drop:2 delay:3:500"));
+    assert!(matches!(network.run(1), Outcome::TimedOut));
+
+    // missing responses leave no margin to exclude a cheater
+    let mut network = FaultyNetwork::new(2, 1, scenario("drop:1 drop:2 corrupt:3"));
+    assert!(matches!(network.run(1), Outcome::TimedOut));
+}
+
+#[test]
+fn protocol_errors() {
+    let mut network = FaultyNetwork::new(2, 1, scenario("corrupt:3"));
+    assert!(matches!(network.run(1), Outcome::Completed { .. }));
+
+    // faults don't mask protocol errors, a replayed sequence is rejected
+    assert!(matches!(network.run(1), Outcome::Failed(ProtocolError::InvalidFreshness)));
+}
@@ -0,0 +1,102 @@
+#![allow(non_snake_case)]
+// NOTE: the vectors are generated with the default SHA-512 hash
+#![cfg(not(feature = "blake2b"))]
+
+// Golden-master vectors for a seeded NetworkSetup and token issuance.
+// M and Tk depend on the start time (through the session id), the run uses a fixed start time and network clock.
+//
+// Regenerate tests/data/vectors.json after an intended change with:
+//   TAT_ADR_REGENERATE=1 cargo test --test vectors
+
+use tat_adr::*;
+
+use std::time::{Duration, SystemTime};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde_json::{json, Value};
+use bls12_381::G1Affine;
+
+const SEED: u64 = 42;
+const THRESHOLD: usize = 2;
+const PROFILE: &str = "EHR";
+const LOCATION: &str = "Hospital";
+const TIME: u64 = 1_600_000_000;
+const PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/vectors.json");
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn generate() -> Value {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    let network = ChaCha20Rng::from_rng(&mut rng).unwrap();
+    let l = rnd_scalar_with(&mut rng);
+    let r = rnd_scalar_with(&mut rng);
+    let st = rnd_scalar_with(&mut rng);
+    let k = rnd_scalar_with(&mut rng);
+    let nonce = rnd_scalar_with(&mut rng).to_bytes();
+
+    let mut setup = NetworkSetup::with_rng(THRESHOLD, network);
+    setup.location(LOCATION, setup.Y * l);
+    setup.profile(PROFILE, LOCATION, setup.G1 * r, setup.A1 * r).unwrap();
+    let public = setup.public();
+
+    // full protocol run
+    let seq = 1usize;
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(TIME);
+    setup.set_clock(Box::new(FixedClock(time)));
+    let seq_bytes = seq.to_le_bytes();
+    let time_str = encode_time(time);
+    let sig = ExtSignature::sign(&st, &setup.G1.into(), &[PROFILE.as_bytes(), seq_bytes.as_ref(), time_str.as_bytes()]);
+
+    let (Mi, PIi) = setup.start(sig, PROFILE, seq, time).unwrap();
+    let M = Mi.interpolate();
+    let PI = PIi.interpolate();
+
+    let c = token_challenge(&setup.public_params().setup_id, &M.into(), &(M * k).into(), &PI.into());
+    let session = SessionId::new(seq, time);
+    let Tki = setup.request(session.as_str(), &(setup.A1 * (k * c)).into(), &(setup.G1 * (k * c)).into()).unwrap();
+    let Tk = Tki.interpolate();
+    let claims = Claims { nonce, issued_at: TIME, ..Claims::default() };
+    let token = Token::with_claims(k, Tk.into(), M.into(), PI.into(), claims, &setup.public_params().setup_id);
+    assert!(token.verify(setup.public_params()));
+
+    // signature over fixed data, covers the challenge and nonce hashing
+    let data: &[&[u8]] = &[b"tat-adr", b"vector"];
+    let sig = ExtSignature::sign(&st, &setup.G1.into(), data);
+
+    json!({
+        "seed": SEED,
+        "threshold": THRESHOLD,
+        "Y": hex(&public.Y.to_compressed()),
+        "A1": hex(&public.A1.to_compressed()),
+        "A2": hex(&public.A2.to_compressed()),
+        "commitments": public.commitments.0.iter().map(|s| hex(&G1Affine::from(s.Yi).to_compressed())).collect::<Vec<_>>(),
+        "PIi": PIi.0.iter().map(|s| hex(&G1Affine::from(s.Yi).to_compressed())).collect::<Vec<_>>(),
+        "PI": hex(&G1Affine::from(PI).to_compressed()),
+        "M": hex(&G1Affine::from(M).to_compressed()),
+        "Tk": hex(&G1Affine::from(Tk).to_compressed()),
+        "token": hex(&token.to_bytes()),
+        "signature": {
+            "P1": hex(&sig.P1.to_compressed()),
+            "c": hex(&sig.sig.c.to_bytes()),
+            "p": hex(&sig.sig.p.to_bytes())
+        }
+    })
+}
+
+#[test]
+fn golden_vectors() {
+    let vectors = generate();
+    if std::env::var("TAT_ADR_REGENERATE").is_ok() {
+        std::fs::write(PATH, serde_json::to_string_pretty(&vectors).unwrap() + "\n").unwrap();
+        return
+    }
+
+    let expected: Value = serde_json::from_str(&std::fs::read_to_string(PATH).unwrap()).unwrap();
+    for (key, value) in expected.as_object().unwrap() {
+        assert!(&vectors[key] == value, "vector mismatch for {}", key);
+    }
+
+    assert!(vectors == expected);
+}